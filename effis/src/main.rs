@@ -6,6 +6,7 @@ extern crate todel;
 mod cors;
 mod rate_limit;
 mod routes;
+mod secret;
 
 #[cfg(test)]
 use std::sync::Once;
@@ -90,6 +91,7 @@ fn rocket() -> Result<Rocket<Build>, anyhow::Error> {
         .manage(conf)
         .attach(DB::init())
         .attach(Cache::init())
+        .attach(secret::SecretFairing)
         .attach(cors::Cors)
         .mount("/", routes::routes())
         .mount("/static/", routes::static_routes()))
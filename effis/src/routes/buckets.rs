@@ -1,7 +1,7 @@
 use rocket::{form::Form, serde::json::Json, State};
 use rocket_db_pools::Connection;
 use todel::{
-    http::ClientIP,
+    http::{ClientIP, TokenAuth},
     ids::IdGenerator,
     models::{ErrorResponse, FetchResponse, File, FileData, FileUpload},
     Conf,
@@ -16,6 +16,9 @@ use crate::{
 /// Upload a file to Effis under a specific bucket.
 /// At the moment, only the attachments bucket is supported.
 ///
+/// Files uploaded with an `Authorization` header are owned by the session's user. Only attachments
+/// owned by a user can be added to their messages.
+///
 /// -----
 ///
 /// ### Example
@@ -24,6 +27,7 @@ use crate::{
 /// curl \
 ///   -F file=@trolley.mp4 \
 ///   -F spoiler=true \
+///   -H "Authorization: <token>" \
 ///   https://cdn.eludris.gay/attachments/
 ///
 /// {
@@ -43,6 +47,7 @@ use crate::{
 pub async fn upload_file<'a>(
     bucket: &'a str,
     upload: Form<FileUpload<'a>>,
+    session: Option<TokenAuth>,
     ip: ClientIP,
     mut cache: Connection<Cache>,
     mut db: Connection<DB>,
@@ -61,6 +66,7 @@ pub async fn upload_file<'a>(
         &mut *gen.inner().lock().await,
         &mut db,
         upload.spoiler,
+        session.map(|s| s.0.user_id),
     )
     .await
     .map_err(|e| rate_limiter.add_headers(e))?;
@@ -1,7 +1,7 @@
 use rocket::{form::Form, serde::json::Json, State};
 use rocket_db_pools::Connection;
 use todel::{
-    http::ClientIP,
+    http::{ClientIP, TokenAuth},
     ids::IdGenerator,
     models::{FetchResponse, File, FileData, FileUpload},
     Conf,
@@ -16,6 +16,9 @@ use crate::{
 /// Upload an attachment to Effis under a specific bucket.
 /// This is a shortcut to [`upload_file`] with the attachments bucket.
 ///
+/// Files uploaded with an `Authorization` header are owned by the session's user. Only attachments
+/// owned by a user can be added to their messages.
+///
 /// -----
 ///
 /// ### Example
@@ -24,6 +27,7 @@ use crate::{
 /// curl \
 ///   -F file=@thang-big.png \
 ///   -F spoiler=false \
+///   -H "Authorization: <token>" \
 ///   https://cdn.eludris.gay/
 ///
 /// {
@@ -41,6 +45,7 @@ use crate::{
 #[post("/", data = "<upload>")]
 pub async fn upload_attachment<'a>(
     upload: Form<FileUpload<'a>>,
+    session: Option<TokenAuth>,
     ip: ClientIP,
     mut cache: Connection<Cache>,
     mut db: Connection<DB>,
@@ -58,6 +63,7 @@ pub async fn upload_attachment<'a>(
         &mut *gen.inner().lock().await,
         &mut db,
         upload.spoiler,
+        session.map(|s| s.0.user_id),
     )
     .await
     .map_err(|e| rate_limiter.add_headers(e))?;
@@ -0,0 +1,47 @@
+use std::time::Duration;
+
+use rocket::{
+    fairing::{Fairing, Info, Kind, Result},
+    Build, Rocket,
+};
+use rocket_db_pools::Database;
+use todel::models::Secret;
+
+use crate::DB;
+
+/// How long to wait between attempts at fetching the instance secret.
+const RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+pub struct SecretFairing;
+
+#[rocket::async_trait]
+impl Fairing for SecretFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Fetch the instance secret",
+            kind: Kind::Ignite,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> Result {
+        if let Some(db) = DB::fetch(&rocket) {
+            // Oprish creates the secret when it first starts, so effis waits for it instead of
+            // having to be started after it.
+            let secret = loop {
+                match Secret::try_get(&db.0).await {
+                    Some(secret) => break secret,
+                    None => {
+                        log::warn!(
+                            "Could not fetch the instance secret, waiting for oprish to create it"
+                        );
+                        rocket::tokio::time::sleep(RETRY_INTERVAL).await;
+                    }
+                }
+            };
+            Ok(rocket.manage(secret))
+        } else {
+            log::error!("Could not obtain the database to fetch the instance secret");
+            Err(rocket)
+        }
+    }
+}
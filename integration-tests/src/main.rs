@@ -22,6 +22,7 @@ use tokio_tungstenite::{
 
 struct State {
     instance_info: InstanceInfo,
+    channel_id: u64,
     rng: Mutex<StdRng>,
 }
 
@@ -35,6 +36,10 @@ async fn main() -> Result<()> {
 
     let state: Arc<State> = Arc::new(State {
         instance_info: (reqwest::get(instance_url).await?.json().await?),
+        channel_id: env::var("CHANNEL_ID")
+            .context("The \"CHANNEL_ID\" environment variable must be set")?
+            .parse()
+            .context("Invalid \"CHANNEL_ID\" environment variable")?,
         rng: Mutex::new(SeedableRng::from_entropy()),
     });
 
@@ -60,10 +65,16 @@ async fn main() -> Result<()> {
                     headers.insert("X-Real-IP", HeaderValue::from_str(&ip)?);
                     let client = Client::builder().default_headers(headers).build()?;
                     client
-                        .post(format!("{}/messages", state.instance_info.oprish_url))
+                        .post(format!(
+                            "{}/channels/{}/messages",
+                            state.instance_info.oprish_url, state.channel_id
+                        ))
                         .json(&MessageCreate {
                             content: format!("Message from client {}", client_id),
                             disguise: None,
+                            attachments: vec![],
+                            reference: None,
+                            mention_reference: true,
                         })
                         .send()
                         .await?;
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS messages (
+  id BIGINT PRIMARY KEY,
+  author_id BIGINT NOT NULL,
+  content TEXT NOT NULL,
+  disguise_name VARCHAR(32),
+  disguise_avatar TEXT,
+  FOREIGN KEY (author_id) REFERENCES users(id) ON DELETE CASCADE ON UPDATE CASCADE
+);
@@ -0,0 +1,9 @@
+ALTER TABLE files ADD COLUMN IF NOT EXISTS owner_id BIGINT;
+ALTER TABLE files ADD CONSTRAINT files_owner_id_fkey FOREIGN KEY (owner_id) REFERENCES users(id) ON DELETE SET NULL ON UPDATE CASCADE;
+
+CREATE TABLE IF NOT EXISTS message_attachments (
+  attachment_id BIGINT PRIMARY KEY,
+  message_id BIGINT NOT NULL,
+  FOREIGN KEY (attachment_id) REFERENCES files(id) ON DELETE CASCADE ON UPDATE CASCADE,
+  FOREIGN KEY (message_id) REFERENCES messages(id) ON DELETE CASCADE ON UPDATE CASCADE
+);
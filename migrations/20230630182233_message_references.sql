@@ -0,0 +1,3 @@
+ALTER TABLE messages ADD COLUMN IF NOT EXISTS reference_id BIGINT;
+ALTER TABLE messages ADD COLUMN IF NOT EXISTS mention_reference BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE messages ADD CONSTRAINT messages_reference_id_fkey FOREIGN KEY (reference_id) REFERENCES messages(id) ON DELETE SET NULL ON UPDATE CASCADE;
@@ -0,0 +1,21 @@
+CREATE TABLE IF NOT EXISTS communities (
+  id BIGINT PRIMARY KEY,
+  owner_id BIGINT NOT NULL,
+  name VARCHAR(32) NOT NULL,
+  description TEXT,
+  icon BIGINT,
+  banner BIGINT,
+  private BOOLEAN NOT NULL DEFAULT FALSE,
+  FOREIGN KEY (owner_id) REFERENCES users(id) ON DELETE CASCADE ON UPDATE CASCADE,
+  FOREIGN KEY (icon) REFERENCES files(id) ON DELETE SET NULL ON UPDATE CASCADE,
+  FOREIGN KEY (banner) REFERENCES files(id) ON DELETE SET NULL ON UPDATE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS community_members (
+  community_id BIGINT NOT NULL,
+  user_id BIGINT NOT NULL,
+  PRIMARY KEY (community_id, user_id),
+  FOREIGN KEY (community_id) REFERENCES communities(id) ON DELETE CASCADE ON UPDATE CASCADE,
+  FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+CREATE INDEX IF NOT EXISTS community_members_user_id_idx ON community_members(user_id);
@@ -0,0 +1,19 @@
+CREATE TYPE channel_type AS ENUM ('TEXT', 'CATEGORY');
+
+CREATE TABLE IF NOT EXISTS channels (
+  id BIGINT PRIMARY KEY,
+  community_id BIGINT NOT NULL,
+  channel_type channel_type NOT NULL DEFAULT 'TEXT',
+  name VARCHAR(32) NOT NULL,
+  topic VARCHAR(1024),
+  position INT NOT NULL DEFAULT 0,
+  parent_id BIGINT,
+  FOREIGN KEY (community_id) REFERENCES communities(id) ON DELETE CASCADE ON UPDATE CASCADE,
+  FOREIGN KEY (parent_id) REFERENCES channels(id) ON DELETE SET NULL ON UPDATE CASCADE
+);
+CREATE INDEX IF NOT EXISTS channels_community_id_idx ON channels(community_id);
+
+-- Messages sent before channels existed don't belong to any channel.
+ALTER TABLE messages ADD COLUMN IF NOT EXISTS channel_id BIGINT;
+ALTER TABLE messages ADD CONSTRAINT messages_channel_id_fkey FOREIGN KEY (channel_id) REFERENCES channels(id) ON DELETE CASCADE ON UPDATE CASCADE;
+CREATE INDEX IF NOT EXISTS messages_channel_id_idx ON messages(channel_id);
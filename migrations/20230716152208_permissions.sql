@@ -0,0 +1,37 @@
+CREATE TABLE IF NOT EXISTS roles (
+  id BIGINT PRIMARY KEY,
+  community_id BIGINT NOT NULL,
+  name VARCHAR(32) NOT NULL,
+  permissions BIGINT NOT NULL DEFAULT 0,
+  position INT NOT NULL DEFAULT 0,
+  FOREIGN KEY (community_id) REFERENCES communities(id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+CREATE INDEX IF NOT EXISTS roles_community_id_idx ON roles(community_id);
+
+-- Every community has a default role which shares its ID, granting VIEW_CHANNEL, SEND_MESSAGES
+-- and ADD_REACTIONS.
+INSERT INTO roles(id, community_id, name, permissions, position)
+SELECT id, id, 'everyone', 896, 0
+FROM communities
+ON CONFLICT DO NOTHING;
+
+CREATE TABLE IF NOT EXISTS member_roles (
+  community_id BIGINT NOT NULL,
+  user_id BIGINT NOT NULL,
+  role_id BIGINT NOT NULL,
+  PRIMARY KEY (community_id, user_id, role_id),
+  FOREIGN KEY (community_id, user_id) REFERENCES community_members(community_id, user_id) ON DELETE CASCADE ON UPDATE CASCADE,
+  FOREIGN KEY (role_id) REFERENCES roles(id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE TYPE overwrite_type AS ENUM ('ROLE', 'MEMBER');
+
+CREATE TABLE IF NOT EXISTS channel_overwrites (
+  channel_id BIGINT NOT NULL,
+  target_id BIGINT NOT NULL,
+  overwrite_type overwrite_type NOT NULL,
+  allow BIGINT NOT NULL DEFAULT 0,
+  deny BIGINT NOT NULL DEFAULT 0,
+  PRIMARY KEY (channel_id, target_id),
+  FOREIGN KEY (channel_id) REFERENCES channels(id) ON DELETE CASCADE ON UPDATE CASCADE
+);
@@ -0,0 +1,17 @@
+ALTER TYPE channel_type ADD VALUE IF NOT EXISTS 'DM';
+ALTER TYPE channel_type ADD VALUE IF NOT EXISTS 'GROUP';
+
+-- Direct channels don't belong to any community and only groups have names.
+ALTER TABLE channels ALTER COLUMN community_id DROP NOT NULL;
+ALTER TABLE channels ALTER COLUMN name DROP NOT NULL;
+ALTER TABLE channels ADD COLUMN IF NOT EXISTS owner_id BIGINT;
+ALTER TABLE channels ADD CONSTRAINT channels_owner_id_fkey FOREIGN KEY (owner_id) REFERENCES users(id) ON DELETE SET NULL ON UPDATE CASCADE;
+
+CREATE TABLE IF NOT EXISTS channel_recipients (
+  channel_id BIGINT NOT NULL,
+  user_id BIGINT NOT NULL,
+  PRIMARY KEY (channel_id, user_id),
+  FOREIGN KEY (channel_id) REFERENCES channels(id) ON DELETE CASCADE ON UPDATE CASCADE,
+  FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+CREATE INDEX IF NOT EXISTS channel_recipients_user_id_idx ON channel_recipients(user_id);
@@ -0,0 +1,13 @@
+CREATE TYPE relationship_type AS ENUM ('FRIEND', 'INCOMING', 'OUTGOING', 'BLOCKED');
+
+-- Relationships are stored from the point of view of both users, a pending friend request is an
+-- `OUTGOING` row for its sender and an `INCOMING` row for its receiver while blocks are one-sided.
+CREATE TABLE IF NOT EXISTS relationships (
+  user_id BIGINT NOT NULL,
+  target_id BIGINT NOT NULL,
+  relationship_type relationship_type NOT NULL,
+  PRIMARY KEY (user_id, target_id),
+  FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE ON UPDATE CASCADE,
+  FOREIGN KEY (target_id) REFERENCES users(id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+CREATE INDEX IF NOT EXISTS relationships_target_id_idx ON relationships(target_id);
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS follows (
+  follower_id BIGINT NOT NULL,
+  user_id BIGINT NOT NULL,
+  PRIMARY KEY (follower_id, user_id),
+  FOREIGN KEY (follower_id) REFERENCES users(id) ON DELETE CASCADE ON UPDATE CASCADE,
+  FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+CREATE INDEX IF NOT EXISTS follows_user_id_idx ON follows(user_id);
@@ -0,0 +1,39 @@
+CREATE TABLE IF NOT EXISTS posts (
+  id BIGINT PRIMARY KEY,
+  author_id BIGINT NOT NULL,
+  topic VARCHAR(32) NOT NULL,
+  title VARCHAR(256) NOT NULL,
+  content TEXT,
+  score INT NOT NULL DEFAULT 0,
+  -- Reddit's hot ranking, the score counts logarithmically while newer posts get a linear bonus
+  -- based on the timestamp embedded in their ID.
+  hot DOUBLE PRECISION GENERATED ALWAYS AS (
+    SIGN(score)::DOUBLE PRECISION * LOG(GREATEST(ABS(score), 1)::DOUBLE PRECISION)
+    + (id >> 16)::DOUBLE PRECISION / 45000
+  ) STORED,
+  FOREIGN KEY (author_id) REFERENCES users(id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+CREATE INDEX IF NOT EXISTS posts_topic_idx ON posts(topic);
+CREATE INDEX IF NOT EXISTS posts_hot_idx ON posts(hot, id);
+CREATE INDEX IF NOT EXISTS posts_score_idx ON posts(score, id);
+
+CREATE TABLE IF NOT EXISTS post_votes (
+  post_id BIGINT NOT NULL,
+  user_id BIGINT NOT NULL,
+  vote SMALLINT NOT NULL CHECK (vote IN (-1, 1)),
+  PRIMARY KEY (post_id, user_id),
+  FOREIGN KEY (post_id) REFERENCES posts(id) ON DELETE CASCADE ON UPDATE CASCADE,
+  FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS comments (
+  id BIGINT PRIMARY KEY,
+  post_id BIGINT NOT NULL,
+  parent_id BIGINT,
+  author_id BIGINT NOT NULL,
+  content TEXT NOT NULL,
+  FOREIGN KEY (post_id) REFERENCES posts(id) ON DELETE CASCADE ON UPDATE CASCADE,
+  FOREIGN KEY (parent_id) REFERENCES comments(id) ON DELETE CASCADE ON UPDATE CASCADE,
+  FOREIGN KEY (author_id) REFERENCES users(id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+CREATE INDEX IF NOT EXISTS comments_post_id_idx ON comments(post_id);
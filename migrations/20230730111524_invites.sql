@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS invites (
+  code VARCHAR(16) PRIMARY KEY,
+  creator_id BIGINT NOT NULL,
+  uses INT NOT NULL DEFAULT 0,
+  max_uses INT,
+  expires_at BIGINT,
+  FOREIGN KEY (creator_id) REFERENCES users(id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS invites_creator_id_idx ON invites(creator_id);
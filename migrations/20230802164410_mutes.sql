@@ -0,0 +1,13 @@
+ALTER TABLE channels ADD COLUMN IF NOT EXISTS slow_mode INT NOT NULL DEFAULT 0;
+
+CREATE TABLE IF NOT EXISTS mutes (
+  community_id BIGINT NOT NULL,
+  user_id BIGINT NOT NULL,
+  moderator_id BIGINT NOT NULL,
+  reason VARCHAR(512),
+  expires_at BIGINT NOT NULL,
+  PRIMARY KEY (community_id, user_id),
+  FOREIGN KEY (community_id) REFERENCES communities(id) ON DELETE CASCADE ON UPDATE CASCADE,
+  FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE ON UPDATE CASCADE,
+  FOREIGN KEY (moderator_id) REFERENCES users(id) ON DELETE CASCADE ON UPDATE CASCADE
+);
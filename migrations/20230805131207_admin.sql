@@ -0,0 +1,13 @@
+ALTER TABLE users ADD COLUMN IF NOT EXISTS suspended_until BIGINT;
+
+CREATE TYPE audit_log_action AS ENUM ('SUSPEND_USER', 'UNSUSPEND_USER', 'VERIFY_USER', 'REVOKE_SESSIONS', 'RESET_USERNAME');
+
+-- Entries deliberately don't reference users so they outlive the accounts they're about.
+CREATE TABLE IF NOT EXISTS audit_log (
+  id BIGINT PRIMARY KEY,
+  admin_id BIGINT NOT NULL,
+  action audit_log_action NOT NULL,
+  target_id BIGINT NOT NULL,
+  reason VARCHAR(512),
+  details VARCHAR(256)
+);
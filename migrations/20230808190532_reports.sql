@@ -0,0 +1,19 @@
+CREATE TYPE report_target_type AS ENUM ('USER', 'MESSAGE', 'FILE');
+CREATE TYPE report_category AS ENUM ('SPAM', 'HARASSMENT', 'HATE_SPEECH', 'NSFW', 'ILLEGAL_CONTENT', 'OTHER');
+CREATE TYPE report_status AS ENUM ('OPEN', 'ACTIONED', 'DISMISSED');
+
+-- Reports don't reference their target so they're kept around after it gets deleted.
+CREATE TABLE IF NOT EXISTS reports (
+  id BIGINT PRIMARY KEY,
+  reporter_id BIGINT NOT NULL,
+  target_type report_target_type NOT NULL,
+  target_id BIGINT NOT NULL,
+  category report_category NOT NULL,
+  details VARCHAR(2000),
+  status report_status NOT NULL DEFAULT 'OPEN',
+  resolved_by BIGINT,
+  FOREIGN KEY (reporter_id) REFERENCES users(id) ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS reports_status_idx ON reports(status);
+CREATE UNIQUE INDEX IF NOT EXISTS reports_open_target_idx ON reports(reporter_id, target_type, target_id) WHERE status = 'OPEN';
@@ -0,0 +1,9 @@
+-- The last TOTP time step a user authenticated with, used to stop codes from being reused.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS two_factor_last_step BIGINT;
+
+CREATE TABLE IF NOT EXISTS recovery_codes (
+  user_id BIGINT NOT NULL,
+  code_hash VARCHAR(64) NOT NULL,
+  PRIMARY KEY (user_id, code_hash),
+  FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE ON UPDATE CASCADE
+);
@@ -0,0 +1,10 @@
+ALTER TABLE sessions ADD COLUMN IF NOT EXISTS created_at BIGINT;
+ALTER TABLE sessions ADD COLUMN IF NOT EXISTS last_used_at BIGINT;
+
+-- Existing sessions get their creation time from their ID.
+UPDATE sessions
+SET created_at = (id >> 16) + 1650000000, last_used_at = (id >> 16) + 1650000000
+WHERE created_at IS NULL;
+
+ALTER TABLE sessions ALTER COLUMN created_at SET NOT NULL;
+ALTER TABLE sessions ALTER COLUMN last_used_at SET NOT NULL;
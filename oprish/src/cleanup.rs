@@ -4,7 +4,11 @@ use rocket::{
     Build, Rocket,
 };
 use rocket_db_pools::Database;
-use todel::{http::DB, models::User};
+use todel::{
+    http::DB,
+    models::{Session, User},
+    Conf,
+};
 use tokio::time::sleep;
 
 pub struct ScheduledCleanup;
@@ -25,6 +29,10 @@ impl Fairing for ScheduledCleanup {
                 .await
                 .expect("Failed to acquire database connection")
         };
+        let conf = rocket
+            .state::<Conf>()
+            .expect("Could not obtain the managed Conf")
+            .clone();
         tokio::spawn(async move {
             let now = Utc::now().naive_utc();
             let midnight = (now + Duration::days(1))
@@ -41,6 +49,9 @@ impl Fairing for ScheduledCleanup {
                 if let Err(err) = User::clean_up_unverified(&mut db).await {
                     log::error!("Couldn't clean up unverified users: {}", err);
                 }
+                if let Err(err) = Session::clean_up_expired(&conf, &mut db).await {
+                    log::error!("Couldn't clean up expired sessions: {}", err);
+                }
                 sleep(
                     Duration::days(1)
                         .to_std()
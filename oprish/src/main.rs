@@ -9,6 +9,8 @@ mod database;
 mod email;
 mod rate_limit;
 mod routes;
+#[cfg(test)]
+mod test_utils;
 
 use std::env;
 #[cfg(test)]
@@ -90,7 +92,15 @@ fn rocket() -> Result<Rocket<Build>, anyhow::Error> {
         .mount("/", get_routes())
         .mount("/messages", messages::get_routes())
         .mount("/users", users::get_routes())
-        .mount("/sessions", sessions::get_routes()))
+        .mount("/sessions", sessions::get_routes())
+        .mount("/communities", communities::get_routes())
+        .mount("/channels", channels::get_routes())
+        .mount("/dms", dms::get_routes())
+        .mount("/invites", invites::get_routes())
+        .mount("/relationships", relationships::get_routes())
+        .mount("/posts", posts::get_routes())
+        .mount("/admin", admin::get_routes())
+        .mount("/reports", reports::get_routes()))
 }
 
 #[rocket::main]
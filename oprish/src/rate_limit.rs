@@ -56,6 +56,12 @@ impl RateLimiter {
             conf,
             get_instance_info,
             create_message,
+            get_messages,
+            get_mentions,
+            edit_message,
+            delete_message,
+            add_reaction,
+            remove_reaction,
             create_user,
             verify_user,
             get_user,
@@ -65,9 +71,52 @@ impl RateLimiter {
             delete_user,
             create_password_reset_code,
             reset_password,
+            two_factor,
             create_session,
             get_sessions,
             delete_session,
+            delete_other_sessions,
+            create_community,
+            get_community,
+            update_community,
+            delete_community,
+            join_community,
+            leave_community,
+            create_channel,
+            get_channel,
+            update_channel,
+            delete_channel,
+            create_role,
+            get_roles,
+            update_role,
+            delete_role,
+            edit_member_roles,
+            edit_overwrites,
+            update_slow_mode,
+            mute_member,
+            get_mutes,
+            moderate_user,
+            get_audit_log,
+            create_report,
+            get_reports,
+            resolve_report,
+            open_dm,
+            create_group,
+            get_dms,
+            edit_recipients,
+            get_relationships,
+            edit_relationships,
+            follow,
+            get_follows,
+            create_post,
+            get_posts,
+            delete_post,
+            vote,
+            create_comment,
+            delete_comment,
+            create_invite,
+            get_invites,
+            delete_invite,
         );
         RateLimiter {
             key: format!("rate_limit:{}:{}", identifier, bucket),
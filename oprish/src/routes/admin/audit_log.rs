@@ -0,0 +1,60 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::AuditLogEntry,
+    Conf,
+};
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Get a page of the instance's audit log, newest entries first.
+///
+/// `before` is the ID of the last entry of the previous page. `limit` defaults to 50 and can be
+/// at most 100. This requires the `MANAGE_USERS` instance-wide permission.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -H "Authorization: <token>" \
+///   "https://api.eludris.gay/admin/audit-log?limit=2"
+///
+/// [
+///   {
+///     "id": 2391360208897,
+///     "admin_id": 48615849987333,
+///     "action": "REVOKE_SESSIONS",
+///     "target_id": 48615849987334,
+///     "reason": "Account compromised",
+///     "details": "Revoked 3 sessions"
+///   },
+///   {
+///     "id": 2391356145665,
+///     "admin_id": 48615849987333,
+///     "action": "VERIFY_USER",
+///     "target_id": 48615849987334
+///   }
+/// ]
+/// ```
+#[autodoc("/admin", category = "Admin")]
+#[get("/audit-log?<before>&<limit>")]
+pub async fn get_audit_log(
+    before: Option<u64>,
+    limit: Option<u32>,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<Vec<AuditLogEntry>>> {
+    let mut rate_limiter = RateLimiter::new("get_audit_log", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    rate_limiter.wrap_response(Json(
+        AuditLogEntry::get_page(before, limit, session.0.user_id, &mut db)
+            .await
+            .map_err(|err| rate_limiter.add_headers(err))?,
+    ))
+}
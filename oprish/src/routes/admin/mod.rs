@@ -0,0 +1,19 @@
+mod audit_log;
+mod reset_username;
+mod revoke_sessions;
+mod suspend;
+mod unsuspend;
+mod verify;
+
+use rocket::Route;
+
+pub fn get_routes() -> Vec<Route> {
+    routes![
+        suspend::suspend_user,
+        unsuspend::unsuspend_user,
+        verify::force_verify_user,
+        revoke_sessions::revoke_user_sessions,
+        reset_username::reset_username,
+        audit_log::get_audit_log,
+    ]
+}
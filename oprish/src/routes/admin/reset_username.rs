@@ -0,0 +1,68 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    ids::IdGenerator,
+    models::{AdminAction, AuditLogEntry, Event, EventTarget, ServerPayload, User},
+    Conf,
+};
+use tokio::sync::Mutex;
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Reset a user's username to `user-<id>`.
+///
+/// This requires the `MANAGE_USERS` instance-wide permission.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -H "Authorization: <token>" \
+///   --json '{"reason":"Impersonating another user"}' \
+///   https://api.eludris.gay/admin/users/48615849987334/reset-username
+///
+/// {
+///   "id": 2391348412417,
+///   "admin_id": 48615849987333,
+///   "action": "RESET_USERNAME",
+///   "target_id": 48615849987334,
+///   "reason": "Impersonating another user",
+///   "details": "Previous username: yendri"
+/// }
+/// ```
+#[autodoc("/admin", category = "Admin")]
+#[post("/users/<user_id>/reset-username", data = "<action>")]
+pub async fn reset_username(
+    user_id: u64,
+    action: Option<Json<AdminAction>>,
+    id_generator: &State<Mutex<IdGenerator>>,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<AuditLogEntry>> {
+    let mut rate_limiter = RateLimiter::new("moderate_user", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    let (user, entry) = User::reset_username(
+        user_id,
+        action.map(|a| a.into_inner()).unwrap_or_default(),
+        session.0.user_id,
+        &mut *id_generator.lock().await,
+        &mut db,
+        &mut *cache,
+    )
+    .await
+    .map_err(|err| rate_limiter.add_headers(err))?;
+    Event::new(
+        ServerPayload::UserUpdate(user),
+        vec![EventTarget::User(user_id), EventTarget::Presence(user_id)],
+    )
+    .publish(&mut *cache)
+    .await
+    .unwrap();
+    rate_limiter.wrap_response(Json(entry))
+}
@@ -0,0 +1,62 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    ids::IdGenerator,
+    models::{AdminAction, AuditLogEntry, Session},
+    Conf,
+};
+use tokio::sync::Mutex;
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Revoke all of a user's sessions, logging them out everywhere.
+///
+/// This requires the `MANAGE_USERS` instance-wide permission.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -H "Authorization: <token>" \
+///   --json '{"reason":"Account compromised"}' \
+///   https://api.eludris.gay/admin/users/48615849987334/revoke-sessions
+///
+/// {
+///   "id": 2391360208897,
+///   "admin_id": 48615849987333,
+///   "action": "REVOKE_SESSIONS",
+///   "target_id": 48615849987334,
+///   "reason": "Account compromised",
+///   "details": "Revoked 3 sessions"
+/// }
+/// ```
+#[autodoc("/admin", category = "Admin")]
+#[post("/users/<user_id>/revoke-sessions", data = "<action>")]
+pub async fn revoke_user_sessions(
+    user_id: u64,
+    action: Option<Json<AdminAction>>,
+    id_generator: &State<Mutex<IdGenerator>>,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<AuditLogEntry>> {
+    let mut rate_limiter = RateLimiter::new("moderate_user", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    rate_limiter.wrap_response(Json(
+        Session::revoke_all(
+            user_id,
+            action.map(|a| a.into_inner()).unwrap_or_default(),
+            session.0.user_id,
+            &mut *id_generator.lock().await,
+            &mut db,
+            &mut *cache,
+        )
+        .await
+        .map_err(|err| rate_limiter.add_headers(err))?,
+    ))
+}
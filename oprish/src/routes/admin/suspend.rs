@@ -0,0 +1,63 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    ids::IdGenerator,
+    models::{AuditLogEntry, User, UserSuspension},
+    Conf,
+};
+use tokio::sync::Mutex;
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Suspend a user, preventing them from logging in or using their existing sessions until their
+/// suspension ends.
+///
+/// This requires the `MANAGE_USERS` instance-wide permission. Administrators can't be suspended.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -H "Authorization: <token>" \
+///   --json '{"duration":604800,"reason":"Repeatedly spamming communities"}' \
+///   https://api.eludris.gay/admin/users/48615849987334/suspend
+///
+/// {
+///   "id": 2391348412417,
+///   "admin_id": 48615849987333,
+///   "action": "SUSPEND_USER",
+///   "target_id": 48615849987334,
+///   "reason": "Repeatedly spamming communities",
+///   "details": "Suspended until 1691920000"
+/// }
+/// ```
+#[autodoc("/admin", category = "Admin")]
+#[post("/users/<user_id>/suspend", data = "<suspension>")]
+pub async fn suspend_user(
+    user_id: u64,
+    suspension: Json<UserSuspension>,
+    id_generator: &State<Mutex<IdGenerator>>,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<AuditLogEntry>> {
+    let mut rate_limiter = RateLimiter::new("moderate_user", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    rate_limiter.wrap_response(Json(
+        User::suspend(
+            user_id,
+            suspension.into_inner(),
+            session.0.user_id,
+            &mut *id_generator.lock().await,
+            &mut db,
+            &mut *cache,
+        )
+        .await
+        .map_err(|err| rate_limiter.add_headers(err))?,
+    ))
+}
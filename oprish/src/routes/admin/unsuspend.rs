@@ -0,0 +1,60 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    ids::IdGenerator,
+    models::{AdminAction, AuditLogEntry, User},
+    Conf,
+};
+use tokio::sync::Mutex;
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Lift a user's suspension before it ends.
+///
+/// This requires the `MANAGE_USERS` instance-wide permission.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -H "Authorization: <token>" \
+///   --json '{"reason":"Appealed"}' \
+///   https://api.eludris.gay/admin/users/48615849987334/unsuspend
+///
+/// {
+///   "id": 2391352737793,
+///   "admin_id": 48615849987333,
+///   "action": "UNSUSPEND_USER",
+///   "target_id": 48615849987334,
+///   "reason": "Appealed"
+/// }
+/// ```
+#[autodoc("/admin", category = "Admin")]
+#[post("/users/<user_id>/unsuspend", data = "<action>")]
+pub async fn unsuspend_user(
+    user_id: u64,
+    action: Option<Json<AdminAction>>,
+    id_generator: &State<Mutex<IdGenerator>>,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<AuditLogEntry>> {
+    let mut rate_limiter = RateLimiter::new("moderate_user", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    rate_limiter.wrap_response(Json(
+        User::unsuspend(
+            user_id,
+            action.map(|a| a.into_inner()).unwrap_or_default(),
+            session.0.user_id,
+            &mut *id_generator.lock().await,
+            &mut db,
+        )
+        .await
+        .map_err(|err| rate_limiter.add_headers(err))?,
+    ))
+}
@@ -0,0 +1,60 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    ids::IdGenerator,
+    models::{AdminAction, AuditLogEntry, User},
+    Conf,
+};
+use tokio::sync::Mutex;
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Verify a user without them having to verify their email.
+///
+/// This requires the `MANAGE_USERS` instance-wide permission.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -X POST \
+///   -H "Authorization: <token>" \
+///   https://api.eludris.gay/admin/users/48615849987334/verify
+///
+/// {
+///   "id": 2391356145665,
+///   "admin_id": 48615849987333,
+///   "action": "VERIFY_USER",
+///   "target_id": 48615849987334
+/// }
+/// ```
+#[autodoc("/admin", category = "Admin")]
+#[post("/users/<user_id>/verify", data = "<action>")]
+pub async fn force_verify_user(
+    user_id: u64,
+    action: Option<Json<AdminAction>>,
+    id_generator: &State<Mutex<IdGenerator>>,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<AuditLogEntry>> {
+    let mut rate_limiter = RateLimiter::new("moderate_user", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    rate_limiter.wrap_response(Json(
+        User::force_verify(
+            user_id,
+            action.map(|a| a.into_inner()).unwrap_or_default(),
+            session.0.user_id,
+            &mut *id_generator.lock().await,
+            &mut db,
+            &mut cache.into_inner(),
+        )
+        .await
+        .map_err(|err| rate_limiter.add_headers(err))?,
+    ))
+}
@@ -0,0 +1,96 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, ClientIP, TokenAuth, DB},
+    ids::IdGenerator,
+    models::{Event, EventTarget, Message, MessageCreate, Permissions, ServerPayload},
+    Conf,
+};
+use tokio::sync::Mutex;
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Post a message to a text channel. Requires the `SEND_MESSAGES` permission.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -H "Authorization: <token>" \
+///   --json '{"content":"Hello, World!"}' \
+///   https://api.eludris.gay/channels/2385062735873/messages
+///
+/// {
+///   "id": 2373120361473,
+///   "channel_id": 2385062735873,
+///   "author": {
+///     "id": 48615849987333,
+///     "username": "yendri",
+///     "social_credit": 0,
+///     "badges": 0,
+///     "permissions": 0
+///   },
+///   "content": "Hello, World!"
+/// }
+/// ```
+#[autodoc("/channels", category = "Messaging")]
+#[post("/<channel_id>/messages", data = "<message>")]
+pub async fn create_message(
+    channel_id: u64,
+    message: Json<MessageCreate>,
+    id_generator: &State<Mutex<IdGenerator>>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    conf: &State<Conf>,
+    session: TokenAuth,
+    ip: ClientIP,
+) -> RateLimitedRouteResponse<Json<Message>> {
+    let mut rate_limiter = RateLimiter::new("create_message", ip, conf.inner());
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    Permissions::require_channel(
+        channel_id,
+        session.0.user_id,
+        Permissions::SEND_MESSAGES,
+        &mut db,
+    )
+    .await
+    .map_err(|err| rate_limiter.add_headers(err))?;
+    let event = Event::new(
+        ServerPayload::MessageCreate(
+            Message::create(
+                message.into_inner(),
+                channel_id,
+                session.0.user_id,
+                conf,
+                &mut *id_generator.lock().await,
+                &mut db,
+                &mut *cache,
+            )
+            .await
+            .map_err(|err| rate_limiter.add_headers(err))?,
+        ),
+        vec![EventTarget::Channel(channel_id)],
+    );
+    event.publish(&mut *cache).await.unwrap();
+    if let ServerPayload::MessageCreate(message) = event.payload {
+        if !message.mentions.is_empty() {
+            Event::new(
+                ServerPayload::MentionCreate(message.clone()),
+                message
+                    .mentions
+                    .iter()
+                    .map(|id| EventTarget::User(*id))
+                    .collect(),
+            )
+            .publish(&mut *cache)
+            .await
+            .unwrap();
+        }
+        rate_limiter.wrap_response(Json(message))
+    } else {
+        unreachable!()
+    }
+}
@@ -0,0 +1,59 @@
+use rocket::{http::Status, response::status::Custom, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::{Channel, Event, EventTarget, Permissions, ServerPayload},
+    Conf,
+};
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Delete a channel along with all of its messages. Requires the `MANAGE_CHANNELS` permission.
+///
+/// Deleting a category moves the channels inside of it out of it.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -X DELETE \
+///   -H "Authorization: <token>" \
+///   https://api.eludris.gay/channels/2385062735873
+/// ```
+#[autodoc("/channels", category = "Channels")]
+#[delete("/<channel_id>")]
+pub async fn delete_channel(
+    channel_id: u64,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Custom<()>> {
+    let mut rate_limiter = RateLimiter::new("delete_channel", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    Permissions::require_channel(
+        channel_id,
+        session.0.user_id,
+        Permissions::MANAGE_CHANNELS,
+        &mut db,
+    )
+    .await
+    .map_err(|err| rate_limiter.add_headers(err))?;
+    let channel = Channel::delete(channel_id, session.0.user_id, &mut db)
+        .await
+        .map_err(|err| rate_limiter.add_headers(err))?;
+    Event::new(
+        ServerPayload::ChannelDelete {
+            id: channel.id,
+            community_id: channel.community_id,
+        },
+        vec![EventTarget::Community(channel.community_id)],
+    )
+    .publish(&mut *cache)
+    .await
+    .unwrap();
+    rate_limiter.wrap_response(Custom(Status::NoContent, ()))
+}
@@ -0,0 +1,64 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::{Channel, Event, EventTarget, Permissions, ServerPayload},
+    Conf,
+};
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Remove a channel's permission overwrite for a role or member. Requires the `MANAGE_CHANNELS`
+/// permission.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -X DELETE \
+///   -H "Authorization: <token>" \
+///   https://api.eludris.gay/channels/2385062735873/overwrites/2384913657857
+///
+/// {
+///   "id": 2385062735873,
+///   "community_id": 2384913657857,
+///   "type": "TEXT",
+///   "name": "announcements",
+///   "position": 0
+/// }
+/// ```
+#[autodoc("/channels", category = "Channels")]
+#[delete("/<channel_id>/overwrites/<target_id>")]
+pub async fn delete_overwrite(
+    channel_id: u64,
+    target_id: u64,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<Channel>> {
+    let mut rate_limiter = RateLimiter::new("edit_overwrites", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    Permissions::require_channel(
+        channel_id,
+        session.0.user_id,
+        Permissions::MANAGE_CHANNELS,
+        &mut db,
+    )
+    .await
+    .map_err(|err| rate_limiter.add_headers(err))?;
+    let channel = Channel::delete_overwrite(channel_id, target_id, session.0.user_id, &mut db)
+        .await
+        .map_err(|err| rate_limiter.add_headers(err))?;
+    Event::new(
+        ServerPayload::ChannelUpdate(channel.clone()),
+        vec![EventTarget::Community(channel.community_id)],
+    )
+    .publish(&mut *cache)
+    .await
+    .unwrap();
+    rate_limiter.wrap_response(Json(channel))
+}
@@ -0,0 +1,50 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::Channel,
+    Conf,
+};
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Get a channel by ID.
+///
+/// Channels can only be fetched by the members of their community.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -H "Authorization: <token>" \
+///   https://api.eludris.gay/channels/2385062735873
+///
+/// {
+///   "id": 2385062735873,
+///   "community_id": 2384913657857,
+///   "type": "TEXT",
+///   "name": "general",
+///   "topic": "Talk about anything lanternfly related.",
+///   "position": 0
+/// }
+/// ```
+#[autodoc("/channels", category = "Channels")]
+#[get("/<channel_id>")]
+pub async fn get_channel(
+    channel_id: u64,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<Channel>> {
+    let mut rate_limiter = RateLimiter::new("get_channel", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    rate_limiter.wrap_response(Json(
+        Channel::get(channel_id, session.0.user_id, &mut db)
+            .await
+            .map_err(|err| rate_limiter.add_headers(err))?,
+    ))
+}
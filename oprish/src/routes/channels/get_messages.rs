@@ -0,0 +1,71 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::Message,
+    Conf,
+};
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Get a text channel's message history.
+///
+/// Messages are returned from newest to oldest. `before` and `after` are message IDs which
+/// bound the returned page, passing only `after` returns the messages directly following it,
+/// which is useful for backfilling after reconnecting to Pandemonium.
+///
+/// `limit` defaults to 50 and can be at most 200.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -H "Authorization: <token>" \
+///   "https://api.eludris.gay/channels/2385062735873/messages?before=2373120361473&limit=1"
+///
+/// [
+///   {
+///     "id": 2373119254529,
+///     "channel_id": 2385062735873,
+///     "author": {
+///       "id": 48615849987333,
+///       "username": "yendri",
+///       "social_credit": 0,
+///       "badges": 0,
+///       "permissions": 0
+///     },
+///     "content": "Hello, World!"
+///   }
+/// ]
+/// ```
+#[autodoc("/channels", category = "Messaging")]
+#[get("/<channel_id>/messages?<before>&<after>&<limit>")]
+pub async fn get_messages(
+    channel_id: u64,
+    before: Option<u64>,
+    after: Option<u64>,
+    limit: Option<u32>,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<Vec<Message>>> {
+    let mut rate_limiter = RateLimiter::new("get_messages", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    rate_limiter.wrap_response(Json(
+        Message::get_history(
+            Some(channel_id),
+            session.0.user_id,
+            before,
+            after,
+            limit,
+            &mut db,
+            &mut cache.into_inner(),
+        )
+        .await
+        .map_err(|err| rate_limiter.add_headers(err))?,
+    ))
+}
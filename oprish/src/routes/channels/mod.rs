@@ -0,0 +1,23 @@
+mod create_message;
+mod delete;
+mod delete_overwrite;
+mod get;
+mod get_messages;
+mod set_overwrite;
+mod update;
+mod update_slow_mode;
+
+use rocket::Route;
+
+pub fn get_routes() -> Vec<Route> {
+    routes![
+        get::get_channel,
+        update::update_channel,
+        delete::delete_channel,
+        create_message::create_message,
+        get_messages::get_messages,
+        set_overwrite::set_overwrite,
+        delete_overwrite::delete_overwrite,
+        update_slow_mode::update_slow_mode,
+    ]
+}
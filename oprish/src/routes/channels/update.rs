@@ -0,0 +1,65 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::{Channel, Event, EventTarget, Permissions, ServerPayload, UpdateChannel},
+    Conf,
+};
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Modify a channel. Requires the `MANAGE_CHANNELS` permission.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -H "Authorization: <token>" \
+///   -X PATCH \
+///   --json '{"topic":"Lanternfly sightings only.","parent_id":null}' \
+///   https://api.eludris.gay/channels/2385062735873
+///
+/// {
+///   "id": 2385062735873,
+///   "community_id": 2384913657857,
+///   "type": "TEXT",
+///   "name": "general",
+///   "topic": "Lanternfly sightings only.",
+///   "position": 0
+/// }
+/// ```
+#[autodoc("/channels", category = "Channels")]
+#[patch("/<channel_id>", data = "<update>")]
+pub async fn update_channel(
+    channel_id: u64,
+    update: Json<UpdateChannel>,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<Channel>> {
+    let mut rate_limiter = RateLimiter::new("update_channel", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    Permissions::require_channel(
+        channel_id,
+        session.0.user_id,
+        Permissions::MANAGE_CHANNELS,
+        &mut db,
+    )
+    .await
+    .map_err(|err| rate_limiter.add_headers(err))?;
+    let channel = Channel::update(channel_id, session.0.user_id, update.into_inner(), &mut db)
+        .await
+        .map_err(|err| rate_limiter.add_headers(err))?;
+    Event::new(
+        ServerPayload::ChannelUpdate(channel.clone()),
+        vec![EventTarget::Community(channel.community_id)],
+    )
+    .publish(&mut *cache)
+    .await
+    .unwrap();
+    rate_limiter.wrap_response(Json(channel))
+}
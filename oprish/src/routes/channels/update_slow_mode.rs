@@ -0,0 +1,73 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::{Channel, Event, EventTarget, Permissions, ServerPayload, UpdateSlowMode},
+    Conf,
+};
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Change how long members have to wait between sending messages in a text channel.
+///
+/// This requires the `MODERATE_MEMBERS` permission. Members with the `MANAGE_MESSAGES` or
+/// `MODERATE_MEMBERS` permissions aren't affected by slow mode.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -X PUT \
+///   -H "Authorization: <token>" \
+///   --json '{"interval":10}' \
+///   https://api.eludris.gay/channels/2385062735873/slow-mode
+///
+/// {
+///   "id": 2385062735873,
+///   "community_id": 2384913657857,
+///   "type": "TEXT",
+///   "name": "general",
+///   "position": 0,
+///   "slow_mode": 10
+/// }
+/// ```
+#[autodoc("/channels", category = "Channels")]
+#[put("/<channel_id>/slow-mode", data = "<update>")]
+pub async fn update_slow_mode(
+    channel_id: u64,
+    update: Json<UpdateSlowMode>,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<Channel>> {
+    let mut rate_limiter = RateLimiter::new("update_slow_mode", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    Permissions::require_channel(
+        channel_id,
+        session.0.user_id,
+        Permissions::MODERATE_MEMBERS,
+        &mut db,
+    )
+    .await
+    .map_err(|err| rate_limiter.add_headers(err))?;
+    let channel =
+        Channel::update_slow_mode(channel_id, session.0.user_id, update.into_inner(), &mut db)
+            .await
+            .map_err(|err| rate_limiter.add_headers(err))?;
+    Event::new(
+        ServerPayload::ChannelSlowModeUpdate {
+            channel_id,
+            community_id: channel.community_id,
+            interval: channel.slow_mode,
+        },
+        vec![EventTarget::Channel(channel_id)],
+    )
+    .publish(&mut *cache)
+    .await
+    .unwrap();
+    rate_limiter.wrap_response(Json(channel))
+}
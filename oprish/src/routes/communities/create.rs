@@ -0,0 +1,56 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    ids::IdGenerator,
+    models::{Community, CommunityCreate},
+    Conf,
+};
+use tokio::sync::Mutex;
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Create a community. You automatically become its owner and first member.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -H "Authorization: <token>" \
+///   --json '{"name":"Spotted Lanternfly Hunters","description":"Stomping them one at a time."}' \
+///   https://api.eludris.gay/communities
+///
+/// {
+///   "id": 2384913657857,
+///   "owner_id": 48615849987333,
+///   "name": "Spotted Lanternfly Hunters",
+///   "description": "Stomping them one at a time.",
+///   "private": false
+/// }
+/// ```
+#[autodoc("/communities", category = "Communities")]
+#[post("/", data = "<community>")]
+pub async fn create_community(
+    community: Json<CommunityCreate>,
+    id_generator: &State<Mutex<IdGenerator>>,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<Community>> {
+    let mut rate_limiter = RateLimiter::new("create_community", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    rate_limiter.wrap_response(Json(
+        Community::create(
+            community.into_inner(),
+            session.0.user_id,
+            &mut *id_generator.lock().await,
+            &mut db,
+        )
+        .await
+        .map_err(|err| rate_limiter.add_headers(err))?,
+    ))
+}
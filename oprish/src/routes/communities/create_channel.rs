@@ -0,0 +1,74 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    ids::IdGenerator,
+    models::{Channel, ChannelCreate, Event, EventTarget, Permissions, ServerPayload},
+    Conf,
+};
+use tokio::sync::Mutex;
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Create a channel in a community. Requires the `MANAGE_CHANNELS` permission.
+///
+/// Channels are put at the end of their community or category unless a `position` is provided.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -H "Authorization: <token>" \
+///   --json '{"name":"general","topic":"Talk about anything lanternfly related."}' \
+///   https://api.eludris.gay/communities/2384913657857/channels
+///
+/// {
+///   "id": 2385062735873,
+///   "community_id": 2384913657857,
+///   "type": "TEXT",
+///   "name": "general",
+///   "topic": "Talk about anything lanternfly related.",
+///   "position": 0
+/// }
+/// ```
+#[autodoc("/communities", category = "Channels")]
+#[post("/<community_id>/channels", data = "<channel>")]
+pub async fn create_channel(
+    community_id: u64,
+    channel: Json<ChannelCreate>,
+    id_generator: &State<Mutex<IdGenerator>>,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<Channel>> {
+    let mut rate_limiter = RateLimiter::new("create_channel", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    Permissions::require_community(
+        community_id,
+        session.0.user_id,
+        Permissions::MANAGE_CHANNELS,
+        &mut db,
+    )
+    .await
+    .map_err(|err| rate_limiter.add_headers(err))?;
+    let channel = Channel::create(
+        community_id,
+        channel.into_inner(),
+        &mut *id_generator.lock().await,
+        &mut db,
+    )
+    .await
+    .map_err(|err| rate_limiter.add_headers(err))?;
+    Event::new(
+        ServerPayload::ChannelCreate(channel.clone()),
+        vec![EventTarget::Community(community_id)],
+    )
+    .publish(&mut *cache)
+    .await
+    .unwrap();
+    rate_limiter.wrap_response(Json(channel))
+}
@@ -0,0 +1,75 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    ids::IdGenerator,
+    models::{Event, EventTarget, Permissions, Role, RoleCreate, ServerPayload},
+    Conf,
+};
+use tokio::sync::Mutex;
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Create a role in a community. Requires the `MANAGE_ROLES` permission.
+///
+/// New roles are put right above the community's default role and can only grant the permissions
+/// you have yourself.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -H "Authorization: <token>" \
+///   --json '{"name":"Moderators","permissions":48}' \
+///   https://api.eludris.gay/communities/2384913657857/roles
+///
+/// {
+///   "id": 2385168121857,
+///   "community_id": 2384913657857,
+///   "name": "Moderators",
+///   "permissions": 48,
+///   "position": 1
+/// }
+/// ```
+#[autodoc("/communities", category = "Communities")]
+#[post("/<community_id>/roles", data = "<role>")]
+pub async fn create_role(
+    community_id: u64,
+    role: Json<RoleCreate>,
+    id_generator: &State<Mutex<IdGenerator>>,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<Role>> {
+    let mut rate_limiter = RateLimiter::new("create_role", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    Permissions::require_community(
+        community_id,
+        session.0.user_id,
+        Permissions::MANAGE_ROLES,
+        &mut db,
+    )
+    .await
+    .map_err(|err| rate_limiter.add_headers(err))?;
+    let role = Role::create(
+        community_id,
+        role.into_inner(),
+        session.0.user_id,
+        &mut *id_generator.lock().await,
+        &mut db,
+    )
+    .await
+    .map_err(|err| rate_limiter.add_headers(err))?;
+    Event::new(
+        ServerPayload::RoleCreate(role.clone()),
+        vec![EventTarget::Community(community_id)],
+    )
+    .publish(&mut *cache)
+    .await
+    .unwrap();
+    rate_limiter.wrap_response(Json(role))
+}
@@ -0,0 +1,60 @@
+use rocket::{http::Status, response::status::Custom, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::{Event, EventTarget, Permissions, Role, ServerPayload},
+    Conf,
+};
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Delete a role below your highest role. Requires the `MANAGE_ROLES` permission.
+///
+/// The community's default role can't be deleted.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -X DELETE \
+///   -H "Authorization: <token>" \
+///   https://api.eludris.gay/communities/2384913657857/roles/2385168121857
+/// ```
+#[autodoc("/communities", category = "Communities")]
+#[delete("/<community_id>/roles/<role_id>")]
+pub async fn delete_role(
+    community_id: u64,
+    role_id: u64,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Custom<()>> {
+    let mut rate_limiter = RateLimiter::new("delete_role", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    Permissions::require_community(
+        community_id,
+        session.0.user_id,
+        Permissions::MANAGE_ROLES,
+        &mut db,
+    )
+    .await
+    .map_err(|err| rate_limiter.add_headers(err))?;
+    Role::delete(role_id, community_id, session.0.user_id, &mut db)
+        .await
+        .map_err(|err| rate_limiter.add_headers(err))?;
+    Event::new(
+        ServerPayload::RoleDelete {
+            id: role_id,
+            community_id,
+        },
+        vec![EventTarget::Community(community_id)],
+    )
+    .publish(&mut *cache)
+    .await
+    .unwrap();
+    rate_limiter.wrap_response(Custom(Status::NoContent, ()))
+}
@@ -0,0 +1,88 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::Community,
+    Conf,
+};
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Get a community by ID.
+///
+/// Private communities can only be fetched by their members.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -H "Authorization: <token>" \
+///   https://api.eludris.gay/communities/2384913657857
+///
+/// {
+///   "id": 2384913657857,
+///   "owner_id": 48615849987333,
+///   "name": "Spotted Lanternfly Hunters",
+///   "description": "Stomping them one at a time.",
+///   "private": false
+/// }
+/// ```
+#[autodoc("/communities", category = "Communities")]
+#[get("/<community_id>")]
+pub async fn get_community(
+    community_id: u64,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<Community>> {
+    let mut rate_limiter = RateLimiter::new("get_community", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    rate_limiter.wrap_response(Json(
+        Community::get(community_id, session.0.user_id, &mut db)
+            .await
+            .map_err(|err| rate_limiter.add_headers(err))?,
+    ))
+}
+
+/// Get the communities you're a member of.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -H "Authorization: <token>" \
+///   https://api.eludris.gay/communities
+///
+/// [
+///   {
+///     "id": 2384913657857,
+///     "owner_id": 48615849987333,
+///     "name": "Spotted Lanternfly Hunters",
+///     "description": "Stomping them one at a time.",
+///     "private": false
+///   }
+/// ]
+/// ```
+#[autodoc("/communities", category = "Communities")]
+#[get("/")]
+pub async fn get_communities(
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<Vec<Community>>> {
+    let mut rate_limiter = RateLimiter::new("get_community", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    rate_limiter.wrap_response(Json(
+        Community::get_joined(session.0.user_id, &mut db)
+            .await
+            .map_err(|err| rate_limiter.add_headers(err))?,
+    ))
+}
@@ -0,0 +1,51 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::Mute,
+    Conf,
+};
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Get all of a community's active mutes, ordered by when they expire.
+///
+/// This requires the `MODERATE_MEMBERS` permission.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -H "Authorization: <token>" \
+///   https://api.eludris.gay/communities/2384913657857/mutes
+///
+/// [
+///   {
+///     "community_id": 2384913657857,
+///     "user_id": 48615849987334,
+///     "moderator_id": 48615849987333,
+///     "reason": "Spamming lanternfly pictures",
+///     "expires_at": 1690995600
+///   }
+/// ]
+/// ```
+#[autodoc("/communities", category = "Communities")]
+#[get("/<community_id>/mutes")]
+pub async fn get_mutes(
+    community_id: u64,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<Vec<Mute>>> {
+    let mut rate_limiter = RateLimiter::new("get_mutes", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    rate_limiter.wrap_response(Json(
+        Mute::get_all(community_id, session.0.user_id, &mut db)
+            .await
+            .map_err(|err| rate_limiter.add_headers(err))?,
+    ))
+}
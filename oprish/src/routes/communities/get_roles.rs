@@ -0,0 +1,56 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::Role,
+    Conf,
+};
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Get the roles of a community you're a member of, ordered by position.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -H "Authorization: <token>" \
+///   https://api.eludris.gay/communities/2384913657857/roles
+///
+/// [
+///   {
+///     "id": 2384913657857,
+///     "community_id": 2384913657857,
+///     "name": "everyone",
+///     "permissions": 896,
+///     "position": 0
+///   },
+///   {
+///     "id": 2385168121857,
+///     "community_id": 2384913657857,
+///     "name": "Moderators",
+///     "permissions": 48,
+///     "position": 1
+///   }
+/// ]
+/// ```
+#[autodoc("/communities", category = "Communities")]
+#[get("/<community_id>/roles")]
+pub async fn get_roles(
+    community_id: u64,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<Vec<Role>>> {
+    let mut rate_limiter = RateLimiter::new("get_roles", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    rate_limiter.wrap_response(Json(
+        Role::get_community_roles(community_id, session.0.user_id, &mut db)
+            .await
+            .map_err(|err| rate_limiter.add_headers(err))?,
+    ))
+}
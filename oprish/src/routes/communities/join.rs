@@ -0,0 +1,56 @@
+use rocket::{http::Status, response::status::Custom, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::{Community, Event, EventTarget, ServerPayload, User},
+    Conf,
+};
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Join a public community.
+///
+/// Joining a community you're already a member of does nothing.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -X PUT \
+///   -H "Authorization: <token>" \
+///   https://api.eludris.gay/communities/2384913657857/members/@me
+/// ```
+#[autodoc("/communities", category = "Communities")]
+#[put("/<community_id>/members/@me")]
+pub async fn join_community(
+    community_id: u64,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Custom<()>> {
+    let mut rate_limiter = RateLimiter::new("join_community", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    if Community::join(community_id, session.0.user_id, &mut db)
+        .await
+        .map_err(|err| rate_limiter.add_headers(err))?
+    {
+        let user = User::get(session.0.user_id, None, &mut db, &mut *cache)
+            .await
+            .map_err(|err| rate_limiter.add_headers(err))?;
+        Event::new(
+            ServerPayload::CommunityMemberJoin { community_id, user },
+            vec![
+                EventTarget::Community(community_id),
+                EventTarget::User(session.0.user_id),
+            ],
+        )
+        .publish(&mut *cache)
+        .await
+        .unwrap();
+    }
+    rate_limiter.wrap_response(Custom(Status::NoContent, ()))
+}
@@ -0,0 +1,54 @@
+use rocket::{http::Status, response::status::Custom, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::{Community, Event, EventTarget, ServerPayload},
+    Conf,
+};
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Leave a community you're a member of.
+///
+/// The owner of a community can't leave it, they have to delete it instead.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -X DELETE \
+///   -H "Authorization: <token>" \
+///   https://api.eludris.gay/communities/2384913657857/members/@me
+/// ```
+#[autodoc("/communities", category = "Communities")]
+#[delete("/<community_id>/members/@me")]
+pub async fn leave_community(
+    community_id: u64,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Custom<()>> {
+    let mut rate_limiter = RateLimiter::new("leave_community", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    Community::leave(community_id, session.0.user_id, &mut db)
+        .await
+        .map_err(|err| rate_limiter.add_headers(err))?;
+    Event::new(
+        ServerPayload::CommunityMemberLeave {
+            community_id,
+            user_id: session.0.user_id,
+        },
+        vec![
+            EventTarget::Community(community_id),
+            EventTarget::User(session.0.user_id),
+        ],
+    )
+    .publish(&mut *cache)
+    .await
+    .unwrap();
+    rate_limiter.wrap_response(Custom(Status::NoContent, ()))
+}
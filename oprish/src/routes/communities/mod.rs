@@ -0,0 +1,136 @@
+mod add_member_role;
+mod create;
+mod create_channel;
+mod create_role;
+mod delete;
+mod delete_role;
+mod get;
+mod get_channels;
+mod get_mutes;
+mod get_roles;
+mod join;
+mod leave;
+mod mute_member;
+mod remove_member_role;
+mod unmute_member;
+mod update;
+mod update_role;
+
+use rocket::Route;
+
+pub fn get_routes() -> Vec<Route> {
+    routes![
+        create::create_community,
+        get::get_community,
+        get::get_communities,
+        update::update_community,
+        delete::delete_community,
+        join::join_community,
+        leave::leave_community,
+        create_channel::create_channel,
+        get_channels::get_channels,
+        create_role::create_role,
+        get_roles::get_roles,
+        update_role::update_role,
+        delete_role::delete_role,
+        add_member_role::add_member_role,
+        remove_member_role::remove_member_role,
+        mute_member::mute_member,
+        unmute_member::unmute_member,
+        get_mutes::get_mutes,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{rocket, test_utils::create_user};
+    use rocket::{
+        http::{Header, Status},
+        local::asynchronous::Client,
+    };
+    use todel::models::{Community, CommunityCreate, Permissions, Role, RoleCreate};
+
+    #[rocket::async_test]
+    async fn create_community() {
+        let client = Client::untracked(rocket().unwrap()).await.unwrap();
+        let (_, token) = create_user(&client, "founder").await;
+
+        let community = CommunityCreate {
+            name: "Woo Enjoyers".to_string(),
+            description: None,
+            icon: None,
+            banner: None,
+            private: false,
+        };
+        let response = client
+            .post("/communities")
+            .header(Header::new("Authorization", token.clone()))
+            .body(serde_json::to_string(&community).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let created = response.into_json::<Community>().await.unwrap();
+        assert_eq!(created.name, community.name);
+
+        let response = client
+            .get(format!("/communities/{}", created.id))
+            .header(Header::new("Authorization", token.clone()))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.into_json::<Community>().await.unwrap(), created);
+
+        let role = RoleCreate {
+            name: "Moderators".to_string(),
+            permissions: (Permissions::MANAGE_MESSAGES | Permissions::KICK_MEMBERS).bits(),
+        };
+        let response = client
+            .post(format!("/communities/{}/roles", created.id))
+            .header(Header::new("Authorization", token.clone()))
+            .body(serde_json::to_string(&role).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let role = response.into_json::<Role>().await.unwrap();
+        assert_eq!(role.position, 1);
+
+        let response = client
+            .get(format!("/communities/{}/roles", created.id))
+            .header(Header::new("Authorization", token.clone()))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let roles = response.into_json::<Vec<Role>>().await.unwrap();
+        assert_eq!(roles.len(), 2);
+        assert_eq!(roles[0].id, created.id);
+        assert_eq!(roles[1], role);
+
+        let response = client
+            .delete(format!("/communities/{0}/roles/{0}", created.id))
+            .header(Header::new("Authorization", token.clone()))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+
+        let response = client
+            .delete(format!("/communities/{}/members/@me", created.id))
+            .header(Header::new("Authorization", token.clone()))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+
+        let response = client
+            .delete(format!("/communities/{}", created.id))
+            .header(Header::new("Authorization", token.clone()))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NoContent);
+
+        let response = client
+            .get(format!("/communities/{}", created.id))
+            .header(Header::new("Authorization", token))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NotFound);
+    }
+}
@@ -0,0 +1,61 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::{Mute, MuteCreate},
+    Conf,
+};
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Mute a member of a community, preventing them from sending messages until the mute expires.
+///
+/// This replaces the member's current mute if they have one. This requires the
+/// `MODERATE_MEMBERS` permission and you can only mute members below your highest role.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -X PUT \
+///   -H "Authorization: <token>" \
+///   --json '{"duration":3600,"reason":"Spamming lanternfly pictures"}' \
+///   https://api.eludris.gay/communities/2384913657857/members/48615849987334/mute
+///
+/// {
+///   "community_id": 2384913657857,
+///   "user_id": 48615849987334,
+///   "moderator_id": 48615849987333,
+///   "reason": "Spamming lanternfly pictures",
+///   "expires_at": 1690995600
+/// }
+/// ```
+#[autodoc("/communities", category = "Communities")]
+#[put("/<community_id>/members/<user_id>/mute", data = "<mute>")]
+pub async fn mute_member(
+    community_id: u64,
+    user_id: u64,
+    mute: Json<MuteCreate>,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<Mute>> {
+    let mut rate_limiter = RateLimiter::new("mute_member", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    rate_limiter.wrap_response(Json(
+        Mute::create(
+            community_id,
+            user_id,
+            mute.into_inner(),
+            session.0.user_id,
+            &mut db,
+            &mut cache.into_inner(),
+        )
+        .await
+        .map_err(|err| rate_limiter.add_headers(err))?,
+    ))
+}
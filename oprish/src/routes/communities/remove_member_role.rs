@@ -0,0 +1,60 @@
+use rocket::{http::Status, response::status::Custom, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::{Event, EventTarget, Permissions, Role, ServerPayload},
+    Conf,
+};
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Take a role below your highest role from a community member. Requires the `MANAGE_ROLES` permission.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -X DELETE \
+///   -H "Authorization: <token>" \
+///   https://api.eludris.gay/communities/2384913657857/members/48615849987333/roles/2385168121857
+/// ```
+#[autodoc("/communities", category = "Communities")]
+#[delete("/<community_id>/members/<user_id>/roles/<role_id>")]
+pub async fn remove_member_role(
+    community_id: u64,
+    user_id: u64,
+    role_id: u64,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Custom<()>> {
+    let mut rate_limiter = RateLimiter::new("edit_member_roles", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    Permissions::require_community(
+        community_id,
+        session.0.user_id,
+        Permissions::MANAGE_ROLES,
+        &mut db,
+    )
+    .await
+    .map_err(|err| rate_limiter.add_headers(err))?;
+    let roles = Role::remove_member(role_id, community_id, user_id, session.0.user_id, &mut db)
+        .await
+        .map_err(|err| rate_limiter.add_headers(err))?;
+    Event::new(
+        ServerPayload::CommunityMemberUpdate {
+            community_id,
+            user_id,
+            roles,
+        },
+        vec![EventTarget::Community(community_id)],
+    )
+    .publish(&mut *cache)
+    .await
+    .unwrap();
+    rate_limiter.wrap_response(Custom(Status::NoContent, ()))
+}
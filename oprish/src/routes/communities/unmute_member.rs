@@ -0,0 +1,49 @@
+use rocket::{http::Status, response::status::Custom, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::Mute,
+    Conf,
+};
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Unmute a member of a community before their mute expires.
+///
+/// This requires the `MODERATE_MEMBERS` permission and you can only unmute members below your
+/// highest role.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -X DELETE \
+///   -H "Authorization: <token>" \
+///   https://api.eludris.gay/communities/2384913657857/members/48615849987334/mute
+/// ```
+#[autodoc("/communities", category = "Communities")]
+#[delete("/<community_id>/members/<user_id>/mute")]
+pub async fn unmute_member(
+    community_id: u64,
+    user_id: u64,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Custom<()>> {
+    let mut rate_limiter = RateLimiter::new("mute_member", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    Mute::delete(
+        community_id,
+        user_id,
+        session.0.user_id,
+        &mut db,
+        &mut cache.into_inner(),
+    )
+    .await
+    .map_err(|err| rate_limiter.add_headers(err))?;
+    rate_limiter.wrap_response(Custom(Status::NoContent, ()))
+}
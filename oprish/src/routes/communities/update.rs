@@ -0,0 +1,62 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::{Community, Permissions, UpdateCommunity},
+    Conf,
+};
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Modify a community. Requires the `MANAGE_COMMUNITY` permission.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -H "Authorization: <token>" \
+///   -X PATCH \
+///   --json '{"description":null,"private":true}' \
+///   https://api.eludris.gay/communities/2384913657857
+///
+/// {
+///   "id": 2384913657857,
+///   "owner_id": 48615849987333,
+///   "name": "Spotted Lanternfly Hunters",
+///   "private": true
+/// }
+/// ```
+#[autodoc("/communities", category = "Communities")]
+#[patch("/<community_id>", data = "<update>")]
+pub async fn update_community(
+    community_id: u64,
+    update: Json<UpdateCommunity>,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<Community>> {
+    let mut rate_limiter = RateLimiter::new("update_community", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    Permissions::require_community(
+        community_id,
+        session.0.user_id,
+        Permissions::MANAGE_COMMUNITY,
+        &mut db,
+    )
+    .await
+    .map_err(|err| rate_limiter.add_headers(err))?;
+    rate_limiter.wrap_response(Json(
+        Community::update(
+            community_id,
+            session.0.user_id,
+            update.into_inner(),
+            &mut db,
+        )
+        .await
+        .map_err(|err| rate_limiter.add_headers(err))?,
+    ))
+}
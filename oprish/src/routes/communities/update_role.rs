@@ -0,0 +1,71 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::{Event, EventTarget, Permissions, Role, ServerPayload, UpdateRole},
+    Conf,
+};
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Modify a role below your highest role. Requires the `MANAGE_ROLES` permission.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -H "Authorization: <token>" \
+///   -X PATCH \
+///   --json '{"permissions":560,"position":2}' \
+///   https://api.eludris.gay/communities/2384913657857/roles/2385168121857
+///
+/// {
+///   "id": 2385168121857,
+///   "community_id": 2384913657857,
+///   "name": "Moderators",
+///   "permissions": 560,
+///   "position": 2
+/// }
+/// ```
+#[autodoc("/communities", category = "Communities")]
+#[patch("/<community_id>/roles/<role_id>", data = "<update>")]
+pub async fn update_role(
+    community_id: u64,
+    role_id: u64,
+    update: Json<UpdateRole>,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<Role>> {
+    let mut rate_limiter = RateLimiter::new("update_role", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    Permissions::require_community(
+        community_id,
+        session.0.user_id,
+        Permissions::MANAGE_ROLES,
+        &mut db,
+    )
+    .await
+    .map_err(|err| rate_limiter.add_headers(err))?;
+    let role = Role::update(
+        role_id,
+        community_id,
+        session.0.user_id,
+        update.into_inner(),
+        &mut db,
+    )
+    .await
+    .map_err(|err| rate_limiter.add_headers(err))?;
+    Event::new(
+        ServerPayload::RoleUpdate(role.clone()),
+        vec![EventTarget::Community(community_id)],
+    )
+    .publish(&mut *cache)
+    .await
+    .unwrap();
+    rate_limiter.wrap_response(Json(role))
+}
@@ -0,0 +1,55 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::{DirectChannel, Event, EventTarget, ServerPayload},
+    Conf,
+};
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Add a user to a group DM you're a recipient of.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -X PUT \
+///   -H "Authorization: <token>" \
+///   https://api.eludris.gay/dms/2385700151297/recipients/48615849987335
+///
+/// {
+///   "id": 2385700151297,
+///   "type": "GROUP",
+///   "name": "The Grub Hub",
+///   "owner_id": 48615849987333,
+///   "recipients": [48615849987333, 48615849987334, 48615849987335]
+/// }
+/// ```
+#[autodoc("/dms", category = "Direct Messages")]
+#[put("/<channel_id>/recipients/<user_id>")]
+pub async fn add_recipient(
+    channel_id: u64,
+    user_id: u64,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<DirectChannel>> {
+    let mut rate_limiter = RateLimiter::new("edit_recipients", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    let channel = DirectChannel::add_recipient(channel_id, user_id, session.0.user_id, &mut db)
+        .await
+        .map_err(|err| rate_limiter.add_headers(err))?;
+    Event::new(
+        ServerPayload::DirectChannelUpdate(channel.clone()),
+        vec![EventTarget::Channel(channel.id), EventTarget::User(user_id)],
+    )
+    .publish(&mut *cache)
+    .await
+    .unwrap();
+    rate_limiter.wrap_response(Json(channel))
+}
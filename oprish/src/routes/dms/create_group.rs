@@ -0,0 +1,68 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    ids::IdGenerator,
+    models::{DirectChannel, Event, EventTarget, GroupCreate, ServerPayload},
+    Conf,
+};
+use tokio::sync::Mutex;
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Create a group DM. You automatically become its owner.
+///
+/// Groups can have at most 10 recipients, including their owner.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -H "Authorization: <token>" \
+///   --json '{"name":"The Grub Hub","recipients":[48615849987334]}' \
+///   https://api.eludris.gay/dms/groups
+///
+/// {
+///   "id": 2385700151297,
+///   "type": "GROUP",
+///   "name": "The Grub Hub",
+///   "owner_id": 48615849987333,
+///   "recipients": [48615849987333, 48615849987334]
+/// }
+/// ```
+#[autodoc("/dms", category = "Direct Messages")]
+#[post("/groups", data = "<group>")]
+pub async fn create_group(
+    group: Json<GroupCreate>,
+    id_generator: &State<Mutex<IdGenerator>>,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<DirectChannel>> {
+    let mut rate_limiter = RateLimiter::new("create_group", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    let channel = DirectChannel::create_group(
+        group.into_inner(),
+        session.0.user_id,
+        &mut *id_generator.lock().await,
+        &mut db,
+    )
+    .await
+    .map_err(|err| rate_limiter.add_headers(err))?;
+    Event::new(
+        ServerPayload::DirectChannelCreate(channel.clone()),
+        channel
+            .recipients
+            .iter()
+            .map(|id| EventTarget::User(*id))
+            .collect(),
+    )
+    .publish(&mut *cache)
+    .await
+    .unwrap();
+    rate_limiter.wrap_response(Json(channel))
+}
@@ -0,0 +1,53 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::DirectChannel,
+    Conf,
+};
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Get all the DMs and group DMs you're a recipient of.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -H "Authorization: <token>" \
+///   https://api.eludris.gay/dms
+///
+/// [
+///   {
+///     "id": 2385700151297,
+///     "type": "DM",
+///     "recipients": [48615849987333, 48615849987334]
+///   },
+///   {
+///     "id": 2385783373825,
+///     "type": "GROUP",
+///     "name": "The Grub Hub",
+///     "owner_id": 48615849987333,
+///     "recipients": [48615849987333, 48615849987334, 48615849987335]
+///   }
+/// ]
+/// ```
+#[autodoc("/dms", category = "Direct Messages")]
+#[get("/")]
+pub async fn get_dms(
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<Vec<DirectChannel>>> {
+    let mut rate_limiter = RateLimiter::new("get_dms", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    rate_limiter.wrap_response(Json(
+        DirectChannel::get_joined(session.0.user_id, &mut db)
+            .await
+            .map_err(|err| rate_limiter.add_headers(err))?,
+    ))
+}
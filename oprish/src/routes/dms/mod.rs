@@ -0,0 +1,17 @@
+mod add_recipient;
+mod create_group;
+mod get_dms;
+mod open_dm;
+mod remove_recipient;
+
+use rocket::Route;
+
+pub fn get_routes() -> Vec<Route> {
+    routes![
+        get_dms::get_dms,
+        open_dm::open_dm,
+        create_group::create_group,
+        add_recipient::add_recipient,
+        remove_recipient::remove_recipient,
+    ]
+}
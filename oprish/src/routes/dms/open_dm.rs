@@ -0,0 +1,68 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    ids::IdGenerator,
+    models::{DirectChannel, Event, EventTarget, ServerPayload},
+    Conf,
+};
+use tokio::sync::Mutex;
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Open a DM with another user.
+///
+/// If you already have a DM with the user the existing one is returned instead.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -X POST \
+///   -H "Authorization: <token>" \
+///   https://api.eludris.gay/dms/users/48615849987334
+///
+/// {
+///   "id": 2385700151297,
+///   "type": "DM",
+///   "recipients": [48615849987333, 48615849987334]
+/// }
+/// ```
+#[autodoc("/dms", category = "Direct Messages")]
+#[post("/users/<user_id>")]
+pub async fn open_dm(
+    user_id: u64,
+    id_generator: &State<Mutex<IdGenerator>>,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<DirectChannel>> {
+    let mut rate_limiter = RateLimiter::new("open_dm", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    let (channel, created) = DirectChannel::open(
+        session.0.user_id,
+        user_id,
+        &mut *id_generator.lock().await,
+        &mut db,
+    )
+    .await
+    .map_err(|err| rate_limiter.add_headers(err))?;
+    if created {
+        Event::new(
+            ServerPayload::DirectChannelCreate(channel.clone()),
+            channel
+                .recipients
+                .iter()
+                .map(|id| EventTarget::User(*id))
+                .collect(),
+        )
+        .publish(&mut *cache)
+        .await
+        .unwrap();
+    }
+    rate_limiter.wrap_response(Json(channel))
+}
@@ -0,0 +1,54 @@
+use rocket::{http::Status, response::status::Custom, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::{DirectChannel, Event, EventTarget, ServerPayload},
+    Conf,
+};
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Remove a recipient from a group DM.
+///
+/// Any recipient can remove themselves, but only the group's owner can remove other recipients.
+/// When the owner leaves, ownership is passed on to another recipient and the group is deleted
+/// once its last recipient leaves.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -X DELETE \
+///   -H "Authorization: <token>" \
+///   https://api.eludris.gay/dms/2385700151297/recipients/48615849987335
+/// ```
+#[autodoc("/dms", category = "Direct Messages")]
+#[delete("/<channel_id>/recipients/<user_id>")]
+pub async fn remove_recipient(
+    channel_id: u64,
+    user_id: u64,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Custom<()>> {
+    let mut rate_limiter = RateLimiter::new("edit_recipients", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    if let Some(channel) =
+        DirectChannel::remove_recipient(channel_id, user_id, session.0.user_id, &mut db)
+            .await
+            .map_err(|err| rate_limiter.add_headers(err))?
+    {
+        Event::new(
+            ServerPayload::DirectChannelUpdate(channel),
+            vec![EventTarget::Channel(channel_id), EventTarget::User(user_id)],
+        )
+        .publish(&mut *cache)
+        .await
+        .unwrap();
+    }
+    rate_limiter.wrap_response(Custom(Status::NoContent, ()))
+}
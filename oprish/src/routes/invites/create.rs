@@ -0,0 +1,62 @@
+use rand::rngs::StdRng;
+use rocket::{http::Status, response::status::Custom, serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::{Invite, InviteCreate},
+    Conf,
+};
+use tokio::sync::Mutex;
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Create an invite which can be used to register on the instance while its registration mode
+/// is `INVITE_ONLY`.
+///
+/// This requires the `CREATE_INVITES` instance-wide permission.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -H "Authorization: <token>" \
+///   --json '{"max_uses":5,"expires_in":86400}' \
+///   https://api.eludris.gay/invites
+///
+/// {
+///   "code": "tYhL3bJq",
+///   "creator_id": 48615849987333,
+///   "uses": 0,
+///   "max_uses": 5,
+///   "expires_at": 1690718400
+/// }
+/// ```
+#[autodoc("/invites", category = "Invites")]
+#[post("/", data = "<invite>")]
+pub async fn create_invite(
+    invite: Json<InviteCreate>,
+    rng: &State<Mutex<StdRng>>,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Custom<Json<Invite>>> {
+    let mut rate_limiter = RateLimiter::new("create_invite", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    rate_limiter.wrap_response(Custom(
+        Status::Created,
+        Json(
+            Invite::create(
+                invite.into_inner(),
+                session.0.user_id,
+                &mut *rng.lock().await,
+                &mut db,
+            )
+            .await
+            .map_err(|err| rate_limiter.add_headers(err))?,
+        ),
+    ))
+}
@@ -0,0 +1,42 @@
+use rocket::{http::Status, response::status::Custom, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::Invite,
+    Conf,
+};
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Revoke an invite.
+///
+/// You can revoke the invites you created, revoking other users' invites requires the
+/// `ADMINISTRATOR` instance-wide permission.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -X DELETE \
+///   -H "Authorization: <token>" \
+///   https://api.eludris.gay/invites/tYhL3bJq
+/// ```
+#[autodoc("/invites", category = "Invites")]
+#[delete("/<code>")]
+pub async fn delete_invite(
+    code: &str,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Custom<()>> {
+    let mut rate_limiter = RateLimiter::new("delete_invite", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    Invite::delete(code, session.0.user_id, &mut db)
+        .await
+        .map_err(|err| rate_limiter.add_headers(err))?;
+    rate_limiter.wrap_response(Custom(Status::NoContent, ()))
+}
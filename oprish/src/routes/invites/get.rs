@@ -0,0 +1,55 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::Invite,
+    Conf,
+};
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Get all of the instance's invites.
+///
+/// This requires the `ADMINISTRATOR` instance-wide permission.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -H "Authorization: <token>" \
+///   https://api.eludris.gay/invites
+///
+/// [
+///   {
+///     "code": "tYhL3bJq",
+///     "creator_id": 48615849987333,
+///     "uses": 2,
+///     "max_uses": 5,
+///     "expires_at": 1690718400
+///   },
+///   {
+///     "code": "Qm8pZx2K",
+///     "creator_id": 48615849987333,
+///     "uses": 14
+///   }
+/// ]
+/// ```
+#[autodoc("/invites", category = "Invites")]
+#[get("/")]
+pub async fn get_invites(
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<Vec<Invite>>> {
+    let mut rate_limiter = RateLimiter::new("get_invites", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    rate_limiter.wrap_response(Json(
+        Invite::get_all(session.0.user_id, &mut db)
+            .await
+            .map_err(|err| rate_limiter.add_headers(err))?,
+    ))
+}
@@ -0,0 +1,13 @@
+mod create;
+mod delete;
+mod get;
+
+use rocket::Route;
+
+pub fn get_routes() -> Vec<Route> {
+    routes![
+        create::create_invite,
+        get::get_invites,
+        delete::delete_invite
+    ]
+}
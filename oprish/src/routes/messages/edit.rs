@@ -0,0 +1,77 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::{Event, EventTarget, Message, MessageEdit, ServerPayload},
+    Conf,
+};
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Edit one of your messages.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -H "Authorization: <token>" \
+///   -X PATCH \
+///   --json '{"content":"Hello, World! (edited)"}' \
+///   https://api.eludris.gay/messages/2373120361473
+///
+/// {
+///   "id": 2373120361473,
+///   "author": {
+///     "id": 48615849987333,
+///     "username": "yendri",
+///     "social_credit": 0,
+///     "badges": 0,
+///     "permissions": 0
+///   },
+///   "content": "Hello, World! (edited)",
+///   "edited_at": 1687634530
+/// }
+/// ```
+#[autodoc("/messages", category = "Messaging")]
+#[patch("/<message_id>", data = "<edit>")]
+pub async fn edit_message(
+    message_id: u64,
+    edit: Json<MessageEdit>,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<Message>> {
+    let mut rate_limiter = RateLimiter::new("edit_message", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    let (message, new_mentions) = Message::edit(
+        message_id,
+        session.0.user_id,
+        edit.into_inner(),
+        conf,
+        &mut db,
+        &mut *cache,
+    )
+    .await
+    .map_err(|err| rate_limiter.add_headers(err))?;
+    Event::new(
+        ServerPayload::MessageUpdate(message.clone()),
+        vec![EventTarget::message(message.channel_id, message.author.id)],
+    )
+    .publish(&mut *cache)
+    .await
+    .unwrap();
+    if !new_mentions.is_empty() {
+        Event::new(
+            ServerPayload::MentionCreate(message.clone()),
+            new_mentions.into_iter().map(EventTarget::User).collect(),
+        )
+        .publish(&mut *cache)
+        .await
+        .unwrap();
+    }
+    rate_limiter.wrap_response(Json(message))
+}
@@ -0,0 +1,71 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::Message,
+    Conf,
+};
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Get the history of the messages which were sent before channels existed.
+///
+/// These messages don't belong to any channel and can't be replied to anymore, new messages are
+/// sent in channels using the [`create_message`] endpoint.
+///
+/// Messages are returned from newest to oldest. `before` and `after` are message IDs which
+/// bound the returned page, passing only `after` returns the messages directly following it.
+///
+/// `limit` defaults to 50 and can be at most 200.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -H "Authorization: <token>" \
+///   "https://api.eludris.gay/messages?before=2373120361473&limit=1"
+///
+/// [
+///   {
+///     "id": 2373119254529,
+///     "author": {
+///       "id": 48615849987333,
+///       "username": "yendri",
+///       "social_credit": 0,
+///       "badges": 0,
+///       "permissions": 0
+///     },
+///     "content": "Hello, World!"
+///   }
+/// ]
+/// ```
+#[autodoc("/messages", category = "Messaging")]
+#[get("/?<before>&<after>&<limit>")]
+pub async fn get_legacy_messages(
+    before: Option<u64>,
+    after: Option<u64>,
+    limit: Option<u32>,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<Vec<Message>>> {
+    let mut rate_limiter = RateLimiter::new("get_messages", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    rate_limiter.wrap_response(Json(
+        Message::get_history(
+            None,
+            session.0.user_id,
+            before,
+            after,
+            limit,
+            &mut db,
+            &mut cache.into_inner(),
+        )
+        .await
+        .map_err(|err| rate_limiter.add_headers(err))?,
+    ))
+}
@@ -1,133 +1,197 @@
-use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
-use crate::Cache;
-use rocket::serde::json::Json;
-use rocket::{Route, State};
-use rocket_db_pools::deadpool_redis::redis::AsyncCommands;
-use rocket_db_pools::Connection;
-use todel::http::{ClientIP, TokenAuth, DB};
-use todel::models::{ErrorResponse, Message, MessageCreate, ServerPayload, User};
-use todel::Conf;
-
-/// Post a message to Eludris.
-///
-/// -----
-///
-/// ### Example
-///
-/// ```sh
-/// curl \
-///   --json '{"author":"Not a weeb","content":"Hello, World!"}' \
-///   https://api.eludris.gay/messages
-///
-/// {
-///   "author": "Not a weeb",
-///   "content": "Hello, World!"
-/// }
-/// ```
-#[autodoc("/messages", category = "Messaging")]
-#[post("/", data = "<message>")]
-pub async fn create_message(
-    message: Json<MessageCreate>,
-    mut db: Connection<DB>,
-    mut cache: Connection<Cache>,
-    conf: &State<Conf>,
-    session: TokenAuth,
-    ip: ClientIP,
-) -> RateLimitedRouteResponse<Result<Json<Message>, ErrorResponse>> {
-    let mut rate_limiter = RateLimiter::new("create_message", ip, conf.inner());
-    rate_limiter.process_rate_limit(&mut cache).await?;
-
-    let mut message = message.into_inner();
-    message.content = message.content.trim().to_string();
-
-    // TODO: handle validation in logic impl
-    if message.content.is_empty() || message.content.len() > conf.oprish.message_limit {
-        error!(
-            rate_limiter,
-            VALIDATION,
-            "content",
-            format!(
-                "Message content has to be between 1 and {} characters long",
-                conf.oprish.message_limit
-            )
-        );
-    }
-    if let Some(disguise) = &message.disguise {
-        if let Some(name) = &disguise.name {
-            if name.len() < 2 || name.len() > 32 {
-                error!(
-                    rate_limiter,
-                    VALIDATION,
-                    "disguise.name",
-                    "The user's disguise name must be between 2 and 32 characters in length"
-                );
-            }
-        }
-    }
+mod add_reaction;
+mod delete;
+mod edit;
+mod get;
+mod remove_reaction;
 
-    let payload = ServerPayload::MessageCreate(Message {
-        author: User::get(session.0.user_id, None, &mut db, &mut *cache)
-            .await
-            .map_err(|err| rate_limiter.add_headers(err))?,
-        message,
-    });
-    cache
-        .publish::<&str, String, ()>("eludris-events", serde_json::to_string(&payload).unwrap())
-        .await
-        .unwrap();
-    if let ServerPayload::MessageCreate(message) = payload {
-        rate_limiter.wrap_response(Ok(Json(message)))
-    } else {
-        unreachable!()
-    }
-}
+use rocket::Route;
 
 pub fn get_routes() -> Vec<Route> {
-    routes![create_message]
+    routes![
+        get::get_legacy_messages,
+        edit::edit_message,
+        delete::delete_message,
+        add_reaction::add_reaction,
+        remove_reaction::remove_reaction,
+    ]
 }
 
 #[cfg(test)]
 mod tests {
-    use super::*;
-    use crate::{rocket, Cache};
-    use rocket::{futures::StreamExt, http::Status, local::asynchronous::Client};
-    use rocket_db_pools::deadpool_redis::Connection;
-    use todel::models::{Message, ServerPayload};
+    use crate::{
+        rocket,
+        test_utils::{create_channel, create_community, create_user, expect_message, subscribe},
+    };
+    use rocket::{
+        http::{Header, Status},
+        local::asynchronous::Client,
+    };
+    use todel::models::{
+        Event, EventTarget, Message, MessageCreate, ServerPayload, EVENTS_CHANNEL,
+        MESSAGE_REFERENCE_CONTENT_LIMIT,
+    };
 
     #[rocket::async_test]
     async fn create_message() {
         let client = Client::untracked(rocket().unwrap()).await.unwrap();
-        let message = Message {
+        let (_, token) = create_user(&client, "messenger").await;
+        let community = create_community(&client, "Messengers", &token).await;
+        let channel = create_channel(&client, community.id, &token).await;
+        assert_eq!(channel.position, 0);
+
+        let message = MessageCreate {
             content: "HeWoo there".to_string(),
+            disguise: None,
+            attachments: vec![],
+            reference: None,
+            mention_reference: true,
         };
 
-        let message_str = serde_json::to_string(&message).unwrap();
-        let payload = serde_json::to_string(&ServerPayload::MessageCreate(message)).unwrap();
+        let mut events = subscribe(&client, EVENTS_CHANNEL).await;
 
-        let pool = client.rocket().state::<Cache>().unwrap();
+        let response = client
+            .post(format!("/channels/{}/messages", channel.id))
+            .header(Header::new("Authorization", token.clone()))
+            .body(serde_json::to_string(&message).unwrap())
+            .dispatch()
+            .await;
 
-        let cache = pool.get().await.unwrap();
-        let mut cache = Connection::take(cache).into_pubsub();
-        cache.subscribe("eludris-events").await.unwrap();
+        assert_eq!(response.status(), Status::Ok);
+        let created = response.into_json::<Message>().await.unwrap();
+        assert_eq!(created.content, message.content);
+        assert_eq!(created.channel_id, Some(channel.id));
+
+        expect_message(
+            &mut events,
+            &Event::new(
+                ServerPayload::MessageCreate(created.clone()),
+                vec![EventTarget::Channel(channel.id)],
+            ),
+        )
+        .await;
 
+        let reply = MessageCreate {
+            content: "General Kenobi".to_string(),
+            disguise: None,
+            attachments: vec![],
+            reference: Some(created.id),
+            mention_reference: false,
+        };
         let response = client
-            .post(uri!("/messages", create_message))
-            .body(&message_str)
+            .post(format!("/channels/{}/messages", channel.id))
+            .header(Header::new("Authorization", token.clone()))
+            .body(serde_json::to_string(&reply).unwrap())
             .dispatch()
             .await;
 
         assert_eq!(response.status(), Status::Ok);
-        assert_eq!(response.into_string().await.unwrap(), message_str);
+        let reply = response.into_json::<Message>().await.unwrap();
+        let reference = reply.reference.unwrap();
+        assert_eq!(reference.id, created.id);
+        assert_eq!(reference.content, created.content);
+        assert!(!reference.mention);
+
+        let response = client
+            .put(format!("/messages/{}/reactions/%F0%9F%91%8D", created.id))
+            .header(Header::new("Authorization", token.clone()))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NoContent);
+
+        let response = client
+            .get(format!(
+                "/channels/{}/messages?after={}",
+                channel.id,
+                created.id - 1
+            ))
+            .header(Header::new("Authorization", token))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        let history = response.into_json::<Vec<Message>>().await.unwrap();
+        let fetched = history.iter().find(|m| m.id == created.id).unwrap();
+        assert_eq!(fetched.reactions.len(), 1);
+        assert_eq!(fetched.reactions[0].count, 1);
+    }
 
+    #[rocket::async_test]
+    async fn reply_to_message() {
+        let client = Client::untracked(rocket().unwrap()).await.unwrap();
+        let (author, author_token) = create_user(&client, "author").await;
+        let (_, replier_token) = create_user(&client, "replier").await;
+
+        let community = create_community(&client, "Repliers", &author_token).await;
+        let channel = create_channel(&client, community.id, &author_token).await;
+        let other_channel = create_channel(&client, community.id, &author_token).await;
+
+        let response = client
+            .put(format!("/communities/{}/members/@me", community.id))
+            .header(Header::new("Authorization", replier_token.clone()))
+            .dispatch()
+            .await;
+        assert!(response.status().class().is_success());
+
+        let message = MessageCreate {
+            content: "Woo! ".repeat(40),
+            disguise: None,
+            attachments: vec![],
+            reference: None,
+            mention_reference: false,
+        };
+        let response = client
+            .post(format!("/channels/{}/messages", channel.id))
+            .header(Header::new("Authorization", author_token))
+            .body(serde_json::to_string(&message).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let created = response.into_json::<Message>().await.unwrap();
+
+        let reply = |reference: u64, mention_reference: bool| MessageCreate {
+            content: "Indeed".to_string(),
+            disguise: None,
+            attachments: vec![],
+            reference: Some(reference),
+            mention_reference,
+        };
+        let send = |channel_id: u64, reply: MessageCreate| {
+            client
+                .post(format!("/channels/{}/messages", channel_id))
+                .header(Header::new("Authorization", replier_token.clone()))
+                .body(serde_json::to_string(&reply).unwrap())
+                .dispatch()
+        };
+
+        // The referenced message's content is trimmed and its author is mentioned if requested.
+        let response = send(channel.id, reply(created.id, true)).await;
+        assert_eq!(response.status(), Status::Ok);
+        let replied = response.into_json::<Message>().await.unwrap();
+        let reference = replied.reference.unwrap();
+        assert_eq!(reference.id, created.id);
+        assert_eq!(reference.author.id, author.id);
         assert_eq!(
-            cache
-                .into_on_message()
-                .next()
-                .await
-                .unwrap()
-                .get_payload::<String>()
-                .unwrap(),
-            payload
+            reference.content,
+            message
+                .content
+                .chars()
+                .take(MESSAGE_REFERENCE_CONTENT_LIMIT)
+                .collect::<String>()
         );
+        assert!(reference.mention);
+        assert_eq!(replied.mentions, vec![author.id]);
+
+        let response = send(channel.id, reply(created.id, false)).await;
+        assert_eq!(response.status(), Status::Ok);
+        let replied = response.into_json::<Message>().await.unwrap();
+        assert!(!replied.reference.unwrap().mention);
+        assert!(replied.mentions.is_empty());
+
+        // Messages can only reply to the messages of their own channel.
+        let response = send(other_channel.id, reply(created.id, true)).await;
+        assert_eq!(response.status(), Status::BadRequest);
+
+        let response = send(channel.id, reply(created.id + 1, false)).await;
+        assert_eq!(response.status(), Status::BadRequest);
     }
 }
@@ -0,0 +1,60 @@
+use rocket::{http::Status, response::status::Custom, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::{Emoji, Event, Message, Reaction, ServerPayload},
+    Conf,
+};
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Remove your reaction from a message.
+///
+/// The emoji has to be a URL encoded unicode emoji. Removing a reaction that doesn't exist does
+/// nothing.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -X DELETE \
+///   -H "Authorization: <token>" \
+///   https://api.eludris.gay/messages/2373120361473/reactions/%F0%9F%91%8D
+/// ```
+#[autodoc("/messages", category = "Messaging")]
+#[delete("/<message_id>/reactions/<emoji>")]
+pub async fn remove_reaction(
+    message_id: u64,
+    emoji: &str,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Custom<()>> {
+    let mut rate_limiter = RateLimiter::new("remove_reaction", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    let emoji = Emoji::parse(emoji).map_err(|err| rate_limiter.add_headers(err))?;
+    if Reaction::remove(message_id, session.0.user_id, &emoji, &mut db)
+        .await
+        .map_err(|err| rate_limiter.add_headers(err))?
+    {
+        let target = Message::get_event_target(message_id, &mut db)
+            .await
+            .map_err(|err| rate_limiter.add_headers(err))?;
+        Event::new(
+            ServerPayload::ReactionRemove {
+                message_id,
+                user_id: session.0.user_id,
+                emoji,
+            },
+            vec![target],
+        )
+        .publish(&mut *cache)
+        .await
+        .unwrap();
+    }
+    rate_limiter.wrap_response(Custom(Status::NoContent, ()))
+}
@@ -1,4 +1,12 @@
+pub mod admin;
+pub mod channels;
+pub mod communities;
+pub mod dms;
+pub mod invites;
 pub mod messages;
+pub mod posts;
+pub mod relationships;
+pub mod reports;
 pub mod sessions;
 pub mod users;
 
@@ -33,6 +41,7 @@ use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter}; // poggers
 ///   "effis_url": "https://cdn.eludris.gay",
 ///   "file_size": 20000000,
 ///   "attachment_file_size": 25000000,
+///   "registration_mode": "OPEN",
 ///   "rate_limits": {
 ///     "oprish": {
 ///       "info": {
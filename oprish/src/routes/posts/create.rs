@@ -0,0 +1,70 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    ids::IdGenerator,
+    models::{Post, PostCreate},
+    Conf,
+};
+use tokio::sync::Mutex;
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Create a post in a topic.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -H "Authorization: <token>" \
+///   --json '{"topic":"lanternflies","title":"Found another one","content":"It was on my car this time."}' \
+///   https://api.eludris.gay/posts
+///
+/// {
+///   "id": 2390219112449,
+///   "author": {
+///     "id": 48615849987333,
+///     "username": "yendri",
+///     "social_credit": 0,
+///     "follower_count": 0,
+///     "following_count": 0,
+///     "status": {
+///       "type": "ONLINE"
+///     },
+///     "badges": 0,
+///     "permissions": 0
+///   },
+///   "topic": "lanternflies",
+///   "title": "Found another one",
+///   "content": "It was on my car this time.",
+///   "score": 0,
+///   "comment_count": 0
+/// }
+/// ```
+#[autodoc("/posts", category = "Posts")]
+#[post("/", data = "<post>")]
+pub async fn create_post(
+    post: Json<PostCreate>,
+    id_generator: &State<Mutex<IdGenerator>>,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<Post>> {
+    let mut rate_limiter = RateLimiter::new("create_post", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    rate_limiter.wrap_response(Json(
+        Post::create(
+            post.into_inner(),
+            session.0.user_id,
+            &mut *id_generator.lock().await,
+            &mut db,
+            &mut cache.into_inner(),
+        )
+        .await
+        .map_err(|err| rate_limiter.add_headers(err))?,
+    ))
+}
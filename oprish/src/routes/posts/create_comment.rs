@@ -0,0 +1,70 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    ids::IdGenerator,
+    models::{Comment, CommentCreate},
+    Conf,
+};
+use tokio::sync::Mutex;
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Comment on a post or reply to one of its comments.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -H "Authorization: <token>" \
+///   --json '{"content":"Already did.","parent_id":2390252929025}' \
+///   https://api.eludris.gay/posts/2390219112449/comments
+///
+/// {
+///   "id": 2390273638401,
+///   "post_id": 2390219112449,
+///   "parent_id": 2390252929025,
+///   "author": {
+///     "id": 48615849987333,
+///     "username": "yendri",
+///     "social_credit": 42,
+///     "follower_count": 0,
+///     "following_count": 0,
+///     "status": {
+///       "type": "ONLINE"
+///     },
+///     "badges": 0,
+///     "permissions": 0
+///   },
+///   "content": "Already did."
+/// }
+/// ```
+#[autodoc("/posts", category = "Posts")]
+#[post("/<post_id>/comments", data = "<comment>")]
+pub async fn create_comment(
+    post_id: u64,
+    comment: Json<CommentCreate>,
+    id_generator: &State<Mutex<IdGenerator>>,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<Comment>> {
+    let mut rate_limiter = RateLimiter::new("create_comment", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    rate_limiter.wrap_response(Json(
+        Comment::create(
+            post_id,
+            comment.into_inner(),
+            session.0.user_id,
+            &mut *id_generator.lock().await,
+            &mut db,
+            &mut cache.into_inner(),
+        )
+        .await
+        .map_err(|err| rate_limiter.add_headers(err))?,
+    ))
+}
@@ -0,0 +1,79 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::Comment,
+    Conf,
+};
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Get all of a post's comments as a tree, with replies nested under the comments they reply to.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -H "Authorization: <token>" \
+///   https://api.eludris.gay/posts/2390219112449/comments
+///
+/// [
+///   {
+///     "id": 2390252929025,
+///     "post_id": 2390219112449,
+///     "author": {
+///       "id": 48615849987334,
+///       "username": "olivier",
+///       "social_credit": 0,
+///       "follower_count": 0,
+///       "following_count": 0,
+///       "status": {
+///         "type": "OFFLINE"
+///       },
+///       "badges": 0,
+///       "permissions": 0
+///     },
+///     "content": "Stomp it.",
+///     "replies": [
+///       {
+///         "id": 2390273638401,
+///         "post_id": 2390219112449,
+///         "parent_id": 2390252929025,
+///         "author": {
+///           "id": 48615849987333,
+///           "username": "yendri",
+///           "social_credit": 42,
+///           "follower_count": 0,
+///           "following_count": 0,
+///           "status": {
+///             "type": "ONLINE"
+///           },
+///           "badges": 0,
+///           "permissions": 0
+///         },
+///         "content": "Already did."
+///       }
+///     ]
+///   }
+/// ]
+/// ```
+#[autodoc("/posts", category = "Posts")]
+#[get("/<post_id>/comments")]
+pub async fn get_comments(
+    post_id: u64,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<Vec<Comment>>> {
+    let mut rate_limiter = RateLimiter::new("get_posts", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    rate_limiter.wrap_response(Json(
+        Comment::get_tree(post_id, &mut db, &mut cache.into_inner())
+            .await
+            .map_err(|err| rate_limiter.add_headers(err))?,
+    ))
+}
@@ -0,0 +1,78 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::{Post, PostSort},
+    Conf,
+};
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Get a page of posts from the whole instance or from a single topic.
+///
+/// `sort` is either `hot` (the default), `new` or `top`. `before` is the ID of the last post
+/// of the previous page, the returned page continues right after it in the same order.
+///
+/// `limit` defaults to 25 and can be at most 100.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -H "Authorization: <token>" \
+///   "https://api.eludris.gay/posts?topic=lanternflies&sort=top&limit=1"
+///
+/// [
+///   {
+///     "id": 2390219112449,
+///     "author": {
+///       "id": 48615849987333,
+///       "username": "yendri",
+///       "social_credit": 42,
+///       "follower_count": 0,
+///       "following_count": 0,
+///       "status": {
+///         "type": "ONLINE"
+///       },
+///       "badges": 0,
+///       "permissions": 0
+///     },
+///     "topic": "lanternflies",
+///     "title": "Found another one",
+///     "content": "It was on my car this time.",
+///     "score": 42,
+///     "comment_count": 3
+///   }
+/// ]
+/// ```
+#[autodoc("/posts", category = "Posts")]
+#[get("/?<topic>&<sort>&<before>&<limit>")]
+pub async fn get_posts(
+    topic: Option<&str>,
+    sort: Option<PostSort>,
+    before: Option<u64>,
+    limit: Option<u32>,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<Vec<Post>>> {
+    let mut rate_limiter = RateLimiter::new("get_posts", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    rate_limiter.wrap_response(Json(
+        Post::get_page(
+            topic,
+            sort.unwrap_or_default(),
+            before,
+            limit,
+            session.0.user_id,
+            &mut db,
+            &mut cache.into_inner(),
+        )
+        .await
+        .map_err(|err| rate_limiter.add_headers(err))?,
+    ))
+}
@@ -0,0 +1,25 @@
+mod create;
+mod create_comment;
+mod delete;
+mod delete_comment;
+mod get;
+mod get_comments;
+mod get_posts;
+mod remove_vote;
+mod vote;
+
+use rocket::Route;
+
+pub fn get_routes() -> Vec<Route> {
+    routes![
+        create::create_post,
+        get_posts::get_posts,
+        get::get_post,
+        delete::delete_post,
+        vote::vote_post,
+        remove_vote::remove_vote,
+        create_comment::create_comment,
+        get_comments::get_comments,
+        delete_comment::delete_comment,
+    ]
+}
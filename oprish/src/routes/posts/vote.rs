@@ -0,0 +1,69 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::{Post, PostVote},
+    Conf,
+};
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Up-vote or down-vote a post, replacing your previous vote on it.
+///
+/// Votes on other users' posts also change their social credit.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -X PUT \
+///   -H "Authorization: <token>" \
+///   --json '{"type":"UP"}' \
+///   https://api.eludris.gay/posts/2390219112449/vote
+///
+/// {
+///   "id": 2390219112449,
+///   "author": {
+///     "id": 48615849987333,
+///     "username": "yendri",
+///     "social_credit": 43,
+///     "follower_count": 0,
+///     "following_count": 0,
+///     "status": {
+///       "type": "ONLINE"
+///     },
+///     "badges": 0,
+///     "permissions": 0
+///   },
+///   "topic": "lanternflies",
+///   "title": "Found another one",
+///   "content": "It was on my car this time.",
+///   "score": 43,
+///   "comment_count": 3,
+///   "vote": "UP"
+/// }
+/// ```
+#[autodoc("/posts", category = "Posts")]
+#[put("/<post_id>/vote", data = "<vote>")]
+pub async fn vote_post(
+    post_id: u64,
+    vote: Json<PostVote>,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<Post>> {
+    let mut rate_limiter = RateLimiter::new("vote", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    Post::vote(post_id, session.0.user_id, vote.into_inner(), &mut db)
+        .await
+        .map_err(|err| rate_limiter.add_headers(err))?;
+    rate_limiter.wrap_response(Json(
+        Post::get(post_id, session.0.user_id, &mut db, &mut cache.into_inner())
+            .await
+            .map_err(|err| rate_limiter.add_headers(err))?,
+    ))
+}
@@ -0,0 +1,54 @@
+use rocket::{http::Status, response::status::Custom, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::{Event, EventTarget, Relationship},
+    Conf,
+};
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Block another user, removing any other relationship you have with them and any follow
+/// between the two of you.
+///
+/// Blocked users can't open DMs with you, send you friend requests or see your presence.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -X PUT \
+///   -H "Authorization: <token>" \
+///   https://api.eludris.gay/relationships/48615849987334/block
+/// ```
+#[autodoc("/relationships", category = "Relationships")]
+#[put("/<user_id>/block")]
+pub async fn block_user(
+    user_id: u64,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Custom<()>> {
+    let mut rate_limiter = RateLimiter::new("edit_relationships", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    let events = Relationship::block(session.0.user_id, user_id, &mut db)
+        .await
+        .map_err(|err| rate_limiter.add_headers(err))?;
+    for event in events {
+        Event::new(
+            event,
+            vec![
+                EventTarget::User(session.0.user_id),
+                EventTarget::User(user_id),
+            ],
+        )
+        .publish(&mut *cache)
+        .await
+        .unwrap();
+    }
+    rate_limiter.wrap_response(Custom(Status::NoContent, ()))
+}
@@ -0,0 +1,49 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::Relationship,
+    Conf,
+};
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Get all of your relationships with other users.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -H "Authorization: <token>" \
+///   https://api.eludris.gay/relationships
+///
+/// [
+///   {
+///     "id": 48615849987334,
+///     "type": "FRIEND"
+///   },
+///   {
+///     "id": 48615849987335,
+///     "type": "OUTGOING"
+///   }
+/// ]
+/// ```
+#[autodoc("/relationships", category = "Relationships")]
+#[get("/")]
+pub async fn get_relationships(
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<Vec<Relationship>>> {
+    let mut rate_limiter = RateLimiter::new("get_relationships", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    rate_limiter.wrap_response(Json(
+        Relationship::get_all(session.0.user_id, &mut db)
+            .await
+            .map_err(|err| rate_limiter.add_headers(err))?,
+    ))
+}
@@ -0,0 +1,19 @@
+mod accept_request;
+mod block;
+mod decline_request;
+mod get;
+mod remove;
+mod send_request;
+
+use rocket::Route;
+
+pub fn get_routes() -> Vec<Route> {
+    routes![
+        get::get_relationships,
+        send_request::send_friend_request,
+        accept_request::accept_friend_request,
+        decline_request::decline_friend_request,
+        block::block_user,
+        remove::remove_relationship,
+    ]
+}
@@ -0,0 +1,53 @@
+use rocket::{http::Status, response::status::Custom, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::{Event, EventTarget, Relationship},
+    Conf,
+};
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Send a friend request to another user.
+///
+/// If they already sent you a friend request, it gets accepted instead.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -X POST \
+///   -H "Authorization: <token>" \
+///   https://api.eludris.gay/relationships/48615849987334
+/// ```
+#[autodoc("/relationships", category = "Relationships")]
+#[post("/<user_id>")]
+pub async fn send_friend_request(
+    user_id: u64,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Custom<()>> {
+    let mut rate_limiter = RateLimiter::new("edit_relationships", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    let events = Relationship::send_request(session.0.user_id, user_id, &mut db)
+        .await
+        .map_err(|err| rate_limiter.add_headers(err))?;
+    for event in events {
+        Event::new(
+            event,
+            vec![
+                EventTarget::User(session.0.user_id),
+                EventTarget::User(user_id),
+            ],
+        )
+        .publish(&mut *cache)
+        .await
+        .unwrap();
+    }
+    rate_limiter.wrap_response(Custom(Status::NoContent, ()))
+}
@@ -0,0 +1,66 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    ids::IdGenerator,
+    models::{Event, EventTarget, Report, ReportCreate, ServerPayload},
+    Conf,
+};
+use tokio::sync::Mutex;
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Report a user, message or file to the instance's moderators.
+///
+/// You can only have one open report about the same target at once.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -H "Authorization: <token>" \
+///   --json '{"target_type":"MESSAGE","target_id":2391730577409,"category":"HARASSMENT","details":"They keep telling me to stomp on lanternflies"}' \
+///   https://api.eludris.gay/reports
+///
+/// {
+///   "id": 2391774093313,
+///   "reporter_id": 48615849987334,
+///   "target_type": "MESSAGE",
+///   "target_id": 2391730577409,
+///   "category": "HARASSMENT",
+///   "details": "They keep telling me to stomp on lanternflies",
+///   "status": "OPEN"
+/// }
+/// ```
+#[autodoc("/reports", category = "Reports")]
+#[post("/", data = "<report>")]
+pub async fn create_report(
+    report: Json<ReportCreate>,
+    id_generator: &State<Mutex<IdGenerator>>,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<Report>> {
+    let mut rate_limiter = RateLimiter::new("create_report", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    let report = Report::create(
+        report.into_inner(),
+        session.0.user_id,
+        &mut *id_generator.lock().await,
+        &mut db,
+    )
+    .await
+    .map_err(|err| rate_limiter.add_headers(err))?;
+    Event::new(
+        ServerPayload::ReportCreate(report.clone()),
+        vec![EventTarget::Moderators],
+    )
+    .publish(&mut *cache)
+    .await
+    .unwrap();
+    rate_limiter.wrap_response(Json(report))
+}
@@ -0,0 +1,65 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::{Report, ReportStatus},
+    Conf,
+};
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Get a page of the instance's reports, newest first.
+///
+/// `status` only returns reports with that status, `before` is the ID of the last report of the
+/// previous page. `limit` defaults to 50 and can be at most 100. This requires the
+/// `MANAGE_REPORTS` instance-wide permission.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -H "Authorization: <token>" \
+///   "https://api.eludris.gay/reports?status=OPEN&limit=2"
+///
+/// [
+///   {
+///     "id": 2391774093313,
+///     "reporter_id": 48615849987334,
+///     "target_type": "MESSAGE",
+///     "target_id": 2391730577409,
+///     "category": "HARASSMENT",
+///     "details": "They keep telling me to stomp on lanternflies",
+///     "status": "OPEN"
+///   },
+///   {
+///     "id": 2391702937601,
+///     "reporter_id": 48615849987333,
+///     "target_type": "USER",
+///     "target_id": 48615849987335,
+///     "category": "SPAM",
+///     "status": "OPEN"
+///   }
+/// ]
+/// ```
+#[autodoc("/reports", category = "Reports")]
+#[get("/?<status>&<before>&<limit>")]
+pub async fn get_reports(
+    status: Option<ReportStatus>,
+    before: Option<u64>,
+    limit: Option<u32>,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<Vec<Report>>> {
+    let mut rate_limiter = RateLimiter::new("get_reports", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    rate_limiter.wrap_response(Json(
+        Report::get_page(status, before, limit, session.0.user_id, &mut db)
+            .await
+            .map_err(|err| rate_limiter.add_headers(err))?,
+    ))
+}
@@ -0,0 +1,13 @@
+mod create;
+mod get;
+mod resolve;
+
+use rocket::Route;
+
+pub fn get_routes() -> Vec<Route> {
+    routes![
+        create::create_report,
+        get::get_reports,
+        resolve::resolve_report
+    ]
+}
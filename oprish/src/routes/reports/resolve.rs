@@ -0,0 +1,54 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::{Report, ReportResolve},
+    Conf,
+};
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Resolve an open report, marking it as actioned or dismissed.
+///
+/// This requires the `MANAGE_REPORTS` instance-wide permission.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -H "Authorization: <token>" \
+///   --json '{"status":"ACTIONED"}' \
+///   https://api.eludris.gay/reports/2391774093313/resolve
+///
+/// {
+///   "id": 2391774093313,
+///   "reporter_id": 48615849987334,
+///   "target_type": "MESSAGE",
+///   "target_id": 2391730577409,
+///   "category": "HARASSMENT",
+///   "details": "They keep telling me to stomp on lanternflies",
+///   "status": "ACTIONED",
+///   "resolved_by": 48615849987333
+/// }
+/// ```
+#[autodoc("/reports", category = "Reports")]
+#[post("/<report_id>/resolve", data = "<resolve>")]
+pub async fn resolve_report(
+    report_id: u64,
+    resolve: Json<ReportResolve>,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<Report>> {
+    let mut rate_limiter = RateLimiter::new("resolve_report", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    rate_limiter.wrap_response(Json(
+        Report::resolve(report_id, resolve.into_inner(), session.0.user_id, &mut db)
+            .await
+            .map_err(|err| rate_limiter.add_headers(err))?,
+    ))
+}
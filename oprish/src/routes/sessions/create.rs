@@ -34,7 +34,9 @@ use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
 ///     "user_id": 48615849987333,
 ///     "platform": "linux",
 ///     "client": "pilfer",
-///     "ip": "fc00:e10d:7150:b1gb:00b5:f00d:babe:1337"
+///     "ip": "fc00:e10d:7150:b1gb:00b5:f00d:babe:1337",
+///     "created_at": 1691780400,
+///     "last_used_at": 1691780400
 ///   }
 /// }
 /// ```
@@ -48,3 +48,45 @@ pub async fn delete_session(
         .map_err(|err| rate_limiter.add_headers(err))?,
     ))
 }
+
+/// Delete all of your sessions except for the one you're currently using, logging you out
+/// everywhere else.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -X DELETE \
+///   -H "Authorization: <token>" \
+///   --json '{"password": "wowsuchpassword"}' \
+///   https://api.eludris.gay/sessions
+/// ```
+#[autodoc("/sessions", category = "Sessions")]
+#[delete("/", data = "<delete>")]
+pub async fn delete_other_sessions(
+    delete: Json<PasswordDeleteCredentials>,
+    conf: &State<Conf>,
+    verifier: &State<Argon2<'static>>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Custom<()>> {
+    let mut rate_limiter = RateLimiter::new("delete_other_sessions", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    rate_limiter.wrap_response(Custom(
+        Status::NoContent,
+        Session::delete_others(
+            session.0.id,
+            session.0.user_id,
+            delete.into_inner(),
+            verifier.inner(),
+            &mut db,
+            &mut *cache,
+        )
+        .await
+        .map_err(|err| rate_limiter.add_headers(err))?,
+    ))
+}
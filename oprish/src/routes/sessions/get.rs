@@ -25,14 +25,18 @@ use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
 ///     "user_id": 48615849987333,
 ///     "platform": "linux",
 ///     "client": "pilfer",
-///     "ip": "fc00:e10d:7150:b1gb:00b5:f00d:babe:1337"
+///     "ip": "fc00:e10d:7150:b1gb:00b5:f00d:babe:1337",
+///     "created_at": 1691780400,
+///     "last_used_at": 1692011280
 ///   },
 ///   {
 ///     "id": 2472278163867,
 ///     "user_id": 48615849987333,
 ///     "platform": "python",
 ///     "client": "velum",
-///     "ip": "127.0.0.1"
+///     "ip": "127.0.0.1",
+///     "created_at": 1689356412,
+///     "last_used_at": 1691965802
 ///   }
 /// ]
 /// ```
@@ -8,6 +8,7 @@ pub fn get_routes() -> Vec<Route> {
     routes![
         create::create_session,
         get::get_sessions,
-        delete::delete_session
+        delete::delete_session,
+        delete::delete_other_sessions
     ]
 }
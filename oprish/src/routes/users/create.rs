@@ -14,6 +14,9 @@ use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
 
 /// Create a new user.
 ///
+/// This fails if the instance's registration mode is `CLOSED`, an `invite_code` is required if
+/// it's `INVITE_ONLY`.
+///
 /// -----
 ///
 /// ### Example
@@ -31,6 +34,8 @@ use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
 ///   "id": 48615849987333,
 ///   "username": "yendri",
 ///   "social_credit": 0,
+///   "follower_count": 0,
+///   "following_count": 0,
 ///   "badges": 0,
 ///   "permissions": 0
 /// }
@@ -0,0 +1,61 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::User,
+    Conf,
+};
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Get the users a user follows.
+///
+/// Users are returned from newest to oldest ID. `before` is a user ID which bounds the returned
+/// page.
+///
+/// `limit` defaults to 50 and can be at most 100.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -H "Authorization: <token>" \
+///   "https://api.eludris.gay/users/48615849987333/following?limit=1"
+///
+/// [
+///   {
+///     "id": 48615849987334,
+///     "username": "olivier",
+///     "social_credit": 0,
+///     "follower_count": 4,
+///     "following_count": 9,
+///     "status": {
+///       "type": "ONLINE"
+///     },
+///     "badges": 0,
+///     "permissions": 0
+///   }
+/// ]
+/// ```
+#[autodoc("/users", category = "Users")]
+#[get("/<user_id>/following?<before>&<limit>")]
+pub async fn get_following(
+    user_id: u64,
+    before: Option<u64>,
+    limit: Option<u32>,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<Vec<User>>> {
+    let mut rate_limiter = RateLimiter::new("get_follows", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    rate_limiter.wrap_response(Json(
+        User::get_following(user_id, before, limit, &mut db, &mut cache.into_inner())
+            .await
+            .map_err(|err| rate_limiter.add_headers(err))?,
+    ))
+}
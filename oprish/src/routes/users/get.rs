@@ -23,6 +23,8 @@ use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
 ///   "id": 48615849987333,
 ///   "username": "yendri",
 ///   "social_credit": 0,
+///   "follower_count": 0,
+///   "following_count": 0,
 ///   "badges": 0,
 ///   "permissions": 0
 /// }
@@ -67,6 +69,8 @@ pub async fn get_self(
 ///   "id": 48615849987333,
 ///   "username": "yendri",
 ///   "social_credit": 0,
+///   "follower_count": 0,
+///   "following_count": 0,
 ///   "badges": 0,
 ///   "permissions": 0
 /// }
@@ -118,6 +122,8 @@ pub async fn get_user(
 ///   "id": 48615849987333,
 ///   "username": "yendri",
 ///   "social_credit": 0,
+///   "follower_count": 0,
+///   "following_count": 0,
 ///   "badges": 0,
 ///   "permissions": 0
 /// }
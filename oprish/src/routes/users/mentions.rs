@@ -0,0 +1,66 @@
+use rocket::{serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::Message,
+    Conf,
+};
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Get the messages that mention you.
+///
+/// Messages are returned from newest to oldest. `before` is a message ID which bounds the
+/// returned page, which is useful for catching up on the mentions you missed while offline.
+///
+/// `limit` defaults to 50 and can be at most 200.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -H "Authorization: <token>" \
+///   "https://api.eludris.gay/users/@me/mentions?limit=1"
+///
+/// [
+///   {
+///     "id": 2373120361473,
+///     "author": {
+///       "id": 48615849987333,
+///       "username": "yendri",
+///       "social_credit": 0,
+///       "badges": 0,
+///       "permissions": 0
+///     },
+///     "content": "Hey <@48615849987334>!",
+///     "mentions": [48615849987334]
+///   }
+/// ]
+/// ```
+#[autodoc("/users", category = "Users")]
+#[get("/@me/mentions?<before>&<limit>")]
+pub async fn get_mentions(
+    before: Option<u64>,
+    limit: Option<u32>,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<Vec<Message>>> {
+    let mut rate_limiter = RateLimiter::new("get_mentions", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    rate_limiter.wrap_response(Json(
+        Message::get_mentions(
+            session.0.user_id,
+            before,
+            limit,
+            &mut db,
+            &mut cache.into_inner(),
+        )
+        .await
+        .map_err(|err| rate_limiter.add_headers(err))?,
+    ))
+}
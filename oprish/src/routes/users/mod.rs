@@ -2,9 +2,15 @@ use rocket::Route;
 
 mod create;
 mod delete;
+mod follow;
+mod followers;
+mod following;
 mod get;
+mod mentions;
 mod profile;
 mod reset_password;
+mod two_factor;
+mod unfollow;
 mod update;
 mod verify;
 
@@ -15,10 +21,262 @@ pub fn get_routes() -> Vec<Route> {
         get::get_self,
         get::get_user,
         get::get_user_with_username,
+        mentions::get_mentions,
+        followers::get_followers,
+        following::get_following,
+        follow::follow_user,
+        unfollow::unfollow_user,
         update::update_user,
         profile::update_profile,
         delete::delete_user,
         reset_password::create_password_reset_code,
         reset_password::reset_password,
+        two_factor::enroll_two_factor,
+        two_factor::confirm_two_factor,
+        two_factor::disable_two_factor,
     ]
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        rocket,
+        test_utils::{create_user, expect_message, log_in, subscribe},
+        Cache,
+    };
+    use rocket::{
+        futures::StreamExt,
+        http::{Header, Status},
+        local::asynchronous::Client,
+        tokio::time::timeout,
+    };
+    use rocket_db_pools::deadpool_redis::redis::AsyncCommands;
+    use todel::models::{
+        Event, EventTarget, ResetPassword, ServerPayload, SessionRevocation, UpdateUser, User,
+        EVENTS_CHANNEL, SESSION_REVOCATION_CHANNEL,
+    };
+
+    async fn get_self(client: &Client, token: &str) -> Status {
+        client
+            .get("/users/@me")
+            .header(Header::new("Authorization", token.to_string()))
+            .dispatch()
+            .await
+            .status()
+    }
+
+    async fn get_user(client: &Client, id: u64, token: &str) -> User {
+        let response = client
+            .get(format!("/users/{}", id))
+            .header(Header::new("Authorization", token.to_string()))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        response.into_json::<User>().await.unwrap()
+    }
+
+    #[rocket::async_test]
+    async fn follow_user() {
+        let client = Client::untracked(rocket().unwrap()).await.unwrap();
+        let (follower, follower_token) = create_user(&client, "follower").await;
+        let (followed, followed_token) = create_user(&client, "followed").await;
+
+        let mut events = subscribe(&client, EVENTS_CHANNEL).await;
+
+        let follow = |id: u64| {
+            client
+                .put(format!("/users/{}/follow", id))
+                .header(Header::new("Authorization", follower_token.clone()))
+                .dispatch()
+        };
+        let unfollow = |id: u64| {
+            client
+                .delete(format!("/users/{}/follow", id))
+                .header(Header::new("Authorization", follower_token.clone()))
+                .dispatch()
+        };
+
+        assert_eq!(follow(follower.id).await.status(), Status::BadRequest);
+
+        assert_eq!(follow(followed.id).await.status(), Status::NoContent);
+        expect_message(
+            &mut events,
+            &Event::new(
+                ServerPayload::FollowCreate {
+                    follower_id: follower.id,
+                    user_id: followed.id,
+                },
+                vec![
+                    EventTarget::User(follower.id),
+                    EventTarget::User(followed.id),
+                ],
+            ),
+        )
+        .await;
+        // Following someone twice doesn't change anything.
+        assert_eq!(follow(followed.id).await.status(), Status::NoContent);
+
+        assert_eq!(
+            get_user(&client, followed.id, &follower_token)
+                .await
+                .follower_count,
+            1
+        );
+        assert_eq!(
+            get_user(&client, follower.id, &followed_token)
+                .await
+                .following_count,
+            1
+        );
+
+        let response = client
+            .get(format!("/users/{}/followers", followed.id))
+            .header(Header::new("Authorization", followed_token.clone()))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let followers = response.into_json::<Vec<User>>().await.unwrap();
+        assert_eq!(
+            followers.iter().map(|u| u.id).collect::<Vec<u64>>(),
+            vec![follower.id]
+        );
+
+        let response = client
+            .get(format!("/users/{}/following", follower.id))
+            .header(Header::new("Authorization", followed_token.clone()))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let following = response.into_json::<Vec<User>>().await.unwrap();
+        assert_eq!(
+            following.iter().map(|u| u.id).collect::<Vec<u64>>(),
+            vec![followed.id]
+        );
+
+        assert_eq!(unfollow(followed.id).await.status(), Status::NoContent);
+        expect_message(
+            &mut events,
+            &Event::new(
+                ServerPayload::FollowDelete {
+                    follower_id: follower.id,
+                    user_id: followed.id,
+                },
+                vec![
+                    EventTarget::User(follower.id),
+                    EventTarget::User(followed.id),
+                ],
+            ),
+        )
+        .await;
+        assert_eq!(unfollow(followed.id).await.status(), Status::NotFound);
+        assert_eq!(
+            get_user(&client, followed.id, &follower_token)
+                .await
+                .follower_count,
+            0
+        );
+    }
+
+    #[rocket::async_test]
+    async fn change_password() {
+        let client = Client::untracked(rocket().unwrap()).await.unwrap();
+        let (user, token) = create_user(&client, "forgetful").await;
+        let other = log_in(&client, &user.username, "wowsuchpassword").await;
+        let mut revocations = subscribe(&client, SESSION_REVOCATION_CHANNEL).await;
+
+        let update = UpdateUser {
+            password: "wowsuchpassword".to_string(),
+            username: None,
+            email: None,
+            new_password: Some("wowsuchbetterpassword".to_string()),
+            two_factor_code: None,
+        };
+        let response = client
+            .patch("/users")
+            .header(Header::new("Authorization", token.clone()))
+            .body(serde_json::to_string(&update).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+
+        // Only the other sessions get revoked.
+        expect_message(
+            &mut revocations,
+            &SessionRevocation {
+                user_id: user.id,
+                session_ids: vec![other.session.id],
+                reason: "Password changed".to_string(),
+            },
+        )
+        .await;
+        assert_eq!(get_self(&client, &token).await, Status::Ok);
+        assert_eq!(get_self(&client, &other.token).await, Status::Unauthorized);
+
+        log_in(&client, &user.username, "wowsuchbetterpassword").await;
+    }
+
+    #[rocket::async_test]
+    async fn reset_password() {
+        let client = Client::untracked(rocket().unwrap()).await.unwrap();
+        let (user, token) = create_user(&client, "amnesiac").await;
+        let other = log_in(&client, &user.username, "wowsuchpassword").await;
+        let mut revocations = subscribe(&client, SESSION_REVOCATION_CHANNEL).await;
+
+        // The code is normally emailed to the user.
+        let email = format!("{}@example.com", user.username);
+        let mut cache = client
+            .rocket()
+            .state::<Cache>()
+            .unwrap()
+            .get()
+            .await
+            .unwrap();
+        cache
+            .set_ex::<_, _, ()>(format!("password-reset:{}", email), 123456, 60)
+            .await
+            .unwrap();
+
+        let reset = ResetPassword {
+            code: 123456,
+            email,
+            password: "wowsuchnewpassword".to_string(),
+        };
+        let response = client
+            .patch("/users/reset-password")
+            .body(serde_json::to_string(&reset).unwrap())
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::NoContent);
+
+        let revocation = timeout(Duration::from_secs(5), async {
+            loop {
+                let revocation: SessionRevocation = serde_json::from_str(
+                    &revocations
+                        .next()
+                        .await
+                        .unwrap()
+                        .get_payload::<String>()
+                        .unwrap(),
+                )
+                .unwrap();
+                if revocation.user_id == user.id {
+                    break revocation;
+                }
+            }
+        })
+        .await
+        .expect("The sessions weren't revoked");
+        // Both the session the user was created with and the other one get revoked.
+        assert_eq!(revocation.session_ids.len(), 2);
+        assert!(revocation.session_ids.contains(&other.session.id));
+        assert_eq!(revocation.reason, "Password reset");
+
+        assert_eq!(get_self(&client, &token).await, Status::Unauthorized);
+        assert_eq!(get_self(&client, &other.token).await, Status::Unauthorized);
+
+        // The new password is the one that got stored.
+        log_in(&client, &user.username, "wowsuchnewpassword").await;
+    }
+}
@@ -1,8 +1,8 @@
 use rocket::{serde::json::Json, State};
-use rocket_db_pools::{deadpool_redis::redis::AsyncCommands, Connection};
+use rocket_db_pools::Connection;
 use todel::{
     http::{Cache, TokenAuth, DB},
-    models::{ServerPayload, UpdateUserProfile, User},
+    models::{Event, EventTarget, ServerPayload, UpdateUserProfile, User},
     Conf,
 };
 
@@ -26,6 +26,8 @@ use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
 ///   "username": "yendri"
 ///   "display_name": "HappyRu"
 ///   "social_credit": 0,
+///   "follower_count": 0,
+///   "following_count": 0,
 ///   "bio": "I am very happy!"
 ///   "badges": 0,
 ///   "permissions": 0
@@ -42,16 +44,19 @@ pub async fn update_profile(
 ) -> RateLimitedRouteResponse<Json<User>> {
     let mut rate_limiter = RateLimiter::new("update_profile", session.0.user_id, conf);
     rate_limiter.process_rate_limit(&mut cache).await?;
-    let payload = ServerPayload::UserUpdate(
-        User::update_profile(session.0.user_id, profile.into_inner(), conf, &mut db)
-            .await
-            .map_err(|err| rate_limiter.add_headers(err))?,
+    let event = Event::new(
+        ServerPayload::UserUpdate(
+            User::update_profile(session.0.user_id, profile.into_inner(), conf, &mut db)
+                .await
+                .map_err(|err| rate_limiter.add_headers(err))?,
+        ),
+        vec![
+            EventTarget::User(session.0.user_id),
+            EventTarget::Presence(session.0.user_id),
+        ],
     );
-    cache
-        .publish::<&str, String, ()>("eludris-events", serde_json::to_string(&payload).unwrap())
-        .await
-        .unwrap();
-    if let ServerPayload::UserUpdate(user) = payload {
+    event.publish(&mut *cache).await.unwrap();
+    if let ServerPayload::UserUpdate(user) = event.payload {
         rate_limiter.wrap_response(Json(user))
     } else {
         unreachable!()
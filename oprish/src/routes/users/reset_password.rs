@@ -52,6 +52,8 @@ pub async fn create_password_reset_code(
 
 /// Reset your password using the password reset code.
 ///
+/// This logs you out of all of your sessions.
+///
 /// -----
 ///
 /// ### Example
@@ -0,0 +1,152 @@
+use argon2::Argon2;
+use rand::rngs::StdRng;
+use rocket::{http::Status, response::status::Custom, serde::json::Json, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::{
+        PasswordDeleteCredentials, TwoFactorConfirm, TwoFactorEnrollment, TwoFactorRecoveryCodes,
+        User,
+    },
+    Conf,
+};
+use tokio::sync::Mutex;
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Start enabling two-factor authentication for your account.
+///
+/// This returns a TOTP secret to add to your authenticator app, two-factor authentication only
+/// gets enabled once you confirm it using the [`confirm_two_factor`] endpoint.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -X POST \
+///   -H "Authorization: <token>" \
+///   https://api.eludris.gay/users/2fa
+///
+/// {
+///   "secret": "JBSWY3DPEHPK3PXP",
+///   "uri": "otpauth://totp/Eludris:yendri?secret=JBSWY3DPEHPK3PXP&issuer=Eludris&algorithm=SHA1&digits=6&period=30"
+/// }
+/// ```
+#[autodoc("/users", category = "Users")]
+#[post("/2fa")]
+pub async fn enroll_two_factor(
+    conf: &State<Conf>,
+    rng: &State<Mutex<StdRng>>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<TwoFactorEnrollment>> {
+    let mut rate_limiter = RateLimiter::new("two_factor", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    rate_limiter.wrap_response(Json(
+        User::enroll_two_factor(
+            session.0.user_id,
+            conf,
+            &mut *rng.lock().await,
+            &mut db,
+            &mut cache.into_inner(),
+        )
+        .await
+        .map_err(|err| rate_limiter.add_headers(err))?,
+    ))
+}
+
+/// Finish enabling two-factor authentication using a code from your authenticator app.
+///
+/// This returns your recovery codes which can each be used once instead of a TOTP code. They're
+/// only shown once so make sure to store them somewhere safe.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -H "Authorization: <token>" \
+///   --json '{"password":"authentícame por favor","code":"492039"}' \
+///   https://api.eludris.gay/users/2fa/confirm
+///
+/// {
+///   "recovery_codes": [
+///     "k3v9q-x7m2p",
+///     "b8n4r-t6w1z",
+///     ...
+///   ]
+/// }
+/// ```
+#[autodoc("/users", category = "Users")]
+#[post("/2fa/confirm", data = "<confirm>")]
+pub async fn confirm_two_factor(
+    confirm: Json<TwoFactorConfirm>,
+    conf: &State<Conf>,
+    verifier: &State<Argon2<'static>>,
+    rng: &State<Mutex<StdRng>>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Json<TwoFactorRecoveryCodes>> {
+    let mut rate_limiter = RateLimiter::new("two_factor", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    rate_limiter.wrap_response(Json(
+        User::confirm_two_factor(
+            session.0.user_id,
+            confirm.into_inner(),
+            verifier.inner(),
+            &mut *rng.lock().await,
+            &mut db,
+            &mut cache.into_inner(),
+        )
+        .await
+        .map_err(|err| rate_limiter.add_headers(err))?,
+    ))
+}
+
+/// Disable two-factor authentication for your account.
+///
+/// This requires your password and either a TOTP code or one of your recovery codes.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -X DELETE \
+///   -H "Authorization: <token>" \
+///   --json '{"password":"authentícame por favor","two_factor_code":"492039"}' \
+///   https://api.eludris.gay/users/2fa
+/// ```
+#[autodoc("/users", category = "Users")]
+#[delete("/2fa", data = "<credentials>")]
+pub async fn disable_two_factor(
+    credentials: Json<PasswordDeleteCredentials>,
+    conf: &State<Conf>,
+    verifier: &State<Argon2<'static>>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Custom<()>> {
+    let mut rate_limiter = RateLimiter::new("two_factor", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    rate_limiter.wrap_response(Custom(
+        Status::NoContent,
+        User::disable_two_factor(
+            session.0.user_id,
+            credentials.into_inner(),
+            verifier.inner(),
+            &mut db,
+        )
+        .await
+        .map_err(|err| rate_limiter.add_headers(err))?,
+    ))
+}
@@ -0,0 +1,52 @@
+use rocket::{http::Status, response::status::Custom, State};
+use rocket_db_pools::Connection;
+use todel::{
+    http::{Cache, TokenAuth, DB},
+    models::{Event, EventTarget, ServerPayload, User},
+    Conf,
+};
+
+use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
+
+/// Stop following a user.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```sh
+/// curl \
+///   -X DELETE \
+///   -H "Authorization: <token>" \
+///   https://api.eludris.gay/users/48615849987333/follow
+/// ```
+#[autodoc("/users", category = "Users")]
+#[delete("/<user_id>/follow")]
+pub async fn unfollow_user(
+    user_id: u64,
+    conf: &State<Conf>,
+    mut db: Connection<DB>,
+    mut cache: Connection<Cache>,
+    session: TokenAuth,
+) -> RateLimitedRouteResponse<Custom<()>> {
+    let mut rate_limiter = RateLimiter::new("follow", session.0.user_id, conf);
+    rate_limiter.process_rate_limit(&mut cache).await?;
+
+    User::unfollow(user_id, session.0.user_id, &mut db)
+        .await
+        .map_err(|err| rate_limiter.add_headers(err))?;
+    Event::new(
+        ServerPayload::FollowDelete {
+            follower_id: session.0.user_id,
+            user_id,
+        },
+        vec![
+            EventTarget::User(session.0.user_id),
+            EventTarget::User(user_id),
+        ],
+    )
+    .publish(&mut *cache)
+    .await
+    .unwrap();
+    rate_limiter.wrap_response(Custom(Status::NoContent, ()))
+}
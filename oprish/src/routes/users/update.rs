@@ -1,10 +1,10 @@
 use argon2::Argon2;
 use rand::rngs::StdRng;
 use rocket::{serde::json::Json, State};
-use rocket_db_pools::{deadpool_redis::redis::AsyncCommands, Connection};
+use rocket_db_pools::Connection;
 use todel::{
     http::{Cache, TokenAuth, DB},
-    models::{Emailer, ServerPayload, UpdateUser, User},
+    models::{Emailer, Event, EventTarget, ServerPayload, UpdateUser, User},
     Conf,
 };
 use tokio::sync::Mutex;
@@ -13,6 +13,8 @@ use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
 
 /// Modify your user account.
 ///
+/// Changing your password logs you out of all of your other sessions.
+///
 /// -----
 ///
 /// ### Example
@@ -29,6 +31,8 @@ use crate::rate_limit::{RateLimitedRouteResponse, RateLimiter};
 ///   "username": "nicolas"
 ///   "display_name": "HappyRu"
 ///   "social_credit": 0,
+///   "follower_count": 0,
+///   "following_count": 0,
 ///   "bio": "I am very happy!"
 ///   "badges": 0,
 ///   "permissions": 0
@@ -48,24 +52,29 @@ pub async fn update_user(
 ) -> RateLimitedRouteResponse<Json<User>> {
     let mut rate_limiter = RateLimiter::new("update_user", session.0.user_id, conf);
     rate_limiter.process_rate_limit(&mut cache).await?;
-    let payload = ServerPayload::UserUpdate(
-        User::update(
-            session.0.user_id,
-            update.into_inner(),
-            mailer,
-            conf,
-            hasher.inner(),
-            &mut *rng.lock().await,
-            &mut db,
-        )
-        .await
-        .map_err(|err| rate_limiter.add_headers(err))?,
+    let event = Event::new(
+        ServerPayload::UserUpdate(
+            User::update(
+                session.0.user_id,
+                session.0.id,
+                update.into_inner(),
+                mailer,
+                conf,
+                hasher.inner(),
+                &mut *rng.lock().await,
+                &mut db,
+                &mut *cache,
+            )
+            .await
+            .map_err(|err| rate_limiter.add_headers(err))?,
+        ),
+        vec![
+            EventTarget::User(session.0.user_id),
+            EventTarget::Presence(session.0.user_id),
+        ],
     );
-    cache
-        .publish::<&str, String, ()>("eludris-events", serde_json::to_string(&payload).unwrap())
-        .await
-        .unwrap();
-    if let ServerPayload::UserUpdate(user) = payload {
+    event.publish(&mut *cache).await.unwrap();
+    if let ServerPayload::UserUpdate(user) = event.payload {
         rate_limiter.wrap_response(Json(user))
     } else {
         unreachable!()
@@ -0,0 +1,136 @@
+//! Fixtures shared by the route tests.
+
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    time::Duration,
+};
+
+use rand::Rng;
+use rocket::{
+    futures::{Stream, StreamExt},
+    http::{Header, Status},
+    local::asynchronous::Client,
+    tokio::time::timeout,
+};
+use rocket_db_pools::deadpool_redis::{redis::Msg, Connection};
+use serde::Serialize;
+use todel::{
+    http::Cache,
+    models::{
+        Channel, ChannelCreate, ChannelType, Community, CommunityCreate, SessionCreate,
+        SessionCreated, User,
+    },
+};
+
+/// A random address to send requests from, to avoid getting rate limited between test runs.
+fn random_remote() -> SocketAddr {
+    SocketAddr::new(
+        IpAddr::V4(Ipv4Addr::from(rand::thread_rng().gen::<u32>())),
+        0,
+    )
+}
+
+/// Log in as a user from a random IP, returning the created session.
+pub async fn log_in(client: &Client, username: &str, password: &str) -> SessionCreated {
+    let session = SessionCreate {
+        identifier: username.to_string(),
+        password: password.to_string(),
+        platform: "linux".to_string(),
+        client: "tests".to_string(),
+        two_factor_code: None,
+    };
+    let response = client
+        .post("/sessions")
+        .remote(random_remote())
+        .body(serde_json::to_string(&session).unwrap())
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Created);
+    response.into_json::<SessionCreated>().await.unwrap()
+}
+
+/// Create a user with a random name and log in as them, returning the user and their token.
+pub async fn create_user(client: &Client, name: &str) -> (User, String) {
+    let username = format!("{}{}", name, rand::thread_rng().gen::<u32>());
+    let response = client
+        .post("/users")
+        .remote(random_remote())
+        .body(format!(
+            r#"{{"username":"{0}","email":"{0}@example.com","password":"wowsuchpassword"}}"#,
+            username
+        ))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Created);
+    let user = response.into_json::<User>().await.unwrap();
+    let token = log_in(client, &username, "wowsuchpassword").await.token;
+    (user, token)
+}
+
+pub async fn create_community(client: &Client, name: &str, token: &str) -> Community {
+    let community = CommunityCreate {
+        name: name.to_string(),
+        description: None,
+        icon: None,
+        banner: None,
+        private: false,
+    };
+    let response = client
+        .post("/communities")
+        .header(Header::new("Authorization", token.to_string()))
+        .body(serde_json::to_string(&community).unwrap())
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    response.into_json::<Community>().await.unwrap()
+}
+
+pub async fn create_channel(client: &Client, community_id: u64, token: &str) -> Channel {
+    let channel = ChannelCreate {
+        channel_type: ChannelType::Text,
+        name: "general".to_string(),
+        topic: None,
+        position: None,
+        parent_id: None,
+    };
+    let response = client
+        .post(format!("/communities/{}/channels", community_id))
+        .header(Header::new("Authorization", token.to_string()))
+        .body(serde_json::to_string(&channel).unwrap())
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+    response.into_json::<Channel>().await.unwrap()
+}
+
+/// Subscribe to one of the redis channels oprish publishes to.
+pub async fn subscribe(client: &Client, channel: &str) -> impl Stream<Item = Msg> + Unpin {
+    let cache = client
+        .rocket()
+        .state::<Cache>()
+        .unwrap()
+        .get()
+        .await
+        .unwrap();
+    let mut pubsub = Connection::take(cache).into_pubsub();
+    pubsub.subscribe(channel).await.unwrap();
+    Box::pin(pubsub.into_on_message())
+}
+
+/// Wait for a message to be published, skipping the ones published by other tests.
+pub async fn expect_message<S: Stream<Item = Msg> + Unpin, T: Serialize>(
+    messages: &mut S,
+    message: &T,
+) {
+    let expected = serde_json::to_string(message).unwrap();
+    timeout(Duration::from_secs(5), async {
+        while let Some(msg) = messages.next().await {
+            if msg.get_payload::<String>().unwrap() == expected {
+                return;
+            }
+        }
+        panic!("The subscription closed");
+    })
+    .await
+    .expect("The message wasn't published");
+}
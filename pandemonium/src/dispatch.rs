@@ -0,0 +1,327 @@
+use futures::StreamExt;
+use redis::{aio::PubSub, Msg};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use todel::models::{
+    Event, EventTarget, ServerPayload, SessionRevocation, EVENTS_CHANNEL,
+    SESSION_REVOCATION_CHANNEL,
+};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+use crate::resume::{GatewayEvent, GATEWAY_CHANNEL};
+use crate::utils::deserialize_message;
+use crate::visibility::CommunityVisibility;
+
+/// Something published on redis which was routed to a connection.
+#[derive(Debug, Clone)]
+pub enum Delivery {
+    /// An event's payload, along with the channel visibility it changed if it did.
+    Payload(Box<ServerPayload>, Option<Arc<CommunityVisibility>>),
+    Revocation(SessionRevocation),
+    Gateway(GatewayEvent),
+}
+
+/// What a connection receives events for.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Subscriptions {
+    /// The ID of the connection's user, `None` until it authenticates.
+    pub user_id: Option<u64>,
+    /// The targets of the events the connection receives.
+    pub targets: HashSet<EventTarget>,
+    /// Whether the connection receives the presence of every user instead of only the ones in
+    /// its [`EventTarget::Presence`] targets.
+    pub all_presences: bool,
+}
+
+#[derive(Debug)]
+struct Connection {
+    tx: UnboundedSender<Delivery>,
+    subscriptions: Subscriptions,
+}
+
+#[derive(Debug, Default)]
+struct DispatchTable {
+    next_id: u64,
+    connections: HashMap<u64, Connection>,
+    /// The IDs of the connections subscribed to each target.
+    targets: HashMap<EventTarget, HashSet<u64>>,
+    /// The IDs of the connections subscribed to every user's presence.
+    all_presences: HashSet<u64>,
+}
+
+impl DispatchTable {
+    fn subscribe(&mut self, id: u64, subscriptions: &Subscriptions) {
+        for target in &subscriptions.targets {
+            self.targets.entry(*target).or_default().insert(id);
+        }
+        if subscriptions.all_presences {
+            self.all_presences.insert(id);
+        }
+    }
+
+    fn unsubscribe(&mut self, id: u64, subscriptions: &Subscriptions) {
+        for target in &subscriptions.targets {
+            if let Some(ids) = self.targets.get_mut(target) {
+                ids.remove(&id);
+                if ids.is_empty() {
+                    self.targets.remove(target);
+                }
+            }
+        }
+        self.all_presences.remove(&id);
+    }
+
+    /// Get the IDs of the connections an event with the provided targets gets delivered to.
+    fn recipients(&self, targets: &[EventTarget]) -> HashSet<u64> {
+        let mut recipients = HashSet::new();
+        for target in targets {
+            match target {
+                EventTarget::Everyone => return self.connections.keys().copied().collect(),
+                EventTarget::Presence(_) => recipients.extend(&self.all_presences),
+                _ => {}
+            }
+            if let Some(ids) = self.targets.get(target) {
+                recipients.extend(ids);
+            }
+        }
+        recipients
+    }
+
+    fn deliver(&self, targets: &[EventTarget], delivery: Delivery) {
+        self.send(self.recipients(targets), delivery);
+    }
+
+    /// Deliver an event, resolving the channel visibility it changes once for all of its
+    /// recipients.
+    fn deliver_event(&self, event: Event) {
+        let recipients = self.recipients(&event.targets);
+        let visibility =
+            CommunityVisibility::changed_by(&event.payload).map(|(community_id, user_id)| {
+                let user_ids: HashSet<u64> = recipients
+                    .iter()
+                    .filter_map(|id| self.connections.get(id)?.subscriptions.user_id)
+                    .filter(|id| user_id.is_none() || user_id == Some(*id))
+                    .collect();
+                Arc::new(CommunityVisibility::new(
+                    community_id,
+                    user_ids.into_iter().collect(),
+                ))
+            });
+        self.send(
+            recipients,
+            Delivery::Payload(Box::new(event.payload), visibility),
+        );
+    }
+
+    fn send(&self, recipients: HashSet<u64>, delivery: Delivery) {
+        for id in recipients {
+            if let Some(connection) = self.connections.get(&id) {
+                // The connection's task already stopped if this fails, it unregisters itself.
+                let _ = connection.tx.send(delivery.clone());
+            }
+        }
+    }
+}
+
+/// The in-memory table routing the events published on redis to the connections of this process
+/// they target.
+#[derive(Debug, Default)]
+pub struct Dispatcher {
+    table: Mutex<DispatchTable>,
+}
+
+impl Dispatcher {
+    /// Register a new connection, it doesn't receive anything until its subscriptions are set.
+    pub fn register(self: &Arc<Self>) -> (Registration, UnboundedReceiver<Delivery>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut table = self.table.lock().unwrap();
+        let id = table.next_id;
+        table.next_id += 1;
+        table.connections.insert(
+            id,
+            Connection {
+                tx,
+                subscriptions: Subscriptions::default(),
+            },
+        );
+        (
+            Registration {
+                id,
+                dispatcher: Arc::clone(self),
+            },
+            rx,
+        )
+    }
+
+    /// Route a message published on redis to the connections it targets.
+    fn route(&self, msg: Msg) {
+        match msg.get_channel_name() {
+            EVENTS_CHANNEL => match deserialize_message::<Event>(msg) {
+                Ok(event) => self.table.lock().unwrap().deliver_event(event),
+                Err(err) => log::warn!("Failed to deserialize event: {}", err),
+            },
+            SESSION_REVOCATION_CHANNEL => match deserialize_message::<SessionRevocation>(msg) {
+                Ok(revocation) => self.table.lock().unwrap().deliver(
+                    &[EventTarget::User(revocation.user_id)],
+                    Delivery::Revocation(revocation),
+                ),
+                Err(err) => log::warn!("Failed to deserialize session revocation: {}", err),
+            },
+            GATEWAY_CHANNEL => match deserialize_message::<GatewayEvent>(msg) {
+                Ok(event) => self.table.lock().unwrap().deliver(
+                    &[EventTarget::User(event.user_id())],
+                    Delivery::Gateway(event),
+                ),
+                Err(err) => log::warn!("Failed to deserialize gateway event: {}", err),
+            },
+            channel => log::warn!("Received a message on an unknown channel: {}", channel),
+        }
+    }
+
+    /// Route the messages of the process' redis subscription until it closes.
+    pub async fn run(&self, pubsub: PubSub) {
+        let mut messages = pubsub.into_on_message();
+        while let Some(msg) = messages.next().await {
+            self.route(msg);
+        }
+    }
+}
+
+/// A connection registered in the [`Dispatcher`], unregistered once dropped.
+#[derive(Debug)]
+pub struct Registration {
+    id: u64,
+    dispatcher: Arc<Dispatcher>,
+}
+
+impl Registration {
+    /// Replace what the connection receives events for.
+    pub fn update(&self, subscriptions: Subscriptions) {
+        let mut table = self.dispatcher.table.lock().unwrap();
+        let old = match table.connections.get(&self.id) {
+            Some(connection) if connection.subscriptions == subscriptions => return,
+            Some(connection) => connection.subscriptions.clone(),
+            None => return,
+        };
+        table.unsubscribe(self.id, &old);
+        table.subscribe(self.id, &subscriptions);
+        if let Some(connection) = table.connections.get_mut(&self.id) {
+            connection.subscriptions = subscriptions;
+        }
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        let mut table = self.dispatcher.table.lock().unwrap();
+        if let Some(connection) = table.connections.remove(&self.id) {
+            table.unsubscribe(self.id, &connection.subscriptions);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::sync::Arc;
+    use todel::models::{Event, EventTarget, ServerPayload};
+
+    use super::{Delivery, Dispatcher, Subscriptions};
+
+    #[test]
+    fn route_events() {
+        let dispatcher = Arc::new(Dispatcher::default());
+        let (member, mut member_rx) = dispatcher.register();
+        let (follower, mut follower_rx) = dispatcher.register();
+        let (_unauthenticated, mut unauthenticated_rx) = dispatcher.register();
+        member.update(Subscriptions {
+            user_id: Some(1),
+            targets: HashSet::from([EventTarget::User(1), EventTarget::Channel(3)]),
+            all_presences: true,
+        });
+        follower.update(Subscriptions {
+            user_id: Some(2),
+            targets: HashSet::from([EventTarget::User(2), EventTarget::Presence(1)]),
+            all_presences: false,
+        });
+
+        let deliver = |targets: &[EventTarget]| {
+            dispatcher.table.lock().unwrap().deliver(
+                targets,
+                Delivery::Payload(Box::new(ServerPayload::Pong), None),
+            )
+        };
+        deliver(&[EventTarget::Channel(3), EventTarget::User(1)]);
+        assert!(member_rx.try_recv().is_ok());
+        assert!(member_rx.try_recv().is_err());
+        assert!(follower_rx.try_recv().is_err());
+
+        deliver(&[EventTarget::User(1), EventTarget::Presence(1)]);
+        assert!(member_rx.try_recv().is_ok());
+        assert!(follower_rx.try_recv().is_ok());
+
+        deliver(&[EventTarget::Presence(4)]);
+        assert!(member_rx.try_recv().is_ok());
+        assert!(follower_rx.try_recv().is_err());
+
+        deliver(&[EventTarget::Moderators]);
+        assert!(member_rx.try_recv().is_err());
+        assert!(follower_rx.try_recv().is_err());
+        assert!(unauthenticated_rx.try_recv().is_err());
+
+        deliver(&[EventTarget::Everyone]);
+        assert!(member_rx.try_recv().is_ok());
+        assert!(follower_rx.try_recv().is_ok());
+        assert!(unauthenticated_rx.try_recv().is_ok());
+
+        drop(member);
+        deliver(&[EventTarget::Channel(3)]);
+        assert!(member_rx.try_recv().is_err());
+        assert!(!dispatcher
+            .table
+            .lock()
+            .unwrap()
+            .targets
+            .contains_key(&EventTarget::Channel(3)));
+    }
+
+    #[test]
+    fn share_visibility() {
+        let dispatcher = Arc::new(Dispatcher::default());
+        let connections: Vec<_> = (1..=3)
+            .map(|user_id| {
+                let (registration, rx) = dispatcher.register();
+                registration.update(Subscriptions {
+                    user_id: Some(user_id),
+                    targets: HashSet::from([EventTarget::User(user_id), EventTarget::Community(4)]),
+                    all_presences: false,
+                });
+                (registration, rx)
+            })
+            .collect();
+        let deliver = |payload: ServerPayload| {
+            dispatcher
+                .table
+                .lock()
+                .unwrap()
+                .deliver_event(Event::new(payload, vec![EventTarget::Community(4)]));
+        };
+
+        deliver(ServerPayload::RoleDelete {
+            id: 5,
+            community_id: 4,
+        });
+        let mut visibilities = vec![];
+        for (_, mut rx) in connections {
+            match rx.try_recv() {
+                Ok(Delivery::Payload(_, Some(visibility))) => {
+                    assert_eq!(visibility.community_id(), 4);
+                    visibilities.push(visibility);
+                }
+                delivery => panic!("Unexpected delivery {:?}", delivery),
+            }
+        }
+        // The visibility is resolved once for every connection.
+        assert!(visibilities.windows(2).all(|v| Arc::ptr_eq(&v[0], &v[1])));
+    }
+}
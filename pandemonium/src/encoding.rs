@@ -0,0 +1,271 @@
+use flate2::{Compress, Compression, FlushCompress};
+use futures::stream::SplitSink;
+use futures::SinkExt;
+use serde::Serialize;
+use todel::models::ClientPayload;
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::{Error as WebSocketError, Message as WebSocketMessage};
+use tokio_tungstenite::WebSocketStream;
+
+/// How the payloads of a connection are serialized, picked using the `encoding` query parameter.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Encoding {
+    /// JSON text frames.
+    #[default]
+    Json,
+    /// MessagePack binary frames, with the same structure as the JSON payloads.
+    MessagePack,
+}
+
+impl Encoding {
+    /// Decode a payload sent by the client, which is either JSON in a text frame or the
+    /// connection's encoding in a binary frame.
+    ///
+    /// Returns `None` for the frames which don't contain payloads.
+    pub fn decode(self, message: &WebSocketMessage) -> Option<Result<ClientPayload, String>> {
+        match message {
+            WebSocketMessage::Text(message) => {
+                Some(serde_json::from_str(message).map_err(|err| err.to_string()))
+            }
+            WebSocketMessage::Binary(message) => Some(match self {
+                Self::Json => serde_json::from_slice(message).map_err(|err| err.to_string()),
+                Self::MessagePack => rmp_serde::from_slice(message).map_err(|err| err.to_string()),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// The way a connection's payloads are encoded.
+///
+/// Connections with `compress=zlib-stream` share one zlib context for all of the payloads sent
+/// to them, each payload ending with a sync flush (`00 00 ff ff`) and sent as a binary frame.
+#[derive(Debug, Default)]
+pub struct Codec {
+    encoding: Encoding,
+    compressor: Option<Compress>,
+}
+
+impl Codec {
+    /// Create a codec from the query string a client connected with.
+    ///
+    /// Returns an error message for the client if one of its parameters is invalid.
+    pub fn from_query(query: Option<&str>) -> Result<Self, String> {
+        let mut codec = Self::default();
+        for param in query.unwrap_or_default().split('&') {
+            match param.split_once('=') {
+                Some(("encoding", "json")) => codec.encoding = Encoding::Json,
+                Some(("encoding", "msgpack")) => codec.encoding = Encoding::MessagePack,
+                Some(("encoding", encoding)) => {
+                    return Err(format!("Unknown encoding {}", encoding))
+                }
+                Some(("compress", "zlib-stream")) => {
+                    codec.compressor = Some(Compress::new(Compression::default(), true))
+                }
+                Some(("compress", compression)) => {
+                    return Err(format!("Unknown compression {}", compression))
+                }
+                _ => {}
+            }
+        }
+        Ok(codec)
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Encode a payload into a websocket message.
+    pub fn encode<T: Serialize>(&mut self, payload: &T) -> WebSocketMessage {
+        match self.encoding {
+            Encoding::Json => self.finish(serde_json::to_vec(payload).unwrap(), true),
+            Encoding::MessagePack => self.finish(rmp_serde::to_vec_named(payload).unwrap(), false),
+        }
+    }
+
+    /// Encode a payload which was already serialized as JSON, like the ones in replay buffers.
+    pub fn encode_json(&mut self, payload: String) -> WebSocketMessage {
+        match self.encoding {
+            Encoding::Json => self.finish(payload.into_bytes(), true),
+            Encoding::MessagePack => {
+                let payload: serde_json::Value =
+                    serde_json::from_str(&payload).expect("Couldn't deserialize JSON payload");
+                self.encode(&payload)
+            }
+        }
+    }
+
+    fn finish(&mut self, payload: Vec<u8>, text: bool) -> WebSocketMessage {
+        match &mut self.compressor {
+            Some(compressor) => WebSocketMessage::Binary(compress(compressor, &payload)),
+            None if text => {
+                WebSocketMessage::Text(String::from_utf8(payload).expect("Invalid JSON payload"))
+            }
+            None => WebSocketMessage::Binary(payload),
+        }
+    }
+}
+
+/// Compress a payload with a connection's zlib context, flushing it so that the client can
+/// decompress the payload on its own.
+fn compress(compressor: &mut Compress, payload: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(payload.len() / 2 + 64);
+    let start = compressor.total_in();
+    loop {
+        let consumed = (compressor.total_in() - start) as usize;
+        compressor
+            .compress_vec(&payload[consumed..], &mut output, FlushCompress::Sync)
+            .expect("Couldn't compress payload");
+        // The flush is only complete if the compressor had room left to write in.
+        if (compressor.total_in() - start) as usize == payload.len()
+            && output.len() < output.capacity()
+        {
+            return output;
+        }
+        output.reserve(output.capacity());
+    }
+}
+
+/// The sending half of a client's websocket connection which encodes the payloads sent through
+/// it.
+#[derive(Debug)]
+pub struct PayloadSink {
+    sink: SplitSink<WebSocketStream<TcpStream>, WebSocketMessage>,
+    codec: Codec,
+}
+
+impl PayloadSink {
+    pub fn new(
+        sink: SplitSink<WebSocketStream<TcpStream>, WebSocketMessage>,
+        codec: Codec,
+    ) -> Self {
+        Self { sink, codec }
+    }
+
+    /// Encode and send a payload.
+    pub async fn send<T: Serialize>(&mut self, payload: &T) -> Result<(), WebSocketError> {
+        let message = self.codec.encode(payload);
+        self.sink.send(message).await
+    }
+
+    /// Encode and send a payload which was already serialized as JSON.
+    pub async fn send_json(&mut self, payload: String) -> Result<(), WebSocketError> {
+        let message = self.codec.encode_json(payload);
+        self.sink.send(message).await
+    }
+
+    pub fn into_inner(self) -> SplitSink<WebSocketStream<TcpStream>, WebSocketMessage> {
+        self.sink
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use flate2::{Decompress, FlushDecompress};
+    use todel::models::{ClientPayload, SequencedPayload};
+    use tokio_tungstenite::tungstenite::Message as WebSocketMessage;
+
+    use super::{Codec, Encoding};
+
+    const PAYLOADS: [&str; 6] = [
+        r#"{"op":"PONG"}"#,
+        r#"{"op":"RATE_LIMIT","d":{"wait":1010}}"#,
+        r#"{"op":"ERROR","d":{"type":"VALIDATION","status":422,"message":"Invalid request","value_name":"status","info":"The user's status name must be between 1 and 150 characters in length"}}"#,
+        r#"{"op":"PRESENCE_UPDATE","d":{"user_id":48615849987333,"status":{"type":"BUSY","text":"ayo"}}}"#,
+        r#"{"op":"MESSAGE_CREATE","d":{"id":2373120361473,"channel_id":3,"author":{"id":48615849987333,"username":"mlynar","social_credit":9999,"follower_count":0,"following_count":2,"status":{"type":"ONLINE"},"badges":256,"permissions":8},"content":"Woo!","mentions":[48615849987334],"edited_at":1687634530}}"#,
+        r#"{"op":"TYPING_START","d":{"channel_id":3,"user_id":48615849987333,"timestamp":1687634530}}"#,
+    ];
+
+    fn payload_bytes(message: WebSocketMessage) -> Vec<u8> {
+        match message {
+            WebSocketMessage::Text(message) => message.into_bytes(),
+            WebSocketMessage::Binary(message) => message,
+            message => panic!("Unexpected message {:?}", message),
+        }
+    }
+
+    #[test]
+    fn parse_query() {
+        let codec = Codec::from_query(None).unwrap();
+        assert_eq!(codec.encoding, Encoding::Json);
+        assert!(codec.compressor.is_none());
+        let codec = Codec::from_query(Some("encoding=msgpack&compress=zlib-stream")).unwrap();
+        assert_eq!(codec.encoding, Encoding::MessagePack);
+        assert!(codec.compressor.is_some());
+        assert!(Codec::from_query(Some("encoding=xml")).is_err());
+        assert!(Codec::from_query(Some("compress=gzip")).is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut codec = Codec::from_query(Some("encoding=msgpack")).unwrap();
+        for json in PAYLOADS {
+            let payload = SequencedPayload {
+                payload: serde_json::from_str(json).unwrap(),
+                seq: 42,
+            };
+            let expected = serde_json::to_value(&payload).unwrap();
+
+            let decoded: SequencedPayload =
+                rmp_serde::from_slice(&payload_bytes(codec.encode(&payload))).unwrap();
+            assert_eq!(serde_json::to_value(&decoded).unwrap(), expected);
+
+            // Replayed payloads are stored as JSON.
+            let replayed = codec.encode_json(serde_json::to_string(&payload).unwrap());
+            let decoded: SequencedPayload =
+                rmp_serde::from_slice(&payload_bytes(replayed)).unwrap();
+            assert_eq!(serde_json::to_value(&decoded).unwrap(), expected);
+        }
+
+        let typing = rmp_serde::to_vec_named(&ClientPayload::Typing { channel_id: 3 }).unwrap();
+        assert!(matches!(
+            codec.encoding().decode(&WebSocketMessage::Binary(typing)),
+            Some(Ok(ClientPayload::Typing { channel_id: 3 }))
+        ));
+        assert!(matches!(
+            codec
+                .encoding()
+                .decode(&WebSocketMessage::Text(r#"{"op":"PING"}"#.to_string())),
+            Some(Ok(ClientPayload::Ping))
+        ));
+    }
+
+    #[test]
+    fn decode_typing() {
+        let decode = |json: &str| Encoding::Json.decode(&WebSocketMessage::Text(json.to_string()));
+        assert!(matches!(
+            decode(r#"{"op":"TYPING","d":{"channel_id":2373120361473}}"#),
+            Some(Ok(ClientPayload::Typing {
+                channel_id: 2373120361473
+            }))
+        ));
+        // Typing indicators always belong to a channel.
+        assert!(matches!(decode(r#"{"op":"TYPING","d":{}}"#), Some(Err(_))));
+        assert!(matches!(
+            decode(r#"{"op":"TYPING","d":{"channel_id":null}}"#),
+            Some(Err(_))
+        ));
+    }
+
+    #[test]
+    fn zlib_stream() {
+        let mut codec = Codec::from_query(Some("compress=zlib-stream")).unwrap();
+        let mut decompressor = Decompress::new(true);
+        for json in PAYLOADS {
+            let value: serde_json::Value = serde_json::from_str(json).unwrap();
+            let compressed = match codec.encode_json(json.to_string()) {
+                WebSocketMessage::Binary(compressed) => compressed,
+                message => panic!("Unexpected message {:?}", message),
+            };
+            assert!(compressed.ends_with(&[0, 0, 0xff, 0xff]));
+            let mut decompressed = Vec::with_capacity(json.len() * 2);
+            decompressor
+                .decompress_vec(&compressed, &mut decompressed, FlushDecompress::Sync)
+                .unwrap();
+            assert_eq!(
+                serde_json::from_slice::<serde_json::Value>(&decompressed).unwrap(),
+                value
+            );
+        }
+    }
+}
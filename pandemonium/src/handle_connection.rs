@@ -1,29 +1,39 @@
-use futures::stream::{SplitSink, SplitStream};
-use futures::{SinkExt, StreamExt};
-use redis::aio::Connection;
-use redis::aio::PubSub;
+use futures::stream::SplitStream;
+use futures::StreamExt;
+use redis::aio::{Connection, MultiplexedConnection};
 use redis::AsyncCommands;
-use sqlx::{Pool, Postgres};
+use serde::Serialize;
+use sqlx::{pool::PoolConnection, Pool, Postgres};
 use std::borrow::Cow;
+use std::collections::HashSet;
 use std::net::{IpAddr, SocketAddr};
 use std::str::FromStr;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 use todel::models::{
-    ClientPayload, InstanceInfo, Secret, ServerPayload, Session, Status, StatusType, User,
+    Channel, ClientPayload, Community, ErrorResponse, Event, EventTarget, InstanceInfo,
+    Permissions, PresenceSubscription, Relationship, RelationshipType, Secret, SequencedPayload,
+    ServerPayload, Session, Status, StatusType, User,
 };
 use todel::Conf;
 use tokio::net::TcpStream;
+use tokio::sync::mpsc::UnboundedReceiver;
 use tokio::sync::Mutex;
-use tokio::time::{interval, Instant};
-use tokio_tungstenite::tungstenite::handshake::server::{Request, Response};
+use tokio::time::{interval, timeout_at, Instant};
+use tokio_tungstenite::tungstenite::handshake::server::{
+    Callback, ErrorResponse as HandshakeErrorResponse, Request, Response,
+};
+use tokio_tungstenite::tungstenite::http::StatusCode;
 use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
 use tokio_tungstenite::tungstenite::protocol::CloseFrame;
-use tokio_tungstenite::tungstenite::Message as WebSocketMessage;
 use tokio_tungstenite::{accept_hdr_async, WebSocketStream};
 
+use crate::dispatch::{Delivery, Dispatcher, Registration, Subscriptions};
+use crate::encoding::{Codec, PayloadSink};
+use crate::presence;
 use crate::rate_limit::RateLimiter;
-use crate::utils::deserialize_message;
+use crate::resume::{self, GatewayEvent, GATEWAY_CHANNEL, HANDOFF_TIMEOUT, RESUME_TIMEOUT};
+use crate::visibility::CommunityVisibility;
 
 // /// Some padding to account for network latency.
 // const TIMEOUT_PADDING: Duration = Duration::from_secs(3);
@@ -35,11 +45,261 @@ const TIMEOUT: Duration = Duration::from_secs(45);
 /// disconnected.
 const TIMEOUT_DURATION: Duration = Duration::from_secs(48); // TIMEOUT_PADDING
 
+/// The sending half of a client's websocket connection.
+type WebSocketSender = Arc<Mutex<PayloadSink>>;
+
 /// Internal pandemonium specific-struct for stored user session-related data.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub struct SessionData {
     session: Session,
     user: User,
+    /// The IDs of the communities the user is a member of.
+    communities: HashSet<u64>,
+    /// The IDs of the channels the user can see.
+    channels: HashSet<u64>,
+    /// The IDs of the users who blocked the user, whose presence is hidden from them.
+    blocked_by: HashSet<u64>,
+    /// The IDs of the users the user follows if they only subscribed to their presence.
+    following: Option<HashSet<u64>>,
+    /// The ID of the gateway session, used to resume it after a disconnect.
+    gateway_session_id: u64,
+    /// The sequence number of the last payload dispatched to the session.
+    seq: u64,
+    /// Whether the session can still be resumed after its connection closes.
+    resumable: bool,
+    /// The resume in progress while the session waits for its previous connection to hand it
+    /// over.
+    resume: Option<PendingResume>,
+    /// Whether the user reported being inactive on the session's connection.
+    idle: bool,
+}
+
+/// Internal pandemonium specific-struct for a gateway session resume which is in progress.
+#[derive(Debug, Clone)]
+struct PendingResume {
+    /// The sequence number of the last payload the client received.
+    seq: u64,
+    /// The nonce of the resume's [`GatewayEvent::Resume`] event.
+    nonce: u64,
+    /// When to stop waiting for the session's previous connection to hand it over.
+    deadline: Instant,
+    /// Whether the session's previous connection stopped receiving new payloads, payloads
+    /// received before that are still dispatched by it.
+    started: bool,
+    /// The payloads received since the resume started, dispatched once it finishes.
+    queued: Vec<ServerPayload>,
+}
+
+impl SessionData {
+    /// Whether the user can see a message sent in a channel, messages which don't belong to any
+    /// channel are visible to everyone.
+    fn can_see(&self, channel_id: Option<u64>) -> bool {
+        match channel_id {
+            Some(id) => self.channels.contains(&id),
+            None => true,
+        }
+    }
+
+    /// Whether the user receives another user's presence.
+    fn can_see_presence(&self, user_id: u64) -> bool {
+        if self.blocked_by.contains(&user_id) {
+            return false;
+        }
+        match &self.following {
+            Some(following) => following.contains(&user_id),
+            None => true,
+        }
+    }
+
+    /// Whether the user can manage the instance's reports and should be notified of new ones.
+    fn is_moderator(&self) -> bool {
+        Permissions::from_bits_truncate(self.user.permissions)
+            .intersects(Permissions::ADMINISTRATOR | Permissions::MANAGE_REPORTS)
+    }
+
+    /// Get what the session's connection has to receive events for.
+    fn subscriptions(&self) -> Subscriptions {
+        let mut targets: HashSet<EventTarget> = self
+            .channels
+            .iter()
+            .map(|id| EventTarget::Channel(*id))
+            .chain(
+                self.communities
+                    .iter()
+                    .map(|id| EventTarget::Community(*id)),
+            )
+            .collect();
+        targets.insert(EventTarget::User(self.user.id));
+        if self.is_moderator() {
+            targets.insert(EventTarget::Moderators);
+        }
+        if let Some(following) = &self.following {
+            targets.extend(following.iter().map(|id| EventTarget::Presence(*id)));
+        }
+        Subscriptions {
+            user_id: Some(self.user.id),
+            targets,
+            all_presences: self.following.is_none(),
+        }
+    }
+
+    /// Keep track of whether another user blocked the user after their relationship changes,
+    /// hiding their presence right away if they did.
+    async fn track_block(
+        &mut self,
+        user_id: u64,
+        relationship_type: RelationshipType,
+        tx: Option<&WebSocketSender>,
+        replays: &mut MultiplexedConnection,
+    ) {
+        if relationship_type != RelationshipType::Blocked {
+            self.blocked_by.remove(&user_id);
+        } else if self.blocked_by.insert(user_id) {
+            self.dispatch(
+                tx,
+                replays,
+                ServerPayload::PresenceUpdate {
+                    user_id,
+                    status: Status {
+                        status_type: StatusType::Offline,
+                        text: None,
+                    },
+                },
+            )
+            .await;
+        }
+    }
+
+    /// Stamp a payload with the session's next sequence number, store it in the session's replay
+    /// buffer and send it if the session's connection is still open.
+    async fn dispatch(
+        &mut self,
+        tx: Option<&WebSocketSender>,
+        replays: &mut MultiplexedConnection,
+        payload: ServerPayload,
+    ) {
+        if let Some(resume) = &mut self.resume {
+            if resume.started {
+                resume.queued.push(payload);
+            }
+            return;
+        }
+        self.seq += 1;
+        let payload = SequencedPayload {
+            payload,
+            seq: self.seq,
+        };
+        if let Err(err) = resume::push(
+            self.gateway_session_id,
+            self.seq,
+            &serde_json::to_string(&payload).unwrap(),
+            replays,
+        )
+        .await
+        {
+            log::error!("Failed to store payload in replay buffer: {}", err);
+        }
+        if let Some(tx) = tx {
+            send_payload(tx, &payload).await;
+        }
+    }
+
+    /// Send a payload which is only relevant while it's fresh without sequencing it, keeping it
+    /// out of the session's replay buffer.
+    async fn send_ephemeral(&self, tx: Option<&WebSocketSender>, payload: ServerPayload) {
+        // Resuming sessions only get sent payloads once their missed ones are replayed.
+        if self.resume.is_some() {
+            return;
+        }
+        if let Some(tx) = tx {
+            send_payload(tx, &payload).await;
+        }
+    }
+
+    /// Replay the payloads the session missed once its previous connection handed it over, then
+    /// dispatch the ones received in the meantime.
+    ///
+    /// Returns the reason to close the session's connection with if it couldn't be resumed.
+    async fn finish_resume(
+        &mut self,
+        tx: Option<&WebSocketSender>,
+        replays: &mut MultiplexedConnection,
+    ) -> Option<String> {
+        let resume = self.resume.take()?;
+        let buffer = match resume::get(self.gateway_session_id, replays).await {
+            Ok(buffer) => buffer,
+            Err(err) => {
+                log::error!("Failed to get replay buffer: {}", err);
+                None
+            }
+        };
+        let (seq, missed) = match buffer.and_then(|b| Some((b.seq, b.missed_since(resume.seq)?))) {
+            Some(missed) => missed,
+            None => {
+                self.resumable = false;
+                if let Some(tx) = tx {
+                    send_payload(tx, &ServerPayload::InvalidSession).await;
+                }
+                return Some("Failed to resume session".to_string());
+            }
+        };
+        self.seq = seq;
+        if let Some(tx) = tx {
+            for payload in missed {
+                send_message(tx, payload).await;
+            }
+            send_payload(tx, &ServerPayload::Resumed).await;
+        }
+        for payload in resume.queued {
+            self.dispatch(tx, replays, payload).await;
+        }
+        None
+    }
+
+    /// Update which of a community's channels the user can see after an event changed it.
+    async fn update_visibility(
+        &mut self,
+        visibility: Option<&CommunityVisibility>,
+        pool: &Pool<Postgres>,
+    ) {
+        let visibility = match visibility {
+            Some(visibility) => visibility,
+            None => return,
+        };
+        let community = match visibility.resolve(pool).await {
+            Some(community) => community,
+            None => return,
+        };
+        self.channels.retain(|id| !community.channels.contains(id));
+        match community.visible.get(&self.user.id) {
+            Some(visible) => {
+                self.communities.insert(visibility.community_id());
+                self.channels.extend(visible);
+            }
+            None => {
+                self.communities.remove(&visibility.community_id());
+            }
+        }
+    }
+
+    /// Refetch the communities and channels the user can see after their memberships change.
+    async fn refresh_visibility(&mut self, pool: &Pool<Postgres>) {
+        let mut db = match pool.acquire().await {
+            Ok(conn) => conn,
+            Err(err) => {
+                log::error!("Couldn't acquire database connection: {}", err);
+                return;
+            }
+        };
+        match Community::get_joined(self.user.id, &mut db).await {
+            Ok(communities) => self.communities = communities.into_iter().map(|c| c.id).collect(),
+            Err(err) => log::error!("Failed to get user communities: {}", err),
+        }
+        match Channel::get_visible_ids(self.user.id, &mut db).await {
+            Ok(channels) => self.channels = channels.into_iter().collect(),
+            Err(err) => log::error!("Failed to get user channels: {}", err),
+        }
+    }
 }
 
 /// A simple function that check's if a client's last ping was over TIMEOUT_DURATION seconds ago and
@@ -54,40 +314,660 @@ async fn check_connection(last_ping: Arc<Mutex<Instant>>) {
     }
 }
 
-// TODO: (like really to fucking do): split this into it's own helper functions (and sanify code)
-/// A function that handles one client connecting and disconnecting.
-pub async fn handle_connection(
-    stream: TcpStream,
-    addr: SocketAddr,
-    cache: Arc<Mutex<Connection>>,
-    pubsub: PubSub,
-    pool: Arc<Pool<Postgres>>,
-    conf: Arc<Conf>,
-    secret: Arc<Secret>,
+/// Validate a session token, returning the reason to close the connection with if it's invalid.
+async fn validate_token(
+    token: &str,
+    secret: &Secret,
+    conf: &Conf,
+    db: &mut PoolConnection<Postgres>,
+) -> Result<Session, String> {
+    match Session::validate_token(token, secret, conf, db).await {
+        Ok(session) => Ok(session),
+        // Suspended users
+        Err(err @ ErrorResponse::Forbidden { .. }) => Err(err.to_string()),
+        Err(_) => Err("Invalid credentials".to_string()),
+    }
+}
+
+/// Mark a session's user as online and fetch the data needed to dispatch payloads to them.
+async fn start_session(
+    user_session: Session,
+    db: &mut PoolConnection<Postgres>,
+    cache: &mut Connection,
+) -> Result<SessionData, String> {
+    let sessions: u32 = match cache
+        .incr(format!("session:{}", user_session.user_id), 1)
+        .await
+    {
+        Ok(sessions) => sessions,
+        Err(err) => {
+            log::error!("Failed to increment user active session counter: {}", err);
+            return Err("Failed to connect user".to_string());
+        }
+    };
+    if sessions == 1 {
+        if let Err(err) = cache
+            .sadd::<_, _, ()>("sessions", user_session.user_id)
+            .await
+        {
+            log::error!("Failed to add user to online users: {}", err);
+            return Err("Failed to connect user".to_string());
+        }
+    }
+    presence::unmark_idle(user_session.user_id, cache).await;
+    let user = match User::get(
+        user_session.user_id,
+        Some(user_session.user_id),
+        &mut *db,
+        &mut *cache,
+    )
+    .await
+    {
+        Ok(user) => user,
+        Err(err) => {
+            log::error!("Failed to get user info: {}", err);
+            return Err("Failed to connect user".to_string());
+        }
+    };
+    if user.status.status_type != StatusType::Offline {
+        if let Err(err) = Event::new(
+            ServerPayload::PresenceUpdate {
+                user_id: user_session.user_id,
+                // I don't like this either
+                status: user.status.clone(),
+            },
+            vec![
+                EventTarget::User(user_session.user_id),
+                EventTarget::Presence(user_session.user_id),
+            ],
+        )
+        .publish(&mut *cache)
+        .await
+        {
+            log::error!("Failed to publish PRESENCE_UPDATE: {}", err);
+            return Err("Failed to connect user".to_string());
+        };
+    }
+    let blocked_by: HashSet<u64> = match Relationship::get_blocked_by(user.id, &mut *db).await {
+        Ok(blocked_by) => blocked_by.into_iter().collect(),
+        Err(err) => {
+            log::error!("Failed to get user blocks: {}", err);
+            return Err("Failed to connect user".to_string());
+        }
+    };
+    let communities = match Community::get_joined(user.id, &mut *db).await {
+        Ok(communities) => communities.into_iter().map(|c| c.id).collect(),
+        Err(err) => {
+            log::error!("Failed to get user communities: {}", err);
+            return Err("Failed to connect user".to_string());
+        }
+    };
+    let channels = match Channel::get_visible_ids(user.id, db).await {
+        Ok(channels) => channels.into_iter().collect(),
+        Err(err) => {
+            log::error!("Failed to get user channels: {}", err);
+            return Err("Failed to connect user".to_string());
+        }
+    };
+    Ok(SessionData {
+        session: user_session,
+        user,
+        communities,
+        channels,
+        blocked_by,
+        following: None,
+        gateway_session_id: rand::random(),
+        seq: 0,
+        resumable: true,
+        resume: None,
+        idle: false,
+    })
+}
+
+/// Get the currently online users relevant to a newly authenticated session.
+async fn get_online_users(
+    session: &SessionData,
+    db: &mut PoolConnection<Postgres>,
+    cache: &mut Connection,
+) -> Result<Vec<User>, String> {
+    match cache.smembers::<_, Vec<u64>>("sessions").await {
+        Ok(users) => {
+            // TODO: try_join_all (please)
+            let mut user_datas = vec![];
+            for user_id in users
+                .into_iter()
+                .filter(|u| u != &session.user.id && !session.blocked_by.contains(u))
+            {
+                match User::get(user_id, None, &mut *db, &mut *cache).await {
+                    Ok(user) => {
+                        if user.status.status_type != StatusType::Offline {
+                            user_datas.push(user);
+                        }
+                    }
+                    Err(err) => {
+                        log::error!("Failed to get online users: {}", err);
+                        return Err("Failed to connect user".to_string());
+                    }
+                }
+            }
+            Ok(user_datas)
+        }
+        Err(err) => {
+            log::error!("Failed to get online users: {}", err);
+            Err("Failed to connect user".to_string())
+        }
+    }
+}
+
+/// Handle an event routed to the session's connection, dispatching it to the session if it's
+/// relevant to them.
+///
+/// Returns the reason to close the session's connection with if it has to be closed.
+async fn handle_event(
+    session: &mut SessionData,
+    delivery: Delivery,
+    tx: Option<&WebSocketSender>,
+    cache: &Arc<Mutex<Connection>>,
+    replays: &mut MultiplexedConnection,
+    pool: &Pool<Postgres>,
+) -> Option<String> {
+    let (payload, visibility) = match delivery {
+        Delivery::Payload(payload, visibility) => (*payload, visibility),
+        Delivery::Revocation(revocation) => {
+            if revocation.user_id == session.user.id
+                && revocation.session_ids.contains(&session.session.id)
+            {
+                session.resumable = false;
+                if let Err(err) = resume::delete(session.gateway_session_id, replays).await {
+                    log::error!("Failed to delete replay buffer: {}", err);
+                }
+                return Some(revocation.reason);
+            }
+            return None;
+        }
+        Delivery::Gateway(GatewayEvent::Resume {
+            user_id,
+            session_id,
+            nonce,
+        }) if session_id == session.gateway_session_id => {
+            match &mut session.resume {
+                Some(resume) if resume.nonce == nonce => resume.started = true,
+                _ => {
+                    // Another connection is resuming the session, stop dispatching payloads to it
+                    // and let the other connection know.
+                    session.resumable = false;
+                    if let Err(err) = cache
+                        .lock()
+                        .await
+                        .publish::<_, _, ()>(
+                            GATEWAY_CHANNEL,
+                            serde_json::to_string(&GatewayEvent::HandOff {
+                                user_id,
+                                session_id,
+                            })
+                            .expect("Couldn't serialize HAND_OFF event"),
+                        )
+                        .await
+                    {
+                        log::error!("Failed to publish HAND_OFF: {}", err);
+                    }
+                    return Some("Session resumed by another connection".to_string());
+                }
+            }
+            return None;
+        }
+        Delivery::Gateway(GatewayEvent::HandOff { session_id, .. })
+            if session_id == session.gateway_session_id =>
+        {
+            if matches!(&session.resume, Some(resume) if resume.started) {
+                return session.finish_resume(tx, replays).await;
+            }
+            return None;
+        }
+        Delivery::Gateway(_) => return None,
+    };
+    match payload {
+        ServerPayload::PresenceUpdate {
+            user_id,
+            mut status,
+        } => {
+            if user_id == session.user.id {
+                session.user.status = status;
+            } else if session.can_see_presence(user_id) {
+                if status.status_type == StatusType::Offline {
+                    status.text = None;
+                }
+                session
+                    .dispatch(
+                        tx,
+                        replays,
+                        ServerPayload::PresenceUpdate { user_id, status },
+                    )
+                    .await;
+            }
+        }
+        ServerPayload::UserUpdate(mut user) => {
+            if user.id == session.user.id {
+                session.user = user;
+            } else {
+                if matches!(&session.following, Some(following) if !following.contains(&user.id)) {
+                    return None;
+                }
+                if session.blocked_by.contains(&user.id) {
+                    user.status = Status {
+                        status_type: StatusType::Offline,
+                        text: None,
+                    };
+                } else if user.status.status_type == StatusType::Offline {
+                    user.status.text = None;
+                }
+                user.email = None;
+                user.verified = None;
+                user.two_factor_auth = None;
+                session
+                    .dispatch(tx, replays, ServerPayload::UserUpdate(user))
+                    .await;
+            }
+        }
+        ServerPayload::MessageCreate(message) => {
+            if session.can_see(message.channel_id) {
+                session
+                    .dispatch(tx, replays, ServerPayload::MessageCreate(message))
+                    .await;
+            }
+        }
+        ServerPayload::MessageUpdate(message) => {
+            if session.can_see(message.channel_id) {
+                session
+                    .dispatch(tx, replays, ServerPayload::MessageUpdate(message))
+                    .await;
+            }
+        }
+        ServerPayload::MentionCreate(message) => {
+            if message.mentions.contains(&session.user.id) && session.can_see(message.channel_id) {
+                session
+                    .dispatch(tx, replays, ServerPayload::MentionCreate(message))
+                    .await;
+            }
+        }
+        ServerPayload::ChannelCreate(channel) => {
+            if session.communities.contains(&channel.community_id) {
+                session.update_visibility(visibility.as_deref(), pool).await;
+                if session.channels.contains(&channel.id) {
+                    session
+                        .dispatch(tx, replays, ServerPayload::ChannelCreate(channel))
+                        .await;
+                }
+            }
+        }
+        ServerPayload::ChannelUpdate(channel) => {
+            if session.communities.contains(&channel.community_id) {
+                // The channel's overwrites might have changed who can see it.
+                let was_visible = session.channels.contains(&channel.id);
+                session.update_visibility(visibility.as_deref(), pool).await;
+                if was_visible || session.channels.contains(&channel.id) {
+                    session
+                        .dispatch(tx, replays, ServerPayload::ChannelUpdate(channel))
+                        .await;
+                }
+            }
+        }
+        ServerPayload::ChannelDelete { id, community_id } => {
+            if session.communities.contains(&community_id) {
+                session.channels.remove(&id);
+                session
+                    .dispatch(
+                        tx,
+                        replays,
+                        ServerPayload::ChannelDelete { id, community_id },
+                    )
+                    .await;
+            }
+        }
+        ServerPayload::ChannelSlowModeUpdate {
+            channel_id,
+            community_id,
+            interval,
+        } => {
+            if session.channels.contains(&channel_id) {
+                session
+                    .dispatch(
+                        tx,
+                        replays,
+                        ServerPayload::ChannelSlowModeUpdate {
+                            channel_id,
+                            community_id,
+                            interval,
+                        },
+                    )
+                    .await;
+            }
+        }
+        ServerPayload::CommunityMemberJoin { community_id, user } => {
+            if user.id == session.user.id {
+                session.update_visibility(visibility.as_deref(), pool).await;
+            }
+            if session.communities.contains(&community_id) {
+                session
+                    .dispatch(
+                        tx,
+                        replays,
+                        ServerPayload::CommunityMemberJoin { community_id, user },
+                    )
+                    .await;
+            }
+        }
+        ServerPayload::CommunityMemberLeave {
+            community_id,
+            user_id,
+        } => {
+            if session.communities.contains(&community_id) {
+                session
+                    .dispatch(
+                        tx,
+                        replays,
+                        ServerPayload::CommunityMemberLeave {
+                            community_id,
+                            user_id,
+                        },
+                    )
+                    .await;
+            }
+            if user_id == session.user.id {
+                session.update_visibility(visibility.as_deref(), pool).await;
+            }
+        }
+        ServerPayload::RoleCreate(role) => {
+            if session.communities.contains(&role.community_id) {
+                session
+                    .dispatch(tx, replays, ServerPayload::RoleCreate(role))
+                    .await;
+            }
+        }
+        ServerPayload::RoleUpdate(role) => {
+            if session.communities.contains(&role.community_id) {
+                session.update_visibility(visibility.as_deref(), pool).await;
+                session
+                    .dispatch(tx, replays, ServerPayload::RoleUpdate(role))
+                    .await;
+            }
+        }
+        ServerPayload::RoleDelete { id, community_id } => {
+            if session.communities.contains(&community_id) {
+                session.update_visibility(visibility.as_deref(), pool).await;
+                session
+                    .dispatch(tx, replays, ServerPayload::RoleDelete { id, community_id })
+                    .await;
+            }
+        }
+        ServerPayload::CommunityMemberUpdate {
+            community_id,
+            user_id,
+            roles,
+        } => {
+            if session.communities.contains(&community_id) {
+                if user_id == session.user.id {
+                    session.update_visibility(visibility.as_deref(), pool).await;
+                }
+                session
+                    .dispatch(
+                        tx,
+                        replays,
+                        ServerPayload::CommunityMemberUpdate {
+                            community_id,
+                            user_id,
+                            roles,
+                        },
+                    )
+                    .await;
+            }
+        }
+        ServerPayload::CommunityDelete { id } => {
+            if session.communities.contains(&id) {
+                session
+                    .dispatch(tx, replays, ServerPayload::CommunityDelete { id })
+                    .await;
+                session.refresh_visibility(pool).await;
+            }
+        }
+        ServerPayload::DirectChannelCreate(channel) => {
+            if channel.recipients.contains(&session.user.id) {
+                session.channels.insert(channel.id);
+                session
+                    .dispatch(tx, replays, ServerPayload::DirectChannelCreate(channel))
+                    .await;
+            }
+        }
+        ServerPayload::DirectChannelUpdate(channel) => {
+            // Removed recipients still get notified that they left the channel.
+            if channel.recipients.contains(&session.user.id) {
+                session.channels.insert(channel.id);
+                session
+                    .dispatch(tx, replays, ServerPayload::DirectChannelUpdate(channel))
+                    .await;
+            } else if session.channels.remove(&channel.id) {
+                session
+                    .dispatch(tx, replays, ServerPayload::DirectChannelUpdate(channel))
+                    .await;
+            }
+        }
+        ServerPayload::RelationshipCreate {
+            user_id,
+            relationship,
+        } => {
+            if user_id == session.user.id {
+                session
+                    .dispatch(
+                        tx,
+                        replays,
+                        ServerPayload::RelationshipCreate {
+                            user_id,
+                            relationship,
+                        },
+                    )
+                    .await;
+            } else if relationship.id == session.user.id {
+                session
+                    .track_block(user_id, relationship.relationship_type, tx, replays)
+                    .await;
+            }
+        }
+        ServerPayload::RelationshipUpdate {
+            user_id,
+            relationship,
+        } => {
+            if user_id == session.user.id {
+                session
+                    .dispatch(
+                        tx,
+                        replays,
+                        ServerPayload::RelationshipUpdate {
+                            user_id,
+                            relationship,
+                        },
+                    )
+                    .await;
+            } else if relationship.id == session.user.id {
+                session
+                    .track_block(user_id, relationship.relationship_type, tx, replays)
+                    .await;
+            }
+        }
+        ServerPayload::RelationshipDelete { user_id, id } => {
+            if user_id == session.user.id {
+                session
+                    .dispatch(
+                        tx,
+                        replays,
+                        ServerPayload::RelationshipDelete { user_id, id },
+                    )
+                    .await;
+            } else if id == session.user.id {
+                session.blocked_by.remove(&user_id);
+            }
+        }
+        ServerPayload::FollowCreate {
+            follower_id,
+            user_id,
+        } => {
+            if follower_id == session.user.id {
+                if let Some(following) = &mut session.following {
+                    following.insert(user_id);
+                }
+            }
+            if follower_id == session.user.id || user_id == session.user.id {
+                session
+                    .dispatch(
+                        tx,
+                        replays,
+                        ServerPayload::FollowCreate {
+                            follower_id,
+                            user_id,
+                        },
+                    )
+                    .await;
+            }
+        }
+        ServerPayload::FollowDelete {
+            follower_id,
+            user_id,
+        } => {
+            if follower_id == session.user.id {
+                if let Some(following) = &mut session.following {
+                    following.remove(&user_id);
+                }
+            }
+            if follower_id == session.user.id || user_id == session.user.id {
+                session
+                    .dispatch(
+                        tx,
+                        replays,
+                        ServerPayload::FollowDelete {
+                            follower_id,
+                            user_id,
+                        },
+                    )
+                    .await;
+            }
+        }
+        ServerPayload::TypingStart {
+            channel_id,
+            user_id,
+            timestamp,
+        } => {
+            if user_id != session.user.id && session.channels.contains(&channel_id) {
+                session
+                    .send_ephemeral(
+                        tx,
+                        ServerPayload::TypingStart {
+                            channel_id,
+                            user_id,
+                            timestamp,
+                        },
+                    )
+                    .await;
+            }
+        }
+        ServerPayload::ReportCreate(report) => {
+            if session.is_moderator() {
+                session
+                    .dispatch(tx, replays, ServerPayload::ReportCreate(report))
+                    .await;
+            }
+        }
+        payload => session.dispatch(tx, replays, payload).await,
+    }
+    None
+}
+
+/// Keep dispatching payloads to a session's replay buffer after its connection closed so that it
+/// can be resumed, until it expires or another connection resumes it.
+async fn linger(
+    session: &mut SessionData,
+    registration: &Registration,
+    events: &mut UnboundedReceiver<Delivery>,
+    cache: &Arc<Mutex<Connection>>,
+    replays: &mut MultiplexedConnection,
+    pool: &Pool<Postgres>,
 ) {
-    let mut rl_address = IpAddr::from_str("127.0.0.1").unwrap();
+    if let Err(err) = resume::refresh(session.gateway_session_id, replays).await {
+        log::error!("Failed to refresh replay buffer: {}", err);
+    }
+    let deadline = Instant::now() + RESUME_TIMEOUT;
+    while let Ok(Some(delivery)) = timeout_at(deadline, events.recv()).await {
+        if handle_event(session, delivery, None, cache, replays, pool)
+            .await
+            .is_some()
+        {
+            return;
+        }
+        registration.update(session.subscriptions());
+    }
+    if let Err(err) = resume::delete(session.gateway_session_id, replays).await {
+        log::error!("Failed to delete replay buffer: {}", err);
+    }
+}
+
+/// The callback reading what a connection needs out of the request it was opened with.
+struct Handshake<'a> {
+    addr: SocketAddr,
+    rl_address: &'a mut IpAddr,
+    codec: &'a mut Codec,
+}
 
-    let socket = match accept_hdr_async(stream, |req: &Request, resp: Response| {
+impl Callback for Handshake<'_> {
+    fn on_request(self, req: &Request, resp: Response) -> Result<Response, HandshakeErrorResponse> {
         let headers = req.headers();
+        let addr = self.addr;
 
         if let Some(ip) = headers.get("X-Real-Ip") {
-            rl_address = ip
+            *self.rl_address = ip
                 .to_str()
                 .map(|ip| IpAddr::from_str(ip).unwrap_or_else(|_| addr.ip()))
                 .unwrap_or_else(|_| addr.ip());
         } else if let Some(ip) = headers.get("CF-Connecting-IP") {
-            rl_address = ip
+            *self.rl_address = ip
                 .to_str()
                 .map(|ip| IpAddr::from_str(ip).unwrap_or_else(|_| addr.ip()))
                 .unwrap_or_else(|_| addr.ip());
         } else {
-            rl_address = addr.ip();
+            *self.rl_address = addr.ip();
+        }
+
+        match Codec::from_query(req.uri().query()) {
+            Ok(codec) => *self.codec = codec,
+            Err(err) => {
+                let mut resp = HandshakeErrorResponse::new(Some(err));
+                *resp.status_mut() = StatusCode::BAD_REQUEST;
+                return Err(resp);
+            }
         }
 
         Ok(resp)
-    })
-    .await
-    {
+    }
+}
+
+// TODO: (like really to fucking do): split this into it's own helper functions (and sanify code)
+/// A function that handles one client connecting and disconnecting.
+pub async fn handle_connection(
+    stream: TcpStream,
+    addr: SocketAddr,
+    cache: Arc<Mutex<Connection>>,
+    mut replays: MultiplexedConnection,
+    dispatcher: Arc<Dispatcher>,
+    pool: Arc<Pool<Postgres>>,
+    conf: Arc<Conf>,
+    secret: Arc<Secret>,
+) {
+    let mut rl_address = IpAddr::from_str("127.0.0.1").unwrap();
+    let mut codec = Codec::default();
+
+    let handshake = Handshake {
+        addr,
+        rl_address: &mut rl_address,
+        codec: &mut codec,
+    };
+    let socket = match accept_hdr_async(stream, handshake).await {
         Ok(socket) => socket,
         Err(err) => {
             log::error!(
@@ -99,8 +979,9 @@ pub async fn handle_connection(
         }
     };
 
+    let encoding = codec.encoding();
     let (tx, mut rx) = socket.split();
-    let tx = Arc::new(Mutex::new(tx));
+    let tx = Arc::new(Mutex::new(PayloadSink::new(tx, codec)));
     let last_ping = Arc::new(Mutex::new(Instant::now()));
 
     let mut rate_limiter = RateLimiter::new(
@@ -125,9 +1006,11 @@ pub async fn handle_connection(
     .await;
 
     let session = Arc::new(Mutex::new(None::<SessionData>));
+    let (registration, mut events) = dispatcher.register();
 
     let handle_rx = async {
         let cache = Arc::clone(&cache);
+        let mut replays = replays.clone();
         while let Some(msg) = rx.next().await {
             log::trace!("New gateway message:\n{:#?}", msg);
             if let Err(wait) = rate_limiter.process_rate_limit().await {
@@ -145,13 +1028,168 @@ pub async fn handle_connection(
                 rate_limited = false;
             }
             match msg {
-                Ok(data) => match data {
-                    WebSocketMessage::Text(message) => {
-                        match serde_json::from_str::<ClientPayload>(&message) {
+                Ok(data) => match encoding.decode(&data) {
+                    Some(payload) => {
+                        match payload {
                             Ok(ClientPayload::Ping) => {
                                 let mut last_ping = last_ping.lock().await;
                                 *last_ping = Instant::now();
                                 send_payload(&tx, &ServerPayload::Pong).await;
+                                if let Some(session) = session.lock().await.as_ref() {
+                                    if let Err(err) =
+                                        resume::refresh(session.gateway_session_id, &mut replays)
+                                            .await
+                                    {
+                                        log::error!("Failed to refresh replay buffer: {}", err);
+                                    }
+                                }
+                            }
+                            Ok(ClientPayload::SubscribePresences(subscription)) => {
+                                let mut session = session.lock().await;
+                                let session = match session.as_mut() {
+                                    Some(session) => session,
+                                    None => continue,
+                                };
+                                session.following = match subscription {
+                                    PresenceSubscription::All => None,
+                                    PresenceSubscription::Following => {
+                                        let mut db = match pool.acquire().await {
+                                            Ok(conn) => conn,
+                                            Err(err) => {
+                                                log::error!(
+                                                    "Couldn't acquire database connection: {}",
+                                                    err
+                                                );
+                                                continue;
+                                            }
+                                        };
+                                        match User::get_following_ids(session.user.id, &mut db)
+                                            .await
+                                        {
+                                            Ok(following) => Some(following.into_iter().collect()),
+                                            Err(err) => {
+                                                log::error!(
+                                                    "Failed to get followed users: {}",
+                                                    err
+                                                );
+                                                continue;
+                                            }
+                                        }
+                                    }
+                                };
+                                registration.update(session.subscriptions());
+                            }
+                            Ok(ClientPayload::UpdatePresence { status_type, text }) => {
+                                let session = session.lock().await;
+                                let session = match session.as_ref() {
+                                    Some(session) => session,
+                                    None => continue,
+                                };
+                                let mut db = match pool.acquire().await {
+                                    Ok(conn) => conn,
+                                    Err(err) => {
+                                        log::error!(
+                                            "Couldn't acquire database connection: {}",
+                                            err
+                                        );
+                                        continue;
+                                    }
+                                };
+                                // Server errors are already logged when they happen.
+                                if let Err(err) = presence::update_status(
+                                    session.user.id,
+                                    Status { status_type, text },
+                                    &mut db,
+                                    &mut *cache.lock().await,
+                                )
+                                .await
+                                {
+                                    send_payload(&tx, &ServerPayload::Error(err)).await;
+                                }
+                            }
+                            Ok(ClientPayload::UpdateActivity { idle }) => {
+                                let mut session = session.lock().await;
+                                let session = match session.as_mut() {
+                                    Some(session) if session.idle != idle => session,
+                                    _ => continue,
+                                };
+                                let mut db = match pool.acquire().await {
+                                    Ok(conn) => conn,
+                                    Err(err) => {
+                                        log::error!(
+                                            "Couldn't acquire database connection: {}",
+                                            err
+                                        );
+                                        continue;
+                                    }
+                                };
+                                session.idle = idle;
+                                presence::set_idle(
+                                    session.user.id,
+                                    idle,
+                                    &mut db,
+                                    &mut *cache.lock().await,
+                                )
+                                .await;
+                            }
+                            Ok(ClientPayload::Typing { channel_id }) => {
+                                // The session isn't kept locked while the permissions are checked.
+                                let user_id = match session.lock().await.as_ref() {
+                                    Some(session) if session.channels.contains(&channel_id) => {
+                                        session.user.id
+                                    }
+                                    _ => continue,
+                                };
+                                let mut typing_rate_limiter = RateLimiter::new(
+                                    Arc::clone(&cache),
+                                    format!("typing:{}", user_id),
+                                    Duration::from_secs(
+                                        conf.pandemonium.typing_rate_limit.reset_after as u64,
+                                    ),
+                                    conf.pandemonium.typing_rate_limit.limit,
+                                );
+                                // Typing indicators are only cosmetic, extra ones are just dropped.
+                                if typing_rate_limiter.process_rate_limit().await.is_err() {
+                                    continue;
+                                }
+                                let mut db = match pool.acquire().await {
+                                    Ok(conn) => conn,
+                                    Err(err) => {
+                                        log::error!(
+                                            "Couldn't acquire database connection: {}",
+                                            err
+                                        );
+                                        continue;
+                                    }
+                                };
+                                if let Err(err) = Permissions::require_channel(
+                                    channel_id,
+                                    user_id,
+                                    Permissions::SEND_MESSAGES,
+                                    &mut db,
+                                )
+                                .await
+                                {
+                                    log::debug!("Couldn't start typing: {}", err);
+                                    continue;
+                                }
+                                let timestamp = SystemTime::now()
+                                    .duration_since(SystemTime::UNIX_EPOCH)
+                                    .unwrap_or(Duration::ZERO)
+                                    .as_secs();
+                                if let Err(err) = Event::new(
+                                    ServerPayload::TypingStart {
+                                        channel_id,
+                                        user_id,
+                                        timestamp,
+                                    },
+                                    vec![EventTarget::Channel(channel_id)],
+                                )
+                                .publish(&mut *cache.lock().await)
+                                .await
+                                {
+                                    log::error!("Failed to publish TYPING_START: {}", err);
+                                }
                             }
                             Ok(ClientPayload::Authenticate(token)) => {
                                 let mut session = session.lock().await;
@@ -169,111 +1207,130 @@ pub async fn handle_connection(
                                     }
                                 };
                                 let user_session =
-                                    match Session::validate_token(&token, &secret, &mut db).await {
+                                    match validate_token(&token, &secret, &conf, &mut db).await {
                                         Ok(session) => session,
-                                        Err(_) => return "Invalid credentials".to_string(),
+                                        Err(reason) => return reason,
                                     };
                                 let mut cache = cache.lock().await;
-                                let sessions: u32 = match cache
-                                    .incr(format!("session:{}", user_session.user_id), 1)
-                                    .await
+                                let session_data =
+                                    match start_session(user_session, &mut db, &mut cache).await {
+                                        Ok(session_data) => session_data,
+                                        Err(reason) => return reason,
+                                    };
+                                let users = match get_online_users(
+                                    &session_data,
+                                    &mut db,
+                                    &mut cache,
+                                )
+                                .await
                                 {
-                                    Ok(sessions) => sessions,
+                                    Ok(users) => users,
+                                    Err(reason) => return reason,
+                                };
+                                if let Err(err) = resume::create(
+                                    session_data.gateway_session_id,
+                                    session_data.user.id,
+                                    session_data.session.id,
+                                    &mut replays,
+                                )
+                                .await
+                                {
+                                    log::error!("Failed to create replay buffer: {}", err);
+                                    return "Failed to connect user".to_string();
+                                }
+                                send_payload(
+                                    &tx,
+                                    &ServerPayload::Authenticated {
+                                        user: session_data.user.clone(),
+                                        users,
+                                        session_id: session_data.gateway_session_id,
+                                    },
+                                )
+                                .await;
+                                registration.update(session_data.subscriptions());
+                                *session = Some(session_data);
+                            }
+                            Ok(ClientPayload::Resume {
+                                token,
+                                session_id,
+                                seq,
+                            }) => {
+                                let mut session = session.lock().await;
+                                if session.is_some() {
+                                    continue;
+                                }
+                                let mut db = match pool.acquire().await {
+                                    Ok(conn) => conn,
                                     Err(err) => {
                                         log::error!(
-                                            "Failed to increment user active session counter: {}",
+                                            "Couldn't acquire database connection: {}",
                                             err
                                         );
-                                        return "Failed to connect user".to_string();
+                                        return "Server failed to authenticate client".to_string();
                                     }
                                 };
-                                if sessions == 1 {
-                                    if let Err(err) = cache
-                                        .sadd::<_, _, ()>("sessions", user_session.user_id)
-                                        .await
-                                    {
-                                        log::error!("Failed to add user to online users: {}", err);
-                                        return "Failed to connect user".to_string();
+                                let user_session =
+                                    match validate_token(&token, &secret, &conf, &mut db).await {
+                                        Ok(session) => session,
+                                        Err(reason) => return reason,
+                                    };
+                                let mut cache = cache.lock().await;
+                                let resumable = match resume::get(session_id, &mut replays).await {
+                                    Ok(Some(buffer)) => {
+                                        buffer.user_id == user_session.user_id
+                                            && buffer.session_id == user_session.id
+                                            && buffer.missed_since(seq).is_some()
                                     }
-                                }
-                                let user = match User::get(
-                                    user_session.user_id,
-                                    Some(user_session.user_id),
-                                    &mut db,
-                                    &mut *cache,
-                                )
-                                .await
-                                {
-                                    Ok(user) => user,
+                                    Ok(None) => false,
                                     Err(err) => {
-                                        log::error!("Failed to get user info: {}", err);
-                                        return "Failed to connect user".to_string();
+                                        log::error!("Failed to get replay buffer: {}", err);
+                                        false
                                     }
                                 };
-                                if user.status.status_type != StatusType::Offline {
-                                    if let Err(err) = cache
-                                        .publish::<_, _, ()>(
-                                            "eludris-events",
-                                            serde_json::to_string(&ServerPayload::PresenceUpdate {
-                                                user_id: user_session.user_id,
-                                                // I don't like this either
-                                                status: user.status.clone(),
-                                            })
-                                            .expect("Couldn't serialize PRESENCE_UPDATE event"),
-                                        )
-                                        .await
-                                    {
-                                        log::error!("Failed to publish PRESENCE_UPDATE: {}", err);
-                                        return "Failed to connect user".to_string();
-                                    };
+                                if !resumable {
+                                    send_payload(&tx, &ServerPayload::InvalidSession).await;
+                                    continue;
                                 }
-                                let users: Vec<User> = match cache
-                                    .smembers::<_, Vec<u64>>("sessions")
+                                let mut session_data =
+                                    match start_session(user_session, &mut db, &mut cache).await {
+                                        Ok(session_data) => session_data,
+                                        Err(reason) => return reason,
+                                    };
+                                let nonce = rand::random();
+                                session_data.gateway_session_id = session_id;
+                                session_data.resume = Some(PendingResume {
+                                    seq,
+                                    nonce,
+                                    deadline: Instant::now() + HANDOFF_TIMEOUT,
+                                    started: false,
+                                    queued: vec![],
+                                });
+                                // The connection has to receive its own event to know which
+                                // payloads its session's previous connection still dispatches.
+                                registration.update(session_data.subscriptions());
+                                // The session's previous connection stops dispatching payloads
+                                // once it receives this, handing the session over.
+                                if let Err(err) = cache
+                                    .publish::<_, _, ()>(
+                                        GATEWAY_CHANNEL,
+                                        serde_json::to_string(&GatewayEvent::Resume {
+                                            user_id: session_data.user.id,
+                                            session_id,
+                                            nonce,
+                                        })
+                                        .expect("Couldn't serialize RESUME event"),
+                                    )
                                     .await
                                 {
-                                    Ok(users) => {
-                                        // TODO: try_join_all (please)
-                                        let mut user_datas = vec![];
-                                        for user_id in users.into_iter().filter(|u| u != &user.id) {
-                                            match User::get(user_id, None, &mut db, &mut *cache)
-                                                .await
-                                            {
-                                                Ok(user) => {
-                                                    if user.status.status_type
-                                                        != StatusType::Offline
-                                                    {
-                                                        user_datas.push(user);
-                                                    }
-                                                }
-                                                Err(err) => {
-                                                    log::error!(
-                                                        "Failed to get online users: {}",
-                                                        err
-                                                    );
-                                                    return "Failed to connect user".to_string();
-                                                }
-                                            }
-                                        }
-                                        user_datas
-                                    }
-                                    Err(err) => {
-                                        log::error!("Failed to get online users: {}", err);
-                                        return "Failed to connect user".to_string();
-                                    }
-                                };
-                                let payload = ServerPayload::Authenticated { user, users };
-                                send_payload(&tx, &payload).await;
-                                if let ServerPayload::Authenticated { user, .. } = payload {
-                                    *session = Some(SessionData {
-                                        session: user_session,
-                                        user,
-                                    });
+                                    log::error!("Failed to publish RESUME: {}", err);
+                                    return "Failed to resume session".to_string();
                                 }
+                                *session = Some(session_data);
                             }
-                            _ => log::debug!("Unknown gateway payload: {}", message),
+                            Err(err) => log::debug!("Unknown gateway payload: {}", err),
                         }
                     }
-                    _ => log::debug!("Unsupported Gateway message type."),
+                    None => log::debug!("Unsupported Gateway message type."),
                 },
                 Err(_) => return "Server failed to receive payload".to_string(),
             }
@@ -282,42 +1339,49 @@ pub async fn handle_connection(
     };
 
     let handle_events = async {
-        pubsub
-            .into_on_message()
-            .for_each(|msg| async {
-                let mut session = session.lock().await;
-                if session.is_none() {
-                    return;
-                }
-                let mut session = session.as_mut().unwrap();
-                match deserialize_message(msg) {
-                    Ok(ServerPayload::PresenceUpdate { user_id, status }) => {
-                        if user_id == session.user.id {
-                            session.user.status = status;
-                        } else {
-                            send_payload(&tx, &ServerPayload::PresenceUpdate { user_id, status })
-                                .await;
-                        }
-                    }
-                    Ok(ServerPayload::UserUpdate(mut user)) => {
-                        if user.id == session.user.id {
-                            session.user = user;
-                        } else {
-                            if user.status.status_type == StatusType::Offline {
-                                user.status.text = None;
+        let mut replays = replays.clone();
+        loop {
+            let deadline = session
+                .lock()
+                .await
+                .as_ref()
+                .and_then(|s| s.resume.as_ref())
+                .map(|r| r.deadline);
+            let delivery = match deadline {
+                Some(deadline) => match timeout_at(deadline, events.recv()).await {
+                    Ok(delivery) => delivery,
+                    // The session's previous connection never handed it over
+                    Err(_) => {
+                        let mut session = session.lock().await;
+                        if let Some(session) = session.as_mut() {
+                            if let Some(reason) =
+                                session.finish_resume(Some(&tx), &mut replays).await
+                            {
+                                return reason;
                             }
-                            user.email = None;
-                            user.verified = None;
-                            send_payload(&tx, &ServerPayload::UserUpdate(user)).await;
                         }
+                        continue;
                     }
-                    Ok(msg) => {
-                        send_payload(&tx, &msg).await;
-                    }
-                    Err(err) => log::warn!("Failed to deserialize event payload: {}", err),
-                }
-            })
-            .await;
+                },
+                None => events.recv().await,
+            };
+            let delivery = match delivery {
+                Some(delivery) => delivery,
+                None => break,
+            };
+            let mut session = session.lock().await;
+            let session = match session.as_mut() {
+                Some(session) => session,
+                None => continue,
+            };
+            if let Some(reason) =
+                handle_event(session, delivery, Some(&tx), &cache, &mut replays, &pool).await
+            {
+                return reason;
+            }
+            registration.update(session.subscriptions());
+        }
+        "Server Error".to_string()
     };
 
     tokio::select! {
@@ -328,15 +1392,18 @@ pub async fn handle_connection(
         reason = handle_rx => {
             close_socket(tx, rx, CloseFrame { code: CloseCode::Error, reason: Cow::Owned(reason) }, rl_address).await;
         },
-        _ = handle_events => {
-            close_socket(tx, rx, CloseFrame { code: CloseCode::Error, reason: Cow::Borrowed("Server Error") }, rl_address).await;
+        reason = handle_events => {
+            close_socket(tx, rx, CloseFrame { code: CloseCode::Error, reason: Cow::Owned(reason) }, rl_address).await;
         },
     };
 
-    let mut cache = cache.lock().await;
-    let session = session.lock().await;
-    if session.is_some() {
-        let session = session.as_ref().unwrap();
+    let mut session = session.lock().await;
+    let session = match session.as_mut() {
+        Some(session) => session,
+        None => return,
+    };
+    let sessions = {
+        let mut cache = cache.lock().await;
         let sessions: u32 = match cache.decr(format!("session:{}", session.user.id), 1).await {
             Ok(sessions) => sessions,
             Err(err) => {
@@ -348,36 +1415,65 @@ pub async fn handle_connection(
             if let Err(err) = cache.srem::<_, _, ()>("sessions", session.user.id).await {
                 log::error!("Failed to remove user from online users: {}", err);
             }
+            presence::clear(session.user.id, &mut cache).await;
         }
         if session.user.status.status_type != StatusType::Offline {
-            if let Err(err) = cache
-                .publish::<_, _, ()>(
-                    "eludris-events",
-                    serde_json::to_string(&ServerPayload::PresenceUpdate {
-                        user_id: session.user.id,
-                        status: Status {
-                            status_type: StatusType::Offline,
-                            text: None,
-                        },
-                    })
-                    .expect("Couldn't serialize PRESENCE_UPDATE event"),
-                )
-                .await
+            if let Err(err) = Event::new(
+                ServerPayload::PresenceUpdate {
+                    user_id: session.user.id,
+                    status: Status {
+                        status_type: StatusType::Offline,
+                        text: None,
+                    },
+                },
+                vec![
+                    EventTarget::User(session.user.id),
+                    EventTarget::Presence(session.user.id),
+                ],
+            )
+            .publish(&mut *cache)
+            .await
             {
                 log::error!("Failed to publish PRESENCE_UPDATE: {}", err);
             };
         }
+        sessions
+    };
+    // The user's other connections might all be inactive now.
+    if sessions > 0 {
+        match pool.acquire().await {
+            Ok(mut db) => {
+                let mut cache = cache.lock().await;
+                if session.idle {
+                    presence::set_idle(session.user.id, false, &mut db, &mut cache).await;
+                } else {
+                    presence::refresh(session.user.id, &mut db, &mut cache).await;
+                }
+            }
+            Err(err) => log::error!("Couldn't acquire database connection: {}", err),
+        }
+    }
+    if session.resumable {
+        linger(
+            session,
+            &registration,
+            &mut events,
+            &cache,
+            &mut replays,
+            &pool,
+        )
+        .await;
     }
 }
 
 async fn close_socket(
-    tx: Arc<Mutex<SplitSink<WebSocketStream<TcpStream>, WebSocketMessage>>>,
+    tx: WebSocketSender,
     rx: SplitStream<WebSocketStream<TcpStream>>,
     frame: CloseFrame<'_>,
     rl_address: IpAddr,
 ) {
     let tx = Arc::try_unwrap(tx).expect("Couldn't obtain tx from MutexLock");
-    let tx = tx.into_inner();
+    let tx = tx.into_inner().into_inner();
 
     if let Err(err) = tx
         .reunite(rx)
@@ -389,18 +1485,15 @@ async fn close_socket(
     }
 }
 
-async fn send_payload(
-    tx: &Arc<Mutex<SplitSink<WebSocketStream<TcpStream>, WebSocketMessage>>>,
-    payload: &ServerPayload,
-) {
-    if let Err(err) = tx
-        .lock()
-        .await
-        .send(WebSocketMessage::Text(
-            serde_json::to_string(payload).unwrap(),
-        ))
-        .await
-    {
+async fn send_payload<T: Serialize>(tx: &WebSocketSender, payload: &T) {
+    if let Err(err) = tx.lock().await.send(payload).await {
+        log::error!("Could not send payload: {}", err);
+    }
+}
+
+/// Send a payload which was already serialized as JSON.
+async fn send_message(tx: &WebSocketSender, message: String) {
+    if let Err(err) = tx.lock().await.send_json(message).await {
         log::error!("Could not send payload: {}", err);
     }
 }
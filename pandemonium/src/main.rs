@@ -1,14 +1,23 @@
+mod dispatch;
+mod encoding;
 mod handle_connection;
+mod presence;
 mod rate_limit;
+mod resume;
 mod utils;
+mod visibility;
 
 #[cfg(test)]
 use std::sync::Once;
 use std::{env, sync::Arc};
 
-use anyhow::Context;
+use anyhow::{bail, Context};
+use dispatch::Dispatcher;
 use sqlx::{pool::PoolOptions, Pool, Postgres};
-use todel::{models::Secret, Conf};
+use todel::{
+    models::{Secret, EVENTS_CHANNEL, SESSION_REVOCATION_CHANNEL},
+    Conf,
+};
 use tokio::{net::TcpListener, sync::Mutex, task};
 
 #[cfg(test)]
@@ -45,6 +54,12 @@ async fn main() -> Result<(), anyhow::Error> {
             .await
             .context("Couldn't get an async connection to redis")?,
     ));
+    // Replay buffers are written to for every dispatched payload, so they get their own
+    // multiplexed connection instead of waiting on the one the rest of the gateway shares.
+    let replays = client
+        .get_multiplexed_tokio_connection()
+        .await
+        .context("Couldn't get a multiplexed connection to redis")?;
     // the max connections is to stay consistent with oprish and effis even though postgresql
     // will most likely not support this many connections at once.
     let pool: Pool<Postgres> = PoolOptions::new()
@@ -59,36 +74,50 @@ async fn main() -> Result<(), anyhow::Error> {
 
     let conf = Arc::new(Conf::new_from_env()?);
 
+    // Every connection of this process gets its events routed to it through the same
+    // subscription.
+    let mut pubsub = client
+        .get_async_connection()
+        .await
+        .context("Couldn't get an async connection to redis")?
+        .into_pubsub();
+    for channel in [
+        EVENTS_CHANNEL,
+        resume::GATEWAY_CHANNEL,
+        SESSION_REVOCATION_CHANNEL,
+    ] {
+        pubsub
+            .subscribe(channel)
+            .await
+            .with_context(|| format!("Couldn't subscribe to {}", channel))?;
+    }
+    let dispatcher = Arc::new(Dispatcher::default());
+
     let socket = TcpListener::bind(&gateway_address)
         .await
         .with_context(|| format!("Couldn't start a websocket on {}", gateway_address))?;
 
     log::info!("Gateway started at {}", gateway_address);
 
-    while let Ok((stream, addr)) = socket.accept().await {
-        log::debug!("New connection on ip {}", addr);
-        let mut pubsub = match client.get_async_connection().await {
-            Ok(connection) => connection.into_pubsub(),
-            Err(err) => {
-                log::warn!("Couldn't get an async connection to redis, {:?}", err);
-                continue;
-            }
-        };
-        if let Err(err) = pubsub.subscribe("eludris-events").await {
-            log::warn!("Couldn't subscribe to eludris-events: {:?}", err);
-            continue;
+    let accept_connections = async {
+        while let Ok((stream, addr)) = socket.accept().await {
+            log::debug!("New connection on ip {}", addr);
+            task::spawn(handle_connection::handle_connection(
+                stream,
+                addr,
+                Arc::clone(&cache),
+                replays.clone(),
+                Arc::clone(&dispatcher),
+                Arc::clone(&pool),
+                Arc::clone(&conf),
+                Arc::clone(&secret),
+            ));
+            log::trace!("Spawned connection handling task for {}", addr);
         }
-        task::spawn(handle_connection::handle_connection(
-            stream,
-            addr,
-            Arc::clone(&cache),
-            pubsub,
-            Arc::clone(&pool),
-            Arc::clone(&conf),
-            Arc::clone(&secret),
-        ));
-        log::trace!("Spawned connection handling task for {}", addr);
-    }
+    };
 
-    Ok(())
+    tokio::select! {
+        _ = accept_connections => Ok(()),
+        _ = dispatcher.run(pubsub) => bail!("Lost the redis event subscription"),
+    }
 }
@@ -0,0 +1,134 @@
+use redis::{aio::Connection, AsyncCommands};
+use sqlx::{pool::PoolConnection, Postgres};
+use todel::models::{ErrorResponse, Event, EventTarget, ServerPayload, Status, StatusType, User};
+
+/// The redis set of the users who automatically appear idle.
+const IDLE_USERS_KEY: &str = "idle-users";
+
+fn idle_sessions_key(user_id: u64) -> String {
+    format!("idle-sessions:{}", user_id)
+}
+
+/// Publish a user's new presence to the users who can see it.
+async fn publish(user_id: u64, status: Status, cache: &mut Connection) {
+    if let Err(err) = Event::new(
+        ServerPayload::PresenceUpdate { user_id, status },
+        vec![EventTarget::User(user_id), EventTarget::Presence(user_id)],
+    )
+    .publish(cache)
+    .await
+    {
+        log::error!("Failed to publish PRESENCE_UPDATE: {}", err);
+    }
+}
+
+/// Change a user's status and publish it, they keep appearing idle if they change it to `ONLINE`
+/// while all of their connections are inactive.
+///
+/// Returns the error to send back to the client if the status couldn't be changed.
+pub async fn update_status(
+    user_id: u64,
+    mut status: Status,
+    db: &mut PoolConnection<Postgres>,
+    cache: &mut Connection,
+) -> Result<(), ErrorResponse> {
+    User::update_status(user_id, &status, db).await?;
+    if status.status_type == StatusType::Online {
+        match cache.sismember(IDLE_USERS_KEY, user_id).await {
+            Ok(true) => status.status_type = StatusType::Idle,
+            Ok(false) => {}
+            Err(err) => log::error!("Failed to determine if user is idle: {}", err),
+        }
+    }
+    publish(user_id, status, cache).await;
+    Ok(())
+}
+
+/// Keep track of one of a user's connections becoming inactive or active again.
+pub async fn set_idle(
+    user_id: u64,
+    idle: bool,
+    db: &mut PoolConnection<Postgres>,
+    cache: &mut Connection,
+) {
+    let result: redis::RedisResult<()> = match idle {
+        true => cache.incr(idle_sessions_key(user_id), 1).await,
+        false => cache.decr(idle_sessions_key(user_id), 1).await,
+    };
+    if let Err(err) = result {
+        log::error!("Failed to update user idle session counter: {}", err);
+        return;
+    }
+    refresh(user_id, db, cache).await;
+}
+
+/// Stop a user from appearing idle when a new connection of theirs starts, which publishes their
+/// presence itself.
+pub async fn unmark_idle(user_id: u64, cache: &mut Connection) {
+    if let Err(err) = cache.srem::<_, _, ()>(IDLE_USERS_KEY, user_id).await {
+        log::error!("Failed to remove user from idle users: {}", err);
+    }
+}
+
+/// Stop keeping track of a user's inactive connections once all of them closed.
+pub async fn clear(user_id: u64, cache: &mut Connection) {
+    if let Err(err) = redis::pipe()
+        .del(idle_sessions_key(user_id))
+        .ignore()
+        .srem(IDLE_USERS_KEY, user_id)
+        .ignore()
+        .query_async::<_, ()>(cache)
+        .await
+    {
+        log::error!("Failed to clear user idle sessions: {}", err);
+    }
+}
+
+/// Mark a user as idle once every one of their connections is inactive and back as online once
+/// one of them isn't, publishing their presence if it changed and they're `ONLINE`.
+pub async fn refresh(user_id: u64, db: &mut PoolConnection<Postgres>, cache: &mut Connection) {
+    let (sessions, idle_sessions): (Option<u32>, Option<u32>) = match redis::pipe()
+        .get(format!("session:{}", user_id))
+        .get(idle_sessions_key(user_id))
+        .query_async(cache)
+        .await
+    {
+        Ok(counters) => counters,
+        Err(err) => {
+            log::error!("Failed to get user session counters: {}", err);
+            return;
+        }
+    };
+    let sessions = sessions.unwrap_or(0);
+    let idle = sessions > 0 && idle_sessions.unwrap_or(0) >= sessions;
+    let changed: redis::RedisResult<u32> = match idle {
+        true => cache.sadd(IDLE_USERS_KEY, user_id).await,
+        false => cache.srem(IDLE_USERS_KEY, user_id).await,
+    };
+    match changed {
+        Ok(0) => return,
+        Ok(_) => {}
+        Err(err) => {
+            log::error!("Failed to update idle users: {}", err);
+            return;
+        }
+    }
+    let user = match User::get(user_id, Some(user_id), db, cache).await {
+        Ok(user) => user,
+        Err(err) => {
+            log::error!("Failed to get user info: {}", err);
+            return;
+        }
+    };
+    if user.status.status_type == StatusType::Online {
+        let status = Status {
+            status_type: if idle {
+                StatusType::Idle
+            } else {
+                StatusType::Online
+            },
+            text: user.status.text,
+        };
+        publish(user_id, status, cache).await;
+    }
+}
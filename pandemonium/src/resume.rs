@@ -0,0 +1,188 @@
+use redis::{aio::MultiplexedConnection, AsyncCommands, RedisResult};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// The redis channel pandemonium uses to hand gateway sessions over between connections.
+pub const GATEWAY_CHANNEL: &str = "eludris-gateway";
+
+/// How long a gateway session can be resumed for after its connection closes.
+pub const RESUME_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// How long a resuming connection waits for the session's previous connection to hand it over
+/// before replaying its payloads anyway.
+pub const HANDOFF_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The maximum amount of payloads kept in a gateway session's replay buffer.
+const REPLAY_BUFFER_SIZE: isize = 500;
+
+/// Internal events used to hand a gateway session over to the connection resuming it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[serde(tag = "op")]
+pub enum GatewayEvent {
+    /// A connection started resuming a gateway session, the nonce is used by the connection to
+    /// recognise its own event.
+    Resume {
+        user_id: u64,
+        session_id: u64,
+        nonce: u64,
+    },
+    /// The previous connection of a gateway session stopped dispatching payloads to it.
+    HandOff { user_id: u64, session_id: u64 },
+}
+
+impl GatewayEvent {
+    /// Get the ID of the user whose gateway session the event is about.
+    pub fn user_id(&self) -> u64 {
+        match self {
+            Self::Resume { user_id, .. } | Self::HandOff { user_id, .. } => *user_id,
+        }
+    }
+}
+
+/// The replay buffer of a gateway session, containing the last payloads dispatched to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayBuffer {
+    /// The ID of the session's user.
+    pub user_id: u64,
+    /// The ID of the [`todel::models::Session`] the gateway session was authenticated with.
+    pub session_id: u64,
+    /// The sequence number of the last payload dispatched to the session.
+    pub seq: u64,
+    /// The serialized payloads, from oldest to newest.
+    pub payloads: Vec<String>,
+}
+
+impl ReplayBuffer {
+    /// Get the payloads dispatched after `seq`, this is `None` if some of them were already
+    /// dropped from the buffer.
+    pub fn missed_since(self, seq: u64) -> Option<Vec<String>> {
+        let first = (self.seq + 1).saturating_sub(self.payloads.len() as u64);
+        if seq > self.seq || seq + 1 < first {
+            return None;
+        }
+        Some(
+            self.payloads
+                .into_iter()
+                .skip((seq + 1 - first) as usize)
+                .collect(),
+        )
+    }
+}
+
+fn session_key(id: u64) -> String {
+    format!("gateway-session:{}", id)
+}
+
+fn payloads_key(id: u64) -> String {
+    format!("gateway-session:{}:payloads", id)
+}
+
+/// Start keeping track of a new gateway session.
+pub async fn create(
+    id: u64,
+    user_id: u64,
+    session_id: u64,
+    cache: &mut MultiplexedConnection,
+) -> RedisResult<()> {
+    redis::pipe()
+        .atomic()
+        .hset_multiple(
+            session_key(id),
+            &[("user_id", user_id), ("session_id", session_id), ("seq", 0)],
+        )
+        .ignore()
+        .expire(session_key(id), RESUME_TIMEOUT.as_secs() as usize)
+        .ignore()
+        .query_async(cache)
+        .await
+}
+
+/// Add a dispatched payload to a gateway session's replay buffer.
+pub async fn push(
+    id: u64,
+    seq: u64,
+    payload: &str,
+    cache: &mut MultiplexedConnection,
+) -> RedisResult<()> {
+    redis::pipe()
+        .atomic()
+        .rpush(payloads_key(id), payload)
+        .ignore()
+        .ltrim(payloads_key(id), -REPLAY_BUFFER_SIZE, -1)
+        .ignore()
+        .hset(session_key(id), "seq", seq)
+        .ignore()
+        .expire(session_key(id), RESUME_TIMEOUT.as_secs() as usize)
+        .ignore()
+        .expire(payloads_key(id), RESUME_TIMEOUT.as_secs() as usize)
+        .ignore()
+        .query_async(cache)
+        .await
+}
+
+/// Keep a gateway session from expiring while its connection is still alive.
+pub async fn refresh(id: u64, cache: &mut MultiplexedConnection) -> RedisResult<()> {
+    redis::pipe()
+        .expire(session_key(id), RESUME_TIMEOUT.as_secs() as usize)
+        .ignore()
+        .expire(payloads_key(id), RESUME_TIMEOUT.as_secs() as usize)
+        .ignore()
+        .query_async(cache)
+        .await
+}
+
+/// Stop keeping track of a gateway session, making it impossible to resume.
+pub async fn delete(id: u64, cache: &mut MultiplexedConnection) -> RedisResult<()> {
+    cache.del(&[session_key(id), payloads_key(id)]).await
+}
+
+/// Get a gateway session's replay buffer if it hasn't expired yet.
+pub async fn get(id: u64, cache: &mut MultiplexedConnection) -> RedisResult<Option<ReplayBuffer>> {
+    let ((user_id, session_id, seq), payloads): ((Option<u64>, Option<u64>, Option<u64>), _) =
+        redis::pipe()
+            .atomic()
+            .hget(session_key(id), &["user_id", "session_id", "seq"])
+            .lrange(payloads_key(id), 0, -1)
+            .query_async(cache)
+            .await?;
+    Ok(match (user_id, session_id, seq) {
+        (Some(user_id), Some(session_id), Some(seq)) => Some(ReplayBuffer {
+            user_id,
+            session_id,
+            seq,
+            payloads,
+        }),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ReplayBuffer;
+
+    #[test]
+    fn missed_since() {
+        let buffer = ReplayBuffer {
+            user_id: 48615849987333,
+            session_id: 2312155037697,
+            seq: 5,
+            payloads: vec!["3".to_string(), "4".to_string(), "5".to_string()],
+        };
+        assert_eq!(
+            buffer.clone().missed_since(2),
+            Some(vec!["3".to_string(), "4".to_string(), "5".to_string()])
+        );
+        assert_eq!(buffer.clone().missed_since(4), Some(vec!["5".to_string()]));
+        assert_eq!(buffer.clone().missed_since(5), Some(vec![]));
+        assert_eq!(buffer.clone().missed_since(1), None);
+        assert_eq!(buffer.clone().missed_since(6), None);
+
+        let buffer = ReplayBuffer {
+            payloads: vec![],
+            seq: 0,
+            ..buffer
+        };
+        assert_eq!(buffer.missed_since(0), Some(vec![]));
+    }
+}
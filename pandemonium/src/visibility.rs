@@ -0,0 +1,101 @@
+use std::collections::{HashMap, HashSet};
+
+use sqlx::{Pool, Postgres};
+use todel::models::{Permissions, ServerPayload};
+use tokio::sync::OnceCell;
+
+/// The channels of a community, resolved along with the ones each user can see.
+#[derive(Debug)]
+pub struct CommunityChannels {
+    /// The IDs of all of the community's channels.
+    pub channels: HashSet<u64>,
+    /// The IDs of the channels each member can see, users who aren't members are left out.
+    pub visible: HashMap<u64, HashSet<u64>>,
+}
+
+/// Which of a community's channels the users an event is delivered to can see after it.
+///
+/// It's shared by all of the connections the event is delivered to and resolved by the first one
+/// which handles the event, so the permissions are only queried once per event.
+#[derive(Debug)]
+pub struct CommunityVisibility {
+    community_id: u64,
+    user_ids: Vec<u64>,
+    channels: OnceCell<Option<CommunityChannels>>,
+}
+
+impl CommunityVisibility {
+    pub fn new(community_id: u64, user_ids: Vec<u64>) -> Self {
+        Self {
+            community_id,
+            user_ids,
+            channels: OnceCell::new(),
+        }
+    }
+
+    /// Get the community whose channels an event might change the visibility of, along with the
+    /// only user it changes it for if there is one.
+    pub fn changed_by(payload: &ServerPayload) -> Option<(u64, Option<u64>)> {
+        match payload {
+            ServerPayload::ChannelCreate(channel) | ServerPayload::ChannelUpdate(channel) => {
+                Some((channel.community_id, None))
+            }
+            ServerPayload::RoleUpdate(role) => Some((role.community_id, None)),
+            ServerPayload::RoleDelete { community_id, .. } => Some((*community_id, None)),
+            ServerPayload::CommunityMemberJoin { community_id, user } => {
+                Some((*community_id, Some(user.id)))
+            }
+            ServerPayload::CommunityMemberLeave {
+                community_id,
+                user_id,
+            }
+            | ServerPayload::CommunityMemberUpdate {
+                community_id,
+                user_id,
+                ..
+            } => Some((*community_id, Some(*user_id))),
+            _ => None,
+        }
+    }
+
+    pub fn community_id(&self) -> u64 {
+        self.community_id
+    }
+
+    /// Resolve the community's channels if no other connection did yet.
+    ///
+    /// Returns `None` if they couldn't be resolved.
+    pub async fn resolve(&self, pool: &Pool<Postgres>) -> Option<&CommunityChannels> {
+        self.channels
+            .get_or_init(|| async {
+                let mut db = match pool.acquire().await {
+                    Ok(conn) => conn,
+                    Err(err) => {
+                        log::error!("Couldn't acquire database connection: {}", err);
+                        return None;
+                    }
+                };
+                match Permissions::get_visible_community_channels(
+                    self.community_id,
+                    &self.user_ids,
+                    &mut db,
+                )
+                .await
+                {
+                    Ok((channels, visible)) => Some(CommunityChannels {
+                        channels: channels.into_iter().collect(),
+                        visible: visible
+                            .into_iter()
+                            .map(|(id, channels)| (id, channels.into_iter().collect()))
+                            .collect(),
+                    }),
+                    Err(err) => {
+                        log::error!("Failed to get visible community channels: {}", err);
+                        None
+                    }
+                }
+            })
+            .await
+            .as_ref()
+    }
+}
@@ -141,7 +141,7 @@ impl Conf {
             bail!("Message limit can not be less than 1024 characters");
         }
         validate_rate_limit_limits!(self.oprish.rate_limits, get_instance_info, create_message);
-        validate_rate_limit_limits!(self.pandemonium, rate_limit);
+        validate_rate_limit_limits!(self.pandemonium, rate_limit, typing_rate_limit);
         validate_rate_limit_limits!(self.effis.rate_limits, assets, attachments, fetch_file);
 
         Url::parse(&self.oprish.url)
@@ -241,6 +241,7 @@ mod tests {
                     limit: 10,
                 },
                 url: "wss://foo.bar".to_string(),
+                ..Default::default()
             },
             effis: EffisConf {
                 file_size: 100_000_000,
@@ -337,6 +338,7 @@ mod tests {
         test_limit!(
             conf,
             conf.pandemonium.rate_limit,
+            conf.pandemonium.typing_rate_limit,
             conf.effis.rate_limits.assets,
             conf.effis.rate_limits.attachments,
             conf.effis.rate_limits.fetch_file,
@@ -11,6 +11,12 @@ pub struct OprishConf {
     #[serde(default = "bio_limit_default")]
     pub bio_limit: usize,
     #[serde(default)]
+    pub registration_mode: RegistrationMode,
+    #[serde(default = "session_idle_timeout_default")]
+    pub session_idle_timeout: u64,
+    #[serde(default)]
+    pub session_lifetime: u64,
+    #[serde(default)]
     pub rate_limits: OprishRateLimits,
 }
 
@@ -20,6 +26,9 @@ impl Default for OprishConf {
             url: "https://example.com".to_string(),
             message_limit: message_limit_default(),
             bio_limit: bio_limit_default(),
+            registration_mode: RegistrationMode::default(),
+            session_idle_timeout: session_idle_timeout_default(),
+            session_lifetime: 0,
             rate_limits: OprishRateLimits::default(),
         }
     }
@@ -33,6 +42,32 @@ fn bio_limit_default() -> usize {
     250
 }
 
+fn session_idle_timeout_default() -> u64 {
+    2_592_000 // 30 days
+}
+
+/// Who can register a new account on an instance.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// "INVITE_ONLY"
+/// ```
+#[autodoc(category = "Instance")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RegistrationMode {
+    /// Anyone can register.
+    #[default]
+    Open,
+    /// Only people with a valid [`Invite`] code can register.
+    InviteOnly,
+    /// Nobody can register.
+    Closed,
+}
+
 /// Rate limits that apply to Oprish (The REST API).
 ///
 /// -----
@@ -49,6 +84,10 @@ fn bio_limit_default() -> usize {
 ///     "reset_after": 5,
 ///     "limit": 10
 ///   },
+///   "get_messages": {
+///     "reset_after": 5,
+///     "limit": 5
+///   },
 ///   "create_user": {
 ///   },
 /// }
@@ -62,6 +101,24 @@ pub struct OprishRateLimits {
     /// Rate limits for the [`create_message`] endpoint.
     #[serde(default = "create_message_default")]
     pub create_message: RateLimitConf,
+    /// Rate limits for the [`get_messages`] endpoint.
+    #[serde(default = "get_messages_default")]
+    pub get_messages: RateLimitConf,
+    /// Rate limits for the [`get_mentions`] endpoint.
+    #[serde(default = "get_mentions_default")]
+    pub get_mentions: RateLimitConf,
+    /// Rate limits for the [`edit_message`] endpoint.
+    #[serde(default = "edit_message_default")]
+    pub edit_message: RateLimitConf,
+    /// Rate limits for the [`delete_message`] endpoint.
+    #[serde(default = "delete_message_default")]
+    pub delete_message: RateLimitConf,
+    /// Rate limits for the [`add_reaction`] endpoint.
+    #[serde(default = "add_reaction_default")]
+    pub add_reaction: RateLimitConf,
+    /// Rate limits for the [`remove_reaction`] endpoint.
+    #[serde(default = "remove_reaction_default")]
+    pub remove_reaction: RateLimitConf,
     /// Rate limits for the [`create_user`] endpoint.
     #[serde(default = "create_user_default")]
     pub create_user: RateLimitConf,
@@ -90,6 +147,10 @@ pub struct OprishRateLimits {
     /// Rate limits for the [`reset_password`] enpoint.
     #[serde(default = "reset_password_default")]
     pub reset_password: RateLimitConf,
+    /// Rate limits for the [`enroll_two_factor`], [`confirm_two_factor`] and
+    /// [`disable_two_factor`] endpoints.
+    #[serde(default = "two_factor_default")]
+    pub two_factor: RateLimitConf,
     /// Rate limits for the [`create_session`] endpoint.
     #[serde(default = "create_session_default")]
     pub create_session: RateLimitConf,
@@ -99,6 +160,133 @@ pub struct OprishRateLimits {
     /// Rate limits for the [`delete_session`] endpoint.
     #[serde(default = "delete_session_default")]
     pub delete_session: RateLimitConf,
+    /// Rate limits for the [`delete_other_sessions`] endpoint.
+    #[serde(default = "delete_other_sessions_default")]
+    pub delete_other_sessions: RateLimitConf,
+    /// Rate limits for the [`create_community`] endpoint.
+    #[serde(default = "create_community_default")]
+    pub create_community: RateLimitConf,
+    /// Rate limits for the [`get_community`] endpoint.
+    #[serde(default = "get_community_default")]
+    pub get_community: RateLimitConf,
+    /// Rate limits for the [`update_community`] endpoint.
+    #[serde(default = "update_community_default")]
+    pub update_community: RateLimitConf,
+    /// Rate limits for the [`delete_community`] endpoint.
+    #[serde(default = "delete_community_default")]
+    pub delete_community: RateLimitConf,
+    /// Rate limits for the [`join_community`] endpoint.
+    #[serde(default = "join_community_default")]
+    pub join_community: RateLimitConf,
+    /// Rate limits for the [`leave_community`] endpoint.
+    #[serde(default = "leave_community_default")]
+    pub leave_community: RateLimitConf,
+    /// Rate limits for the [`create_channel`] endpoint.
+    #[serde(default = "create_channel_default")]
+    pub create_channel: RateLimitConf,
+    /// Rate limits for the [`get_channel`] endpoint.
+    #[serde(default = "get_channel_default")]
+    pub get_channel: RateLimitConf,
+    /// Rate limits for the [`update_channel`] endpoint.
+    #[serde(default = "update_channel_default")]
+    pub update_channel: RateLimitConf,
+    /// Rate limits for the [`delete_channel`] endpoint.
+    #[serde(default = "delete_channel_default")]
+    pub delete_channel: RateLimitConf,
+    /// Rate limits for the [`create_role`] endpoint.
+    #[serde(default = "create_role_default")]
+    pub create_role: RateLimitConf,
+    /// Rate limits for the [`get_roles`] endpoint.
+    #[serde(default = "get_roles_default")]
+    pub get_roles: RateLimitConf,
+    /// Rate limits for the [`update_role`] endpoint.
+    #[serde(default = "update_role_default")]
+    pub update_role: RateLimitConf,
+    /// Rate limits for the [`delete_role`] endpoint.
+    #[serde(default = "delete_role_default")]
+    pub delete_role: RateLimitConf,
+    /// Rate limits for the [`edit_member_roles`] endpoint.
+    #[serde(default = "edit_member_roles_default")]
+    pub edit_member_roles: RateLimitConf,
+    /// Rate limits for the [`edit_overwrites`] endpoint.
+    #[serde(default = "edit_overwrites_default")]
+    pub edit_overwrites: RateLimitConf,
+    /// Rate limits for the [`update_slow_mode`] endpoint.
+    #[serde(default = "update_slow_mode_default")]
+    pub update_slow_mode: RateLimitConf,
+    /// Rate limits for the [`mute_member`] and [`unmute_member`] endpoints.
+    #[serde(default = "mute_member_default")]
+    pub mute_member: RateLimitConf,
+    /// Rate limits for the [`get_mutes`] endpoint.
+    #[serde(default = "get_mutes_default")]
+    pub get_mutes: RateLimitConf,
+    /// Rate limits for the [`suspend_user`], [`unsuspend_user`], [`force_verify_user`],
+    /// [`revoke_user_sessions`] and [`reset_username`] endpoints.
+    #[serde(default = "moderate_user_default")]
+    pub moderate_user: RateLimitConf,
+    /// Rate limits for the [`get_audit_log`] endpoint.
+    #[serde(default = "get_audit_log_default")]
+    pub get_audit_log: RateLimitConf,
+    /// Rate limits for the [`create_report`] endpoint.
+    #[serde(default = "create_report_default")]
+    pub create_report: RateLimitConf,
+    /// Rate limits for the [`get_reports`] endpoint.
+    #[serde(default = "get_reports_default")]
+    pub get_reports: RateLimitConf,
+    /// Rate limits for the [`resolve_report`] endpoint.
+    #[serde(default = "resolve_report_default")]
+    pub resolve_report: RateLimitConf,
+    /// Rate limits for the [`open_dm`] endpoint.
+    #[serde(default = "open_dm_default")]
+    pub open_dm: RateLimitConf,
+    /// Rate limits for the [`create_group`] endpoint.
+    #[serde(default = "create_group_default")]
+    pub create_group: RateLimitConf,
+    /// Rate limits for the [`get_dms`] endpoint.
+    #[serde(default = "get_dms_default")]
+    pub get_dms: RateLimitConf,
+    /// Rate limits for the [`edit_recipients`] endpoint.
+    #[serde(default = "edit_recipients_default")]
+    pub edit_recipients: RateLimitConf,
+    /// Rate limits for the [`get_relationships`] endpoint.
+    #[serde(default = "get_relationships_default")]
+    pub get_relationships: RateLimitConf,
+    /// Rate limits for the [`edit_relationships`] endpoint.
+    #[serde(default = "edit_relationships_default")]
+    pub edit_relationships: RateLimitConf,
+    /// Rate limits for the [`follow`] endpoint.
+    #[serde(default = "follow_default")]
+    pub follow: RateLimitConf,
+    /// Rate limits for the [`get_follows`] endpoint.
+    #[serde(default = "get_follows_default")]
+    pub get_follows: RateLimitConf,
+    /// Rate limits for the [`create_post`] endpoint.
+    #[serde(default = "create_post_default")]
+    pub create_post: RateLimitConf,
+    /// Rate limits for the [`get_posts`] endpoint.
+    #[serde(default = "get_posts_default")]
+    pub get_posts: RateLimitConf,
+    /// Rate limits for the [`delete_post`] endpoint.
+    #[serde(default = "delete_post_default")]
+    pub delete_post: RateLimitConf,
+    /// Rate limits for the [`vote`] endpoint.
+    #[serde(default = "vote_default")]
+    pub vote: RateLimitConf,
+    /// Rate limits for the [`create_comment`] endpoint.
+    #[serde(default = "create_comment_default")]
+    pub create_comment: RateLimitConf,
+    /// Rate limits for the [`delete_comment`] endpoint.
+    #[serde(default = "delete_comment_default")]
+    pub delete_comment: RateLimitConf,
+    /// Rate limits for the [`create_invite`] endpoint.
+    #[serde(default = "create_invite_default")]
+    pub create_invite: RateLimitConf,
+    /// Rate limits for the [`get_invites`] endpoint.
+    #[serde(default = "get_invites_default")]
+    pub get_invites: RateLimitConf,
+    /// Rate limits for the [`delete_invite`] endpoint.
+    #[serde(default = "delete_invite_default")]
+    pub delete_invite: RateLimitConf,
 }
 
 impl Default for OprishRateLimits {
@@ -106,6 +294,12 @@ impl Default for OprishRateLimits {
         Self {
             get_instance_info: get_instance_info_default(),
             create_message: create_message_default(),
+            get_messages: get_messages_default(),
+            get_mentions: get_mentions_default(),
+            edit_message: edit_message_default(),
+            delete_message: delete_message_default(),
+            add_reaction: add_reaction_default(),
+            remove_reaction: remove_reaction_default(),
             create_user: create_message_default(),
             verify_user: verify_user_default(),
             get_user: get_user_default(),
@@ -115,9 +309,52 @@ impl Default for OprishRateLimits {
             delete_user: delete_user_default(),
             create_password_reset_code: create_session_default(),
             reset_password: reset_password_default(),
+            two_factor: two_factor_default(),
             create_session: create_session_default(),
             get_sessions: get_sessions_default(),
             delete_session: delete_session_default(),
+            delete_other_sessions: delete_other_sessions_default(),
+            create_community: create_community_default(),
+            get_community: get_community_default(),
+            update_community: update_community_default(),
+            delete_community: delete_community_default(),
+            join_community: join_community_default(),
+            leave_community: leave_community_default(),
+            create_channel: create_channel_default(),
+            get_channel: get_channel_default(),
+            update_channel: update_channel_default(),
+            delete_channel: delete_channel_default(),
+            create_role: create_role_default(),
+            get_roles: get_roles_default(),
+            update_role: update_role_default(),
+            delete_role: delete_role_default(),
+            edit_member_roles: edit_member_roles_default(),
+            edit_overwrites: edit_overwrites_default(),
+            update_slow_mode: update_slow_mode_default(),
+            mute_member: mute_member_default(),
+            get_mutes: get_mutes_default(),
+            moderate_user: moderate_user_default(),
+            get_audit_log: get_audit_log_default(),
+            create_report: create_report_default(),
+            get_reports: get_reports_default(),
+            resolve_report: resolve_report_default(),
+            open_dm: open_dm_default(),
+            create_group: create_group_default(),
+            get_dms: get_dms_default(),
+            edit_recipients: edit_recipients_default(),
+            get_relationships: get_relationships_default(),
+            edit_relationships: edit_relationships_default(),
+            follow: follow_default(),
+            get_follows: get_follows_default(),
+            create_post: create_post_default(),
+            get_posts: get_posts_default(),
+            delete_post: delete_post_default(),
+            vote: vote_default(),
+            create_comment: create_comment_default(),
+            delete_comment: delete_comment_default(),
+            create_invite: create_invite_default(),
+            get_invites: get_invites_default(),
+            delete_invite: delete_invite_default(),
         }
     }
 }
@@ -136,6 +373,48 @@ fn create_message_default() -> RateLimitConf {
     }
 }
 
+fn get_messages_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 5,
+    }
+}
+
+fn get_mentions_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 5,
+    }
+}
+
+fn edit_message_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 5,
+    }
+}
+
+fn delete_message_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 10,
+    }
+}
+
+fn add_reaction_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 10,
+    }
+}
+
+fn remove_reaction_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 10,
+    }
+}
+
 fn create_user_default() -> RateLimitConf {
     RateLimitConf {
         reset_after: 3600,
@@ -199,6 +478,13 @@ fn reset_password_default() -> RateLimitConf {
     }
 }
 
+fn two_factor_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 60,
+        limit: 5,
+    }
+}
+
 fn create_session_default() -> RateLimitConf {
     RateLimitConf {
         reset_after: 1800,
@@ -219,3 +505,297 @@ fn delete_session_default() -> RateLimitConf {
         limit: 10,
     }
 }
+
+fn delete_other_sessions_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 300,
+        limit: 5,
+    }
+}
+
+fn create_community_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 60,
+        limit: 5,
+    }
+}
+
+fn get_community_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 10,
+    }
+}
+
+fn update_community_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 5,
+    }
+}
+
+fn delete_community_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 60,
+        limit: 5,
+    }
+}
+
+fn join_community_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 5,
+    }
+}
+
+fn leave_community_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 5,
+    }
+}
+
+fn create_channel_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 60,
+        limit: 10,
+    }
+}
+
+fn get_channel_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 10,
+    }
+}
+
+fn update_channel_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 5,
+    }
+}
+
+fn delete_channel_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 60,
+        limit: 10,
+    }
+}
+
+fn create_role_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 60,
+        limit: 10,
+    }
+}
+
+fn get_roles_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 10,
+    }
+}
+
+fn update_role_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 5,
+    }
+}
+
+fn delete_role_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 60,
+        limit: 10,
+    }
+}
+
+fn edit_member_roles_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 10,
+    }
+}
+
+fn edit_overwrites_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 10,
+    }
+}
+
+fn update_slow_mode_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 5,
+    }
+}
+
+fn mute_member_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 10,
+    }
+}
+
+fn get_mutes_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 5,
+    }
+}
+
+fn moderate_user_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 10,
+    }
+}
+
+fn get_audit_log_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 5,
+    }
+}
+
+fn create_report_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 300,
+        limit: 5,
+    }
+}
+
+fn get_reports_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 5,
+    }
+}
+
+fn resolve_report_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 10,
+    }
+}
+
+fn open_dm_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 5,
+    }
+}
+
+fn create_group_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 2,
+    }
+}
+
+fn get_dms_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 5,
+    }
+}
+
+fn edit_recipients_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 10,
+    }
+}
+
+fn get_relationships_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 5,
+    }
+}
+
+fn edit_relationships_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 10,
+    }
+}
+
+fn follow_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 10,
+    }
+}
+
+fn get_follows_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 5,
+    }
+}
+
+fn create_post_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 2,
+    }
+}
+
+fn get_posts_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 10,
+    }
+}
+
+fn delete_post_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 5,
+    }
+}
+
+fn vote_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 20,
+    }
+}
+
+fn create_comment_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 5,
+    }
+}
+
+fn delete_comment_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 5,
+    }
+}
+
+fn create_invite_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 3600,
+        limit: 10,
+    }
+}
+
+fn get_invites_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 5,
+    }
+}
+
+fn delete_invite_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 10,
+    }
+}
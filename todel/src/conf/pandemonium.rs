@@ -8,6 +8,9 @@ pub struct PandemoniumConf {
     pub url: String,
     #[serde(default = "pandemonium_rate_limit_default")]
     pub rate_limit: RateLimitConf,
+    /// The rate limit of the `TYPING` payloads a user sends, separate from the connection's.
+    #[serde(default = "typing_rate_limit_default")]
+    pub typing_rate_limit: RateLimitConf,
 }
 
 impl Default for PandemoniumConf {
@@ -15,6 +18,7 @@ impl Default for PandemoniumConf {
         Self {
             url: "https://example.com".to_string(),
             rate_limit: pandemonium_rate_limit_default(),
+            typing_rate_limit: typing_rate_limit_default(),
         }
     }
 }
@@ -25,3 +29,10 @@ fn pandemonium_rate_limit_default() -> RateLimitConf {
         limit: 5,
     }
 }
+
+fn typing_rate_limit_default() -> RateLimitConf {
+    RateLimitConf {
+        reset_after: 5,
+        limit: 3,
+    }
+}
@@ -8,6 +8,7 @@ use rocket_db_pools::Database;
 use crate::{
     error,
     models::{ErrorResponse, Secret, Session},
+    Conf,
 };
 
 use super::DB;
@@ -29,9 +30,17 @@ impl<'r> FromRequest<'r> for TokenAuth {
             .rocket()
             .state::<Secret>()
             .expect("Could not obtain the managed Secret");
+        let conf = request
+            .rocket()
+            .state::<Conf>()
+            .expect("Could not obtain the managed Conf");
         match request.headers().get_one("Authorization") {
-            Some(token) => match Session::validate_token(token, secret, &mut db).await {
+            Some(token) => match Session::validate_token(token, secret, conf, &mut db).await {
                 Ok(session) => Outcome::Success(Self(session)),
+                // Suspended users
+                Err(err @ ErrorResponse::Forbidden { .. }) => {
+                    Outcome::Failure((Status::Forbidden, err))
+                }
                 Err(err) => Outcome::Failure((Status::Unauthorized, err)),
             },
             None => Outcome::Failure((Status::Unauthorized, error!(UNAUTHORIZED))),
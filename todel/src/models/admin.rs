@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+
+/// The type of action an [`AuditLogEntry`] records.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// "SUSPEND_USER"
+/// ```
+#[autodoc(category = "Admin")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[cfg_attr(feature = "logic", derive(sqlx::Type))]
+#[cfg_attr(feature = "logic", sqlx(type_name = "audit_log_action"))]
+#[cfg_attr(feature = "logic", sqlx(rename_all = "SCREAMING_SNAKE_CASE"))]
+pub enum AuditLogAction {
+    /// A user was suspended.
+    SuspendUser,
+    /// A user's suspension was lifted.
+    UnsuspendUser,
+    /// A user was verified without them verifying their email.
+    VerifyUser,
+    /// All of a user's sessions were revoked.
+    RevokeSessions,
+    /// A user's username was reset.
+    ResetUsername,
+}
+
+/// The AuditLogEntry payload, which records an action an instance admin took.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// {
+///   "id": 2391348412417,
+///   "admin_id": 48615849987333,
+///   "action": "RESET_USERNAME",
+///   "target_id": 48615849987334,
+///   "reason": "Impersonating another user",
+///   "details": "Previous username: yendri"
+/// }
+/// ```
+#[autodoc(category = "Admin")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditLogEntry {
+    /// The entry's ID.
+    pub id: u64,
+    /// The ID of the admin who took the action.
+    pub admin_id: u64,
+    /// The action the admin took.
+    pub action: AuditLogAction,
+    /// The ID of the user the action was taken on.
+    pub target_id: u64,
+    /// The reason the admin gave for the action.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Extra information about the action, like when a suspension ends or a user's previous
+    /// username.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+}
+
+/// The AdminAction payload. This is used to give a reason for an admin action which gets stored
+/// in the instance's audit log.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// {
+///   "reason": "Impersonating another user"
+/// }
+/// ```
+#[autodoc(category = "Admin")]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AdminAction {
+    /// The reason for the action. This field has to be between 1 and 512 characters long.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// The UserSuspension payload. This is used to suspend a user.
+///
+/// Suspended users can't log in or use their existing sessions until their suspension ends.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// {
+///   "duration": 604800,
+///   "reason": "Repeatedly spamming communities"
+/// }
+/// ```
+#[autodoc(category = "Admin")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UserSuspension {
+    /// The amount of seconds the user will be suspended for. This field has to be between 1 and
+    /// 315360000 (10 years).
+    pub duration: u64,
+    /// The reason for the suspension. This field has to be between 1 and 512 characters long.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
@@ -0,0 +1,174 @@
+use serde::{Deserialize, Serialize};
+use serde_with::rust::double_option;
+
+use super::ChannelOverwrite;
+
+fn is_zero(value: &u32) -> bool {
+    *value == 0
+}
+
+/// The type of a [`Channel`].
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// "TEXT"
+/// ```
+#[autodoc(category = "Channels")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+#[cfg_attr(feature = "logic", derive(sqlx::Type))]
+#[cfg_attr(feature = "logic", sqlx(type_name = "channel_type"))]
+#[cfg_attr(feature = "logic", sqlx(rename_all = "UPPERCASE"))]
+pub enum ChannelType {
+    /// A channel members can send messages in.
+    #[default]
+    Text,
+    /// A category other channels can be grouped under.
+    Category,
+}
+
+/// The Channel payload. Channels belong to a [`Community`] and are visible to the members with the
+/// `VIEW_CHANNEL` [`Permissions`] in them.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// {
+///   "id": 2384915337217,
+///   "community_id": 2384913657857,
+///   "type": "TEXT",
+///   "name": "general",
+///   "topic": "Talk about anything lanternfly related.",
+///   "position": 0,
+///   "parent_id": 2384914796545,
+///   "slow_mode": 10
+/// }
+/// ```
+#[autodoc(category = "Channels")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Channel {
+    /// The channel's ID.
+    pub id: u64,
+    /// The ID of the community the channel belongs to.
+    pub community_id: u64,
+    /// The channel's type.
+    #[serde(rename = "type")]
+    pub channel_type: ChannelType,
+    /// The channel's name.
+    pub name: String,
+    /// The channel's topic.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub topic: Option<String>,
+    /// The channel's position in its community or category, starting from 0.
+    pub position: u32,
+    /// The ID of the category the channel is in.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<u64>,
+    /// The amount of seconds members have to wait between sending messages in the channel, this
+    /// isn't present if the channel has no slow mode.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "is_zero")]
+    pub slow_mode: u32,
+    /// The channel's permission overwrites.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub overwrites: Vec<ChannelOverwrite>,
+}
+
+/// The ChannelCreate payload. This is used to create a channel in a community.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// {
+///   "name": "general",
+///   "topic": "Talk about anything lanternfly related.",
+///   "parent_id": 2384914796545
+/// }
+/// ```
+#[autodoc(category = "Channels")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChannelCreate {
+    /// The channel's type. Defaults to `TEXT`.
+    #[serde(default)]
+    #[serde(rename = "type")]
+    pub channel_type: ChannelType,
+    /// The channel's name. This field has to be between 1 and 32 characters long.
+    pub name: String,
+    /// The channel's topic. This field has to be between 1 and 1024 characters long.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub topic: Option<String>,
+    /// The channel's position. Defaults to after the community's last channel.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<u32>,
+    /// The ID of the category to put the channel in. Categories can't be nested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<u64>,
+}
+
+/// The UpdateChannel payload. This payload is used to update a channel. The abscence of a field or
+/// it being `undefined` means that it won't have an effect. Explicitly setting a field as `null`
+/// will clear it.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// {
+///   "topic": null,
+///   "position": 2
+/// }
+/// ```
+#[autodoc(category = "Channels")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UpdateChannel {
+    /// The channel's new name. This field follows the same rules as the [`ChannelCreate`] `name`
+    /// field.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// The channel's new topic. This field follows the same rules as the [`ChannelCreate`]
+    /// `topic` field.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "double_option"
+    )]
+    pub topic: Option<Option<String>>,
+    /// The channel's new position.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<u32>,
+    /// The ID of the channel's new category.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "double_option"
+    )]
+    pub parent_id: Option<Option<u64>>,
+}
+
+/// The UpdateSlowMode payload. This is used to change a channel's slow mode.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// {
+///   "interval": 10
+/// }
+/// ```
+#[autodoc(category = "Channels")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UpdateSlowMode {
+    /// The amount of seconds members have to wait between sending messages, `0` disables slow
+    /// mode. This field has to be at most 21600 (6 hours).
+    pub interval: u32,
+}
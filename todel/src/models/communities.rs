@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+use serde_with::rust::double_option;
+
+/// The Community payload. Communities are the core unit of Eludris, they group users together as
+/// members.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// {
+///   "id": 2384913657857,
+///   "owner_id": 48615849987333,
+///   "name": "Spotted Lanternfly Hunters",
+///   "description": "Stomping them one at a time.",
+///   "icon": 2384911065089,
+///   "private": false
+/// }
+/// ```
+#[autodoc(category = "Communities")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Community {
+    /// The community's ID.
+    pub id: u64,
+    /// The ID of the community's owner.
+    pub owner_id: u64,
+    /// The community's name.
+    pub name: String,
+    /// The community's description.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// The ID of the community's icon. This is a file in the "avatars" bucket.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<u64>,
+    /// The ID of the community's banner. This is a file in the "banners" bucket.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub banner: Option<u64>,
+    /// Whether the community is private. Private communities can't be joined or seen by
+    /// non-members.
+    pub private: bool,
+}
+
+/// The CommunityCreate payload. This is used to create a community.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// {
+///   "name": "Spotted Lanternfly Hunters",
+///   "description": "Stomping them one at a time.",
+///   "private": false
+/// }
+/// ```
+#[autodoc(category = "Communities")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommunityCreate {
+    /// The community's name. This field has to be between 1 and 32 characters long.
+    pub name: String,
+    /// The community's description. This field has to be between 1 and 500 characters long.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// The community's icon. This field has to be a valid file ID in the "avatars" bucket.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<u64>,
+    /// The community's banner. This field has to be a valid file ID in the "banners" bucket.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub banner: Option<u64>,
+    /// Whether the community is private. Defaults to `false`.
+    #[serde(default)]
+    pub private: bool,
+}
+
+/// The UpdateCommunity payload. This payload is used to update a community. The abscence of a
+/// field or it being `undefined` means that it won't have an effect. Explicitly setting a field as
+/// `null` will clear it.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// {
+///   "description": null,
+///   "private": true
+/// }
+/// ```
+#[autodoc(category = "Communities")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UpdateCommunity {
+    /// The community's new name. This field follows the same rules as the [`CommunityCreate`]
+    /// `name` field.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// The community's new description. This field follows the same rules as the
+    /// [`CommunityCreate`] `description` field.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "double_option"
+    )]
+    pub description: Option<Option<String>>,
+    /// The community's new icon. This field has to be a valid file ID in the "avatars" bucket.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "double_option"
+    )]
+    pub icon: Option<Option<u64>>,
+    /// The community's new banner. This field has to be a valid file ID in the "banners" bucket.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "double_option"
+    )]
+    pub banner: Option<Option<u64>>,
+    /// Whether the community is private.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub private: Option<bool>,
+}
@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+
+/// The type of a [`DirectChannel`].
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// "GROUP"
+/// ```
+#[autodoc(category = "Direct Messages")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+#[cfg_attr(feature = "logic", derive(sqlx::Type))]
+#[cfg_attr(feature = "logic", sqlx(type_name = "channel_type"))]
+#[cfg_attr(feature = "logic", sqlx(rename_all = "UPPERCASE"))]
+pub enum DirectChannelType {
+    /// A private channel between two users.
+    Dm,
+    /// A private channel between a group of users, managed by its owner.
+    Group,
+}
+
+/// The DirectChannel payload. Direct channels don't belong to any community and can only be
+/// seen by their recipients.
+///
+/// Messages are sent to and fetched from direct channels the same way as community channels.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// {
+///   "id": 2385291767809,
+///   "type": "GROUP",
+///   "name": "Lanternfly Strike Team",
+///   "owner_id": 48615849987333,
+///   "recipients": [48615849987333, 48615849987334, 48615849987335]
+/// }
+/// ```
+#[autodoc(category = "Direct Messages")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DirectChannel {
+    /// The channel's ID.
+    pub id: u64,
+    /// The channel's type.
+    #[serde(rename = "type")]
+    pub channel_type: DirectChannelType,
+    /// The group's name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// The ID of the group's owner.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner_id: Option<u64>,
+    /// The IDs of the channel's recipients, including you.
+    pub recipients: Vec<u64>,
+}
+
+/// The GroupCreate payload. This is used to create a group DM with other users.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// {
+///   "name": "Lanternfly Strike Team",
+///   "recipients": [48615849987334, 48615849987335]
+/// }
+/// ```
+#[autodoc(category = "Direct Messages")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GroupCreate {
+    /// The group's name. This field has to be between 1 and 32 characters long.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// The IDs of the users to add to the group. A group can have up to 10 recipients, including
+    /// you.
+    #[serde(default)]
+    pub recipients: Vec<u64>,
+}
@@ -103,4 +103,5 @@ pub struct File {
     pub spoiler: bool,
     pub width: Option<usize>,
     pub height: Option<usize>,
+    pub owner_id: Option<u64>,
 }
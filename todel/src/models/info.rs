@@ -1,4 +1,4 @@
-use crate::conf::{EffisRateLimits, OprishRateLimits, RateLimitConf};
+use crate::conf::{EffisRateLimits, OprishRateLimits, RateLimitConf, RegistrationMode};
 use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "logic")]
@@ -23,6 +23,7 @@ const VERSION: &str = env!("CARGO_PKG_VERSION");
 ///   "effis_url": "https://cdn.eludris.gay",
 ///   "file_size": 20000000,
 ///   "attachment_file_size": 25000000,
+///   "registration_mode": "OPEN",
 ///   "rate_limits": {
 ///     "oprish": {
 ///       "info": {
@@ -84,6 +85,8 @@ pub struct InstanceInfo {
     pub file_size: u64,
     /// The maximum file size (in bytes) of an attachment.
     pub attachment_file_size: u64,
+    /// Who can register on the instance.
+    pub registration_mode: RegistrationMode,
     /// The instance's email address if any.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub email_address: Option<String>,
@@ -163,6 +166,7 @@ impl InstanceInfo {
             effis_url: conf.effis.url.clone(),
             file_size: conf.effis.file_size,
             attachment_file_size: conf.effis.attachment_file_size,
+            registration_mode: conf.oprish.registration_mode,
             email_address: conf.email.as_ref().map(|e| e.address.clone()),
             rate_limits: rate_limits.then_some(InstanceRateLimits {
                 oprish: conf.oprish.rate_limits.clone(),
@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+
+/// The Invite payload. Invites let people register on instances whose registration mode is
+/// `INVITE_ONLY`.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// {
+///   "code": "tYhL3bJq",
+///   "creator_id": 48615849987333,
+///   "uses": 2,
+///   "max_uses": 5,
+///   "expires_at": 1690718400
+/// }
+/// ```
+#[autodoc(category = "Invites")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Invite {
+    /// The invite's code.
+    pub code: String,
+    /// The ID of the user who created the invite.
+    pub creator_id: u64,
+    /// The amount of users who registered using the invite.
+    pub uses: u32,
+    /// The amount of times the invite can be used.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_uses: Option<u32>,
+    /// The UNIX timestamp (in seconds) after which the invite can no longer be used.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+}
+
+/// The InviteCreate payload. This is used to create a new invite.
+///
+/// Any field set to `null`, `undefined` or is missing means the invite isn't limited by it.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// {
+///   "max_uses": 5,
+///   "expires_in": 86400
+/// }
+/// ```
+#[autodoc(category = "Invites")]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InviteCreate {
+    /// The amount of times the invite can be used. This field has to be at least 1.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_uses: Option<u32>,
+    /// The amount of seconds after which the invite expires. This field has to be at least 1.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_in: Option<u64>,
+}
@@ -0,0 +1,434 @@
+use redis::AsyncCommands;
+use sqlx::{pool::PoolConnection, Postgres};
+
+use super::now;
+use crate::{
+    ids::IdGenerator,
+    models::{
+        AdminAction, AuditLogAction, AuditLogEntry, ErrorResponse, Permissions, Session, User,
+        UserSuspension,
+    },
+};
+
+/// The default amount of entries returned by [`AuditLogEntry::get_page`].
+pub const AUDIT_LOG_DEFAULT_LIMIT: u32 = 50;
+/// The maximum amount of entries returned by [`AuditLogEntry::get_page`].
+pub const AUDIT_LOG_MAX_LIMIT: u32 = 100;
+/// The maximum length of an admin action's reason.
+pub const ADMIN_REASON_LIMIT: usize = 512;
+/// The maximum duration of a suspension in seconds.
+pub const SUSPENSION_MAX_DURATION: u64 = 315_360_000;
+
+fn validate_reason(reason: &Option<String>) -> Result<(), ErrorResponse> {
+    if let Some(reason) = reason {
+        if reason.is_empty() || reason.len() > ADMIN_REASON_LIMIT {
+            return Err(error!(
+                VALIDATION,
+                "reason",
+                format!(
+                    "The reason must be between 1 and {} characters in length",
+                    ADMIN_REASON_LIMIT
+                )
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Make sure an admin can take actions on a user, other administrators can only be acted on by
+/// themselves.
+async fn ensure_actionable(
+    user_id: u64,
+    admin_id: u64,
+    db: &mut PoolConnection<Postgres>,
+) -> Result<(), ErrorResponse> {
+    Permissions::require_instance(admin_id, Permissions::MANAGE_USERS, &mut *db).await?;
+    if !User::exists(user_id, &mut *db).await? {
+        return Err(error!(NOT_FOUND));
+    }
+    if user_id != admin_id
+        && Permissions::get_instance(user_id, db)
+            .await?
+            .contains(Permissions::ADMINISTRATOR)
+    {
+        return Err(error!(FORBIDDEN));
+    }
+    Ok(())
+}
+
+/// Record an admin action in the instance's audit log.
+async fn log_action(
+    admin_id: u64,
+    action: AuditLogAction,
+    target_id: u64,
+    reason: Option<String>,
+    details: Option<String>,
+    id_generator: &mut IdGenerator,
+    db: &mut PoolConnection<Postgres>,
+) -> Result<AuditLogEntry, ErrorResponse> {
+    let id = id_generator.generate();
+    sqlx::query!(
+        "
+INSERT INTO audit_log(id, admin_id, action, target_id, reason, details)
+VALUES($1, $2, $3, $4, $5, $6)
+        ",
+        id as i64,
+        admin_id as i64,
+        action as AuditLogAction,
+        target_id as i64,
+        reason,
+        details,
+    )
+    .execute(db)
+    .await
+    .map_err(|err| {
+        log::error!("Couldn't store audit log entry: {}", err);
+        error!(SERVER, "Failed to update the audit log")
+    })?;
+    Ok(AuditLogEntry {
+        id,
+        admin_id,
+        action,
+        target_id,
+        reason,
+        details,
+    })
+}
+
+impl AdminAction {
+    pub fn ensure_valid(&mut self) {
+        self.reason = self.reason.as_ref().map(|r| r.trim().to_string());
+    }
+
+    pub fn validate(&self) -> Result<(), ErrorResponse> {
+        validate_reason(&self.reason)
+    }
+}
+
+impl UserSuspension {
+    pub fn ensure_valid(&mut self) {
+        self.reason = self.reason.as_ref().map(|r| r.trim().to_string());
+    }
+
+    pub fn validate(&self) -> Result<(), ErrorResponse> {
+        if self.duration == 0 || self.duration > SUSPENSION_MAX_DURATION {
+            return Err(error!(
+                VALIDATION,
+                "duration",
+                format!(
+                    "The suspension's duration must be between 1 and {} seconds",
+                    SUSPENSION_MAX_DURATION
+                )
+            ));
+        }
+        validate_reason(&self.reason)
+    }
+}
+
+impl User {
+    /// Get the error suspended users get when trying to use their account.
+    pub(crate) fn suspension_error(suspended_until: u64) -> ErrorResponse {
+        error!(
+            FORBIDDEN,
+            format!("Your account is suspended until {}", suspended_until)
+        )
+    }
+
+    /// Suspend a user, replacing their current suspension if they have one.
+    ///
+    /// The user's sessions are kept but their gateway connections get closed.
+    pub async fn suspend<C: AsyncCommands>(
+        id: u64,
+        mut suspension: UserSuspension,
+        admin_id: u64,
+        id_generator: &mut IdGenerator,
+        db: &mut PoolConnection<Postgres>,
+        cache: &mut C,
+    ) -> Result<AuditLogEntry, ErrorResponse> {
+        suspension.ensure_valid();
+        suspension.validate()?;
+        ensure_actionable(id, admin_id, &mut *db).await?;
+        if id == admin_id {
+            return Err(error!(VALIDATION, "user", "You can't suspend yourself"));
+        }
+        let suspended_until = now() + suspension.duration;
+        sqlx::query!(
+            "
+UPDATE users
+SET suspended_until = $1
+WHERE id = $2
+            ",
+            suspended_until as i64,
+            id as i64,
+        )
+        .execute(&mut *db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't suspend user: {}", err);
+            error!(SERVER, "Failed to suspend user")
+        })?;
+        Session::disconnect_all(id, "Account suspended", &mut *db, cache).await?;
+        log_action(
+            admin_id,
+            AuditLogAction::SuspendUser,
+            id,
+            suspension.reason,
+            Some(format!("Suspended until {}", suspended_until)),
+            id_generator,
+            db,
+        )
+        .await
+    }
+
+    /// Lift a user's suspension.
+    pub async fn unsuspend(
+        id: u64,
+        mut action: AdminAction,
+        admin_id: u64,
+        id_generator: &mut IdGenerator,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<AuditLogEntry, ErrorResponse> {
+        action.ensure_valid();
+        action.validate()?;
+        ensure_actionable(id, admin_id, &mut *db).await?;
+        let result = sqlx::query!(
+            "
+UPDATE users
+SET suspended_until = NULL
+WHERE id = $1
+AND suspended_until > $2
+            ",
+            id as i64,
+            now() as i64,
+        )
+        .execute(&mut *db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't unsuspend user: {}", err);
+            error!(SERVER, "Failed to unsuspend user")
+        })?;
+        if result.rows_affected() == 0 {
+            return Err(error!(VALIDATION, "user", "The user isn't suspended"));
+        }
+        log_action(
+            admin_id,
+            AuditLogAction::UnsuspendUser,
+            id,
+            action.reason,
+            None,
+            id_generator,
+            db,
+        )
+        .await
+    }
+
+    /// Verify a user without them having to verify their email.
+    pub async fn force_verify<C: AsyncCommands>(
+        id: u64,
+        mut action: AdminAction,
+        admin_id: u64,
+        id_generator: &mut IdGenerator,
+        db: &mut PoolConnection<Postgres>,
+        cache: &mut C,
+    ) -> Result<AuditLogEntry, ErrorResponse> {
+        action.ensure_valid();
+        action.validate()?;
+        ensure_actionable(id, admin_id, &mut *db).await?;
+        let result = sqlx::query!(
+            "
+UPDATE users
+SET verified = TRUE
+WHERE id = $1
+AND verified = FALSE
+            ",
+            id as i64,
+        )
+        .execute(&mut *db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't verify user: {}", err);
+            error!(SERVER, "Failed to verify user")
+        })?;
+        if result.rows_affected() == 0 {
+            return Err(error!(VALIDATION, "user", "The user is already verified"));
+        }
+        cache
+            .del::<_, ()>(format!("verification:{}", id))
+            .await
+            .map_err(|err| {
+                log::error!("Couldn't delete verification code from cache: {}", err);
+                error!(SERVER, "Failed to verify user")
+            })?;
+        log_action(
+            admin_id,
+            AuditLogAction::VerifyUser,
+            id,
+            action.reason,
+            None,
+            id_generator,
+            db,
+        )
+        .await
+    }
+
+    /// Reset a user's username to one based on their ID.
+    ///
+    /// Returns the updated user along with the audit log entry.
+    pub async fn reset_username<C: AsyncCommands>(
+        id: u64,
+        mut action: AdminAction,
+        admin_id: u64,
+        id_generator: &mut IdGenerator,
+        db: &mut PoolConnection<Postgres>,
+        cache: &mut C,
+    ) -> Result<(Self, AuditLogEntry), ErrorResponse> {
+        action.ensure_valid();
+        action.validate()?;
+        ensure_actionable(id, admin_id, &mut *db).await?;
+        let username = format!("user-{}", id);
+        let previous = sqlx::query!(
+            "
+UPDATE users u
+SET username = $1
+FROM users old
+WHERE u.id = $2
+AND old.id = u.id
+RETURNING old.username
+            ",
+            username,
+            id as i64,
+        )
+        .fetch_one(&mut *db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't reset username: {}", err);
+            error!(SERVER, "Failed to reset username")
+        })?
+        .username;
+        let entry = log_action(
+            admin_id,
+            AuditLogAction::ResetUsername,
+            id,
+            action.reason,
+            Some(format!("Previous username: {}", previous)),
+            id_generator,
+            &mut *db,
+        )
+        .await?;
+        Ok((Self::get(id, None, db, cache).await?, entry))
+    }
+}
+
+impl Session {
+    /// Revoke all of a user's sessions, logging them out everywhere.
+    pub async fn revoke_all<C: AsyncCommands>(
+        user_id: u64,
+        mut action: AdminAction,
+        admin_id: u64,
+        id_generator: &mut IdGenerator,
+        db: &mut PoolConnection<Postgres>,
+        cache: &mut C,
+    ) -> Result<AuditLogEntry, ErrorResponse> {
+        action.ensure_valid();
+        action.validate()?;
+        ensure_actionable(user_id, admin_id, &mut *db).await?;
+        let revoked = Self::revoke(
+            user_id,
+            None,
+            "Sessions revoked by an administrator",
+            &mut *db,
+            cache,
+        )
+        .await?;
+        log_action(
+            admin_id,
+            AuditLogAction::RevokeSessions,
+            user_id,
+            action.reason,
+            Some(format!("Revoked {} sessions", revoked)),
+            id_generator,
+            db,
+        )
+        .await
+    }
+}
+
+impl AuditLogEntry {
+    /// Get a page of the instance's audit log, ordered from newest to oldest.
+    pub async fn get_page(
+        before: Option<u64>,
+        limit: Option<u32>,
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Vec<Self>, ErrorResponse> {
+        let limit = limit.unwrap_or(AUDIT_LOG_DEFAULT_LIMIT);
+        if limit == 0 || limit > AUDIT_LOG_MAX_LIMIT {
+            return Err(error!(
+                VALIDATION,
+                "limit",
+                format!(
+                    "The entry limit must be between 1 and {}",
+                    AUDIT_LOG_MAX_LIMIT
+                )
+            ));
+        }
+        Permissions::require_instance(user_id, Permissions::MANAGE_USERS, &mut *db).await?;
+        sqlx::query!(
+            r#"
+SELECT id, admin_id, action as "action: AuditLogAction", target_id, reason, details
+FROM audit_log
+WHERE id < $1
+ORDER BY id DESC
+LIMIT $2
+            "#,
+            before.map(|b| b as i64).unwrap_or(i64::MAX),
+            limit as i64,
+        )
+        .fetch_all(db)
+        .await
+        .map(|rows| {
+            rows.into_iter()
+                .map(|r| Self {
+                    id: r.id as u64,
+                    admin_id: r.admin_id as u64,
+                    action: r.action,
+                    target_id: r.target_id as u64,
+                    reason: r.reason,
+                    details: r.details,
+                })
+                .collect()
+        })
+        .map_err(|err| {
+            log::error!("Couldn't fetch audit log: {}", err);
+            error!(SERVER, "Failed to fetch audit log")
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::{AdminAction, UserSuspension};
+
+    #[test]
+    fn validate_admin_actions() {
+        let mut action = AdminAction {
+            reason: Some("  Impersonating another user ".to_string()),
+        };
+        action.ensure_valid();
+        assert_eq!(action.reason.as_deref(), Some("Impersonating another user"));
+        assert!(action.validate().is_ok());
+        assert!(AdminAction::default().validate().is_ok());
+        action.reason = Some("h".repeat(513));
+        assert!(action.validate().is_err());
+
+        let mut suspension = UserSuspension {
+            duration: 604800,
+            reason: None,
+        };
+        assert!(suspension.validate().is_ok());
+        suspension.duration = 0;
+        assert!(suspension.validate().is_err());
+        suspension.duration = super::SUSPENSION_MAX_DURATION + 1;
+        assert!(suspension.validate().is_err());
+    }
+}
@@ -0,0 +1,580 @@
+use redis::AsyncCommands;
+use sqlx::{pool::PoolConnection, postgres::PgRow, Postgres, QueryBuilder, Row};
+
+use super::permissions::{get_overwrites, MemberPermissions};
+use crate::{
+    ids::IdGenerator,
+    models::{
+        Channel, ChannelCreate, ChannelOverwriteEdit, ChannelType, Community, DirectChannel,
+        ErrorResponse, OverwriteType, Permissions, UpdateChannel, UpdateSlowMode,
+    },
+};
+
+/// The maximum slow mode interval of a channel in seconds.
+pub const SLOW_MODE_MAX_INTERVAL: u32 = 21600;
+
+fn validate_name(name: &str) -> Result<(), ErrorResponse> {
+    if name.is_empty() || name.len() > 32 {
+        Err(error!(
+            VALIDATION,
+            "name", "The channel's name must be between 1 and 32 characters in length"
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+fn validate_topic(topic: &str) -> Result<(), ErrorResponse> {
+    if topic.is_empty() || topic.len() > 1024 {
+        Err(error!(
+            VALIDATION,
+            "topic", "The channel's topic must be between 1 and 1024 characters in length"
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Make sure a channel's parent is a category in the same community.
+async fn validate_parent(
+    parent_id: u64,
+    community_id: u64,
+    channel_type: ChannelType,
+    db: &mut PoolConnection<Postgres>,
+) -> Result<(), ErrorResponse> {
+    if channel_type == ChannelType::Category {
+        return Err(error!(
+            VALIDATION,
+            "parent_id", "Categories can't be put inside other categories"
+        ));
+    }
+    let parent = sqlx::query!(
+        r#"
+SELECT channel_type = 'CATEGORY' AS "is_category!"
+FROM channels
+WHERE id = $1
+AND community_id = $2
+        "#,
+        parent_id as i64,
+        community_id as i64,
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(|err| {
+        log::error!("Couldn't fetch parent channel: {}", err);
+        error!(SERVER, "Failed to validate channel parent")
+    })?;
+    match parent {
+        Some(parent) if parent.is_category => Ok(()),
+        _ => Err(error!(
+            VALIDATION,
+            "parent_id", "The channel's parent must be a category in the same community"
+        )),
+    }
+}
+
+/// Attach their overwrites to a set of channels.
+async fn with_overwrites(
+    mut channels: Vec<Channel>,
+    db: &mut PoolConnection<Postgres>,
+) -> Result<Vec<Channel>, ErrorResponse> {
+    let ids: Vec<i64> = channels.iter().map(|c| c.id as i64).collect();
+    let mut overwrites = get_overwrites(&ids, db).await?;
+    for channel in channels.iter_mut() {
+        channel.overwrites = overwrites.remove(&channel.id).unwrap_or_default();
+    }
+    Ok(channels)
+}
+
+impl ChannelCreate {
+    pub fn ensure_valid(&mut self) {
+        self.name = self.name.trim().to_string();
+        self.topic = self.topic.as_ref().map(|t| t.trim().to_string());
+    }
+
+    pub fn validate(&self) -> Result<(), ErrorResponse> {
+        validate_name(&self.name)?;
+        if let Some(topic) = &self.topic {
+            validate_topic(topic)?;
+        }
+        Ok(())
+    }
+}
+
+impl UpdateChannel {
+    pub fn ensure_valid(&mut self) {
+        self.name = self.name.as_ref().map(|n| n.trim().to_string());
+        if let Some(Some(topic)) = &self.topic {
+            self.topic = Some(Some(topic.trim().to_string()));
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), ErrorResponse> {
+        if self.name.is_none()
+            && self.topic.is_none()
+            && self.position.is_none()
+            && self.parent_id.is_none()
+        {
+            return Err(error!(VALIDATION, "body", "At least one field must exist"));
+        }
+        if let Some(name) = &self.name {
+            validate_name(name)?;
+        }
+        if let Some(Some(topic)) = &self.topic {
+            validate_topic(topic)?;
+        }
+        Ok(())
+    }
+}
+
+impl UpdateSlowMode {
+    pub fn validate(&self) -> Result<(), ErrorResponse> {
+        if self.interval > SLOW_MODE_MAX_INTERVAL {
+            return Err(error!(
+                VALIDATION,
+                "interval",
+                format!(
+                    "The slow mode interval must be at most {} seconds",
+                    SLOW_MODE_MAX_INTERVAL
+                )
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Channel {
+    fn from_row(row: PgRow) -> Self {
+        Self {
+            id: row.get::<i64, _>("id") as u64,
+            community_id: row.get::<i64, _>("community_id") as u64,
+            channel_type: row.get("channel_type"),
+            name: row.get("name"),
+            topic: row.get("topic"),
+            position: row.get::<i32, _>("position") as u32,
+            parent_id: row.get::<Option<i64>, _>("parent_id").map(|p| p as u64),
+            slow_mode: row.get::<i32, _>("slow_mode") as u32,
+            overwrites: vec![],
+        }
+    }
+
+    pub async fn create(
+        community_id: u64,
+        mut channel: ChannelCreate,
+        id_generator: &mut IdGenerator,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Self, ErrorResponse> {
+        channel.ensure_valid();
+        channel.validate()?;
+        if let Some(parent_id) = channel.parent_id {
+            validate_parent(parent_id, community_id, channel.channel_type, &mut *db).await?;
+        }
+        let id = id_generator.generate();
+        sqlx::query(
+            "
+INSERT INTO channels(id, community_id, channel_type, name, topic, position, parent_id)
+VALUES($1, $2, $3, $4, $5, COALESCE($6, (
+  SELECT COALESCE(MAX(position) + 1, 0)
+  FROM channels
+  WHERE community_id = $2
+  AND parent_id IS NOT DISTINCT FROM $7
+)), $7)
+RETURNING *
+            ",
+        )
+        .bind(id as i64)
+        .bind(community_id as i64)
+        .bind(channel.channel_type)
+        .bind(channel.name)
+        .bind(channel.topic)
+        .bind(channel.position.map(|p| p as i32))
+        .bind(channel.parent_id.map(|p| p as i64))
+        .fetch_one(db)
+        .await
+        .map(Self::from_row)
+        .map_err(|err| {
+            log::error!("Failed to store channel in database: {}", err);
+            error!(SERVER, "Could not create channel")
+        })
+    }
+
+    /// Get a channel, channels can only be fetched by the members who can view them.
+    pub async fn get(
+        id: u64,
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Self, ErrorResponse> {
+        let channel = sqlx::query(
+            "
+SELECT *
+FROM channels
+WHERE id = $1
+AND community_id IS NOT NULL
+            ",
+        )
+        .bind(id as i64)
+        .fetch_optional(&mut *db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't fetch channel from database: {}", err);
+            error!(SERVER, "Failed to fetch channel")
+        })?
+        .map(Self::from_row)
+        .ok_or_else(|| error!(NOT_FOUND))?;
+        if !Permissions::get_channel(id, user_id, &mut *db)
+            .await?
+            .contains(Permissions::VIEW_CHANNEL)
+        {
+            return Err(error!(NOT_FOUND));
+        }
+        with_overwrites(vec![channel], db)
+            .await
+            .map(|mut c| c.remove(0))
+    }
+
+    /// Get all of the channels of a community the user can view, ordered by position.
+    pub async fn get_community_channels(
+        community_id: u64,
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Vec<Self>, ErrorResponse> {
+        Permissions::require_community(community_id, user_id, Permissions::empty(), &mut *db)
+            .await?;
+        let permissions =
+            Permissions::get_community_channels(community_id, user_id, &mut *db).await?;
+        let channels = sqlx::query(
+            "
+SELECT *
+FROM channels
+WHERE community_id = $1
+ORDER BY position, id
+            ",
+        )
+        .bind(community_id as i64)
+        .fetch_all(&mut *db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't fetch community channels: {}", err);
+            error!(SERVER, "Failed to fetch channels")
+        })?
+        .into_iter()
+        .map(Self::from_row)
+        .filter(
+            |c| matches!(permissions.get(&c.id), Some(p) if p.contains(Permissions::VIEW_CHANNEL)),
+        )
+        .collect();
+        with_overwrites(channels, db).await
+    }
+
+    /// Get the IDs of all the channels a user can see, including their direct channels.
+    pub async fn get_visible_ids(
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Vec<u64>, ErrorResponse> {
+        let mut channels: Vec<u64> = DirectChannel::get_joined(user_id, &mut *db)
+            .await?
+            .into_iter()
+            .map(|c| c.id)
+            .collect();
+        for community in Community::get_joined(user_id, &mut *db).await? {
+            channels.extend(
+                Permissions::get_community_channels(community.id, user_id, &mut *db)
+                    .await?
+                    .into_iter()
+                    .filter(|(_, p)| p.contains(Permissions::VIEW_CHANNEL))
+                    .map(|(id, _)| id),
+            );
+        }
+        Ok(channels)
+    }
+
+    pub async fn update(
+        id: u64,
+        user_id: u64,
+        mut update: UpdateChannel,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Self, ErrorResponse> {
+        update.ensure_valid();
+        update.validate()?;
+        let channel = Self::get(id, user_id, &mut *db).await?;
+        if let Some(Some(parent_id)) = update.parent_id {
+            if parent_id == id {
+                return Err(error!(
+                    VALIDATION,
+                    "parent_id", "A channel can't be its own parent"
+                ));
+            }
+            validate_parent(
+                parent_id,
+                channel.community_id,
+                channel.channel_type,
+                &mut *db,
+            )
+            .await?;
+        }
+        let mut query: QueryBuilder<Postgres> = QueryBuilder::new("UPDATE channels SET ");
+        let mut seperated = query.separated(", ");
+        if let Some(name) = update.name {
+            seperated.push("name = ").push_bind_unseparated(name);
+        }
+        if let Some(topic) = update.topic {
+            seperated.push("topic = ").push_bind_unseparated(topic);
+        }
+        if let Some(position) = update.position {
+            seperated
+                .push("position = ")
+                .push_bind_unseparated(position as i32);
+        }
+        if let Some(parent_id) = update.parent_id {
+            seperated
+                .push("parent_id = ")
+                .push_bind_unseparated(parent_id.map(|p| p as i64));
+        }
+        let updated = query
+            .push(" WHERE id = ")
+            .push_bind(id as i64)
+            .push(" RETURNING *")
+            .build()
+            .fetch_one(&mut *db)
+            .await
+            .map(Self::from_row)
+            .map_err(|err| {
+                log::error!("Couldn't update channel: {}", err);
+                error!(SERVER, "Failed to update channel")
+            })?;
+        Ok(Self {
+            overwrites: channel.overwrites,
+            ..updated
+        })
+    }
+
+    /// Delete a channel, the channels inside a deleted category are moved out of it.
+    pub async fn delete(
+        id: u64,
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Self, ErrorResponse> {
+        let channel = Self::get(id, user_id, &mut *db).await?;
+        sqlx::query!(
+            "
+DELETE FROM channels
+WHERE id = $1
+            ",
+            id as i64
+        )
+        .execute(db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't delete channel: {}", err);
+            error!(SERVER, "Failed to delete channel")
+        })?;
+        Ok(channel)
+    }
+
+    /// Change how long members have to wait between sending messages in a channel.
+    pub async fn update_slow_mode(
+        id: u64,
+        user_id: u64,
+        update: UpdateSlowMode,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Self, ErrorResponse> {
+        update.validate()?;
+        let channel = Self::get(id, user_id, &mut *db).await?;
+        if channel.channel_type == ChannelType::Category {
+            return Err(error!(
+                VALIDATION,
+                "channel", "Only text channels can have a slow mode"
+            ));
+        }
+        sqlx::query!(
+            "
+UPDATE channels
+SET slow_mode = $1
+WHERE id = $2
+            ",
+            update.interval as i32,
+            id as i64,
+        )
+        .execute(db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't update channel slow mode: {}", err);
+            error!(SERVER, "Failed to update channel")
+        })?;
+        Ok(Self {
+            slow_mode: update.interval,
+            ..channel
+        })
+    }
+
+    /// Make sure a user waited for a channel's slow mode since their last message in it, starting
+    /// their next wait if they did.
+    pub(crate) async fn check_slow_mode<C: AsyncCommands>(
+        id: u64,
+        user_id: u64,
+        interval: u32,
+        cache: &mut C,
+    ) -> Result<(), ErrorResponse> {
+        let key = format!("slow-mode:{}:{}", id, user_id);
+        let fresh: bool = cache.set_nx(&key, 1).await.map_err(|err| {
+            log::error!("Couldn't set slow mode key in cache: {}", err);
+            error!(SERVER, "Failed to check slow mode")
+        })?;
+        if fresh {
+            cache
+                .expire::<_, ()>(&key, interval as usize)
+                .await
+                .map_err(|err| {
+                    log::error!("Couldn't set slow mode key expiry: {}", err);
+                    error!(SERVER, "Failed to check slow mode")
+                })?;
+            return Ok(());
+        }
+        let ttl: i64 = cache.pttl(&key).await.map_err(|err| {
+            log::error!("Couldn't fetch slow mode key expiry: {}", err);
+            error!(SERVER, "Failed to check slow mode")
+        })?;
+        if ttl < 0 {
+            // The key somehow lost its expiry, restart the wait instead of locking the user out.
+            cache
+                .expire::<_, ()>(&key, interval as usize)
+                .await
+                .map_err(|err| {
+                    log::error!("Couldn't set slow mode key expiry: {}", err);
+                    error!(SERVER, "Failed to check slow mode")
+                })?;
+            return Err(error!(RATE_LIMITED, interval as u64 * 1000));
+        }
+        Err(error!(RATE_LIMITED, ttl as u64))
+    }
+
+    /// Create or replace a channel's overwrite for a role or member.
+    ///
+    /// Users can only allow or deny the permissions they have themselves.
+    pub async fn set_overwrite(
+        id: u64,
+        target_id: u64,
+        overwrite: ChannelOverwriteEdit,
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Self, ErrorResponse> {
+        let channel = Self::get(id, user_id, &mut *db).await?;
+        MemberPermissions::get(channel.community_id, user_id, &mut *db)
+            .await?
+            .ensure_grantable(overwrite.allow | overwrite.deny)?;
+        let target_exists = match overwrite.overwrite_type {
+            OverwriteType::Role => sqlx::query!(
+                r#"
+SELECT EXISTS(
+  SELECT 1
+  FROM roles
+  WHERE id = $1
+  AND community_id = $2
+) AS "exists!"
+                "#,
+                target_id as i64,
+                channel.community_id as i64,
+            )
+            .fetch_one(&mut *db)
+            .await
+            .map(|r| r.exists)
+            .map_err(|err| {
+                log::error!("Couldn't check overwrite target: {}", err);
+                error!(SERVER, "Failed to update channel overwrites")
+            })?,
+            OverwriteType::Member => {
+                Community::is_member(channel.community_id, target_id, &mut *db).await?
+            }
+        };
+        if !target_exists {
+            return Err(error!(
+                VALIDATION,
+                "target_id", "The overwrite's target must be a role or member of the community"
+            ));
+        }
+        sqlx::query!(
+            "
+INSERT INTO channel_overwrites(channel_id, target_id, overwrite_type, allow, deny)
+VALUES($1, $2, $3, $4, $5)
+ON CONFLICT (channel_id, target_id)
+DO UPDATE SET overwrite_type = $3, allow = $4, deny = $5
+            ",
+            id as i64,
+            target_id as i64,
+            overwrite.overwrite_type as OverwriteType,
+            overwrite.allow as i64,
+            overwrite.deny as i64,
+        )
+        .execute(&mut *db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't store channel overwrite: {}", err);
+            error!(SERVER, "Failed to update channel overwrites")
+        })?;
+        with_overwrites(vec![channel], db)
+            .await
+            .map(|mut c| c.remove(0))
+    }
+
+    /// Remove a channel's overwrite for a role or member.
+    pub async fn delete_overwrite(
+        id: u64,
+        target_id: u64,
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Self, ErrorResponse> {
+        let channel = Self::get(id, user_id, &mut *db).await?;
+        let result = sqlx::query!(
+            "
+DELETE FROM channel_overwrites
+WHERE channel_id = $1
+AND target_id = $2
+            ",
+            id as i64,
+            target_id as i64,
+        )
+        .execute(&mut *db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't delete channel overwrite: {}", err);
+            error!(SERVER, "Failed to update channel overwrites")
+        })?;
+        if result.rows_affected() == 0 {
+            return Err(error!(NOT_FOUND));
+        }
+        with_overwrites(vec![channel], db)
+            .await
+            .map(|mut c| c.remove(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::{ChannelCreate, ChannelType, UpdateChannel};
+
+    #[test]
+    fn validate_channel() {
+        let mut channel = ChannelCreate {
+            channel_type: ChannelType::Text,
+            name: " general ".to_string(),
+            topic: Some("".to_string()),
+            position: None,
+            parent_id: None,
+        };
+        channel.ensure_valid();
+        assert_eq!(channel.name, "general");
+        assert!(channel.validate().is_err());
+        channel.topic = None;
+        assert!(channel.validate().is_ok());
+        channel.name = "g".repeat(33);
+        assert!(channel.validate().is_err());
+
+        let update = UpdateChannel {
+            name: None,
+            topic: None,
+            position: None,
+            parent_id: None,
+        };
+        assert!(update.validate().is_err());
+    }
+}
@@ -0,0 +1,421 @@
+use sqlx::{pool::PoolConnection, postgres::PgRow, Connection, Postgres, QueryBuilder, Row};
+
+use crate::{
+    ids::IdGenerator,
+    models::{Community, CommunityCreate, ErrorResponse, File, Role, UpdateCommunity},
+};
+
+fn validate_name(name: &str) -> Result<(), ErrorResponse> {
+    if name.is_empty() || name.len() > 32 {
+        Err(error!(
+            VALIDATION,
+            "name", "The community's name must be between 1 and 32 characters in length"
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+fn validate_description(description: &str) -> Result<(), ErrorResponse> {
+    if description.is_empty() || description.len() > 500 {
+        Err(error!(
+            VALIDATION,
+            "description",
+            "The community's description must be between 1 and 500 characters in length"
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+async fn validate_icon(icon: u64, db: &mut PoolConnection<Postgres>) -> Result<(), ErrorResponse> {
+    if File::get(icon, "avatars", db).await.is_none() {
+        Err(error!(
+            VALIDATION,
+            "icon", "The community's icon must be a valid file that must exist"
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+async fn validate_banner(
+    banner: u64,
+    db: &mut PoolConnection<Postgres>,
+) -> Result<(), ErrorResponse> {
+    if File::get(banner, "banners", db).await.is_none() {
+        Err(error!(
+            VALIDATION,
+            "banner", "The community's banner must be a valid file that must exist"
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+impl CommunityCreate {
+    pub fn ensure_valid(&mut self) {
+        self.name = self.name.trim().to_string();
+        self.description = self.description.as_ref().map(|d| d.trim().to_string());
+    }
+
+    pub async fn validate(&self, db: &mut PoolConnection<Postgres>) -> Result<(), ErrorResponse> {
+        validate_name(&self.name)?;
+        if let Some(description) = &self.description {
+            validate_description(description)?;
+        }
+        if let Some(icon) = self.icon {
+            validate_icon(icon, &mut *db).await?;
+        }
+        if let Some(banner) = self.banner {
+            validate_banner(banner, db).await?;
+        }
+        Ok(())
+    }
+}
+
+impl UpdateCommunity {
+    pub fn ensure_valid(&mut self) {
+        self.name = self.name.as_ref().map(|n| n.trim().to_string());
+        if let Some(Some(description)) = &self.description {
+            self.description = Some(Some(description.trim().to_string()));
+        }
+    }
+
+    pub async fn validate(&self, db: &mut PoolConnection<Postgres>) -> Result<(), ErrorResponse> {
+        if self.name.is_none()
+            && self.description.is_none()
+            && self.icon.is_none()
+            && self.banner.is_none()
+            && self.private.is_none()
+        {
+            return Err(error!(VALIDATION, "body", "At least one field must exist"));
+        }
+        if let Some(name) = &self.name {
+            validate_name(name)?;
+        }
+        if let Some(Some(description)) = &self.description {
+            validate_description(description)?;
+        }
+        if let Some(Some(icon)) = self.icon {
+            validate_icon(icon, &mut *db).await?;
+        }
+        if let Some(Some(banner)) = self.banner {
+            validate_banner(banner, db).await?;
+        }
+        Ok(())
+    }
+}
+
+impl Community {
+    fn from_row(row: PgRow) -> Self {
+        Self {
+            id: row.get::<i64, _>("id") as u64,
+            owner_id: row.get::<i64, _>("owner_id") as u64,
+            name: row.get("name"),
+            description: row.get("description"),
+            icon: row.get::<Option<i64>, _>("icon").map(|i| i as u64),
+            banner: row.get::<Option<i64>, _>("banner").map(|b| b as u64),
+            private: row.get("private"),
+        }
+    }
+
+    pub async fn create(
+        mut community: CommunityCreate,
+        owner_id: u64,
+        id_generator: &mut IdGenerator,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Self, ErrorResponse> {
+        community.ensure_valid();
+        community.validate(&mut *db).await?;
+        let id = id_generator.generate();
+        let mut tx = db.begin().await.map_err(|err| {
+            log::error!("Couldn't start community creation transaction: {}", err);
+            error!(SERVER, "Could not create community")
+        })?;
+        sqlx::query!(
+            "
+INSERT INTO communities(id, owner_id, name, description, icon, banner, private)
+VALUES($1, $2, $3, $4, $5, $6, $7)
+            ",
+            id as i64,
+            owner_id as i64,
+            community.name,
+            community.description,
+            community.icon.map(|i| i as i64),
+            community.banner.map(|b| b as i64),
+            community.private,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|err| {
+            log::error!("Failed to store community in database: {}", err);
+            error!(SERVER, "Could not create community")
+        })?;
+        sqlx::query!(
+            "
+INSERT INTO community_members(community_id, user_id)
+VALUES($1, $2)
+            ",
+            id as i64,
+            owner_id as i64,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|err| {
+            log::error!("Failed to add community owner as a member: {}", err);
+            error!(SERVER, "Could not create community")
+        })?;
+        Role::create_default(id, &mut tx).await?;
+        tx.commit().await.map_err(|err| {
+            log::error!("Couldn't commit community creation: {}", err);
+            error!(SERVER, "Could not create community")
+        })?;
+        Ok(Self {
+            id,
+            owner_id,
+            name: community.name,
+            description: community.description,
+            icon: community.icon,
+            banner: community.banner,
+            private: community.private,
+        })
+    }
+
+    /// Get a community, private communities can only be fetched by their members.
+    pub async fn get(
+        id: u64,
+        requester_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Self, ErrorResponse> {
+        let community = sqlx::query(
+            "
+SELECT *
+FROM communities
+WHERE id = $1
+            ",
+        )
+        .bind(id as i64)
+        .fetch_optional(&mut *db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't fetch community from database: {}", err);
+            error!(SERVER, "Failed to fetch community")
+        })?
+        .map(Self::from_row)
+        .ok_or_else(|| error!(NOT_FOUND))?;
+        if community.private && !Self::is_member(id, requester_id, db).await? {
+            return Err(error!(NOT_FOUND));
+        }
+        Ok(community)
+    }
+
+    /// Get the communities a user is a member of.
+    pub async fn get_joined(
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Vec<Self>, ErrorResponse> {
+        sqlx::query(
+            "
+SELECT c.*
+FROM communities c
+JOIN community_members m
+ON c.id = m.community_id
+WHERE m.user_id = $1
+ORDER BY c.id
+            ",
+        )
+        .bind(user_id as i64)
+        .fetch_all(db)
+        .await
+        .map(|rows| rows.into_iter().map(Self::from_row).collect())
+        .map_err(|err| {
+            log::error!("Couldn't fetch user communities: {}", err);
+            error!(SERVER, "Failed to fetch communities")
+        })
+    }
+
+    pub async fn is_member(
+        id: u64,
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<bool, ErrorResponse> {
+        sqlx::query!(
+            r#"
+SELECT EXISTS(
+  SELECT 1
+  FROM community_members
+  WHERE community_id = $1
+  AND user_id = $2
+) AS "is_member!"
+            "#,
+            id as i64,
+            user_id as i64,
+        )
+        .fetch_one(db)
+        .await
+        .map(|r| r.is_member)
+        .map_err(|err| {
+            log::error!("Couldn't check community membership: {}", err);
+            error!(SERVER, "Failed to check community membership")
+        })
+    }
+
+    pub async fn update(
+        id: u64,
+        user_id: u64,
+        mut update: UpdateCommunity,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Self, ErrorResponse> {
+        update.ensure_valid();
+        Self::get(id, user_id, &mut *db).await?;
+        update.validate(&mut *db).await?;
+        let mut query: QueryBuilder<Postgres> = QueryBuilder::new("UPDATE communities SET ");
+        let mut seperated = query.separated(", ");
+        if let Some(name) = update.name {
+            seperated.push("name = ").push_bind_unseparated(name);
+        }
+        if let Some(description) = update.description {
+            seperated
+                .push("description = ")
+                .push_bind_unseparated(description);
+        }
+        if let Some(icon) = update.icon {
+            seperated
+                .push("icon = ")
+                .push_bind_unseparated(icon.map(|i| i as i64));
+        }
+        if let Some(banner) = update.banner {
+            seperated
+                .push("banner = ")
+                .push_bind_unseparated(banner.map(|b| b as i64));
+        }
+        if let Some(private) = update.private {
+            seperated.push("private = ").push_bind_unseparated(private);
+        }
+        query
+            .push(" WHERE id = ")
+            .push_bind(id as i64)
+            .push(" RETURNING *")
+            .build()
+            .fetch_one(db)
+            .await
+            .map(Self::from_row)
+            .map_err(|err| {
+                log::error!("Couldn't update community: {}", err);
+                error!(SERVER, "Failed to update community")
+            })
+    }
+
+    pub async fn delete(
+        id: u64,
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<(), ErrorResponse> {
+        let community = Self::get(id, user_id, &mut *db).await?;
+        if community.owner_id != user_id {
+            return Err(error!(FORBIDDEN));
+        }
+        sqlx::query!(
+            "
+DELETE FROM communities
+WHERE id = $1
+            ",
+            id as i64
+        )
+        .execute(db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't delete community: {}", err);
+            error!(SERVER, "Failed to delete community")
+        })?;
+        Ok(())
+    }
+
+    /// Join a public community.
+    ///
+    /// Returns whether the user wasn't already a member of the community.
+    pub async fn join(
+        id: u64,
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<bool, ErrorResponse> {
+        let community = Self::get(id, user_id, &mut *db).await?;
+        if community.private {
+            // Private communities can only be seen by users who are already members of them.
+            return Ok(false);
+        }
+        sqlx::query!(
+            "
+INSERT INTO community_members(community_id, user_id)
+VALUES($1, $2)
+ON CONFLICT DO NOTHING
+            ",
+            id as i64,
+            user_id as i64,
+        )
+        .execute(db)
+        .await
+        .map(|r| r.rows_affected() > 0)
+        .map_err(|err| {
+            log::error!("Couldn't add community member: {}", err);
+            error!(SERVER, "Failed to join community")
+        })
+    }
+
+    /// Leave a community. The owner of a community can't leave it.
+    pub async fn leave(
+        id: u64,
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<(), ErrorResponse> {
+        let community = Self::get(id, user_id, &mut *db).await?;
+        if community.owner_id == user_id {
+            return Err(error!(
+                VALIDATION,
+                "community", "The owner of a community can't leave it"
+            ));
+        }
+        let result = sqlx::query!(
+            "
+DELETE FROM community_members
+WHERE community_id = $1
+AND user_id = $2
+            ",
+            id as i64,
+            user_id as i64,
+        )
+        .execute(db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't remove community member: {}", err);
+            error!(SERVER, "Failed to leave community")
+        })?;
+        if result.rows_affected() == 0 {
+            return Err(error!(NOT_FOUND));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::CommunityCreate;
+
+    #[test]
+    fn ensure_valid_community() {
+        let mut community = CommunityCreate {
+            name: "  Woo  ".to_string(),
+            description: Some(" A place for woo ".to_string()),
+            icon: None,
+            banner: None,
+            private: false,
+        };
+        community.ensure_valid();
+        assert_eq!(community.name, "Woo");
+        assert_eq!(community.description.unwrap(), "A place for woo");
+        assert!(super::validate_name("").is_err());
+        assert!(super::validate_name(&"w".repeat(33)).is_err());
+        assert!(super::validate_description(&"w".repeat(501)).is_err());
+    }
+}
@@ -0,0 +1,483 @@
+use sqlx::{pool::PoolConnection, Postgres};
+
+use crate::{
+    ids::IdGenerator,
+    models::{
+        DirectChannel, DirectChannelType, ErrorResponse, GroupCreate, Permissions, Relationship,
+        User,
+    },
+};
+
+/// The maximum amount of recipients a group can have, including its owner.
+pub const GROUP_MAX_RECIPIENTS: usize = 10;
+
+fn validate_name(name: &str) -> Result<(), ErrorResponse> {
+    if name.is_empty() || name.len() > 32 {
+        Err(error!(
+            VALIDATION,
+            "name", "The group's name must be between 1 and 32 characters in length"
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Add recipients to a direct channel.
+async fn add_recipients(
+    channel_id: u64,
+    recipients: &[u64],
+    db: &mut PoolConnection<Postgres>,
+) -> Result<(), ErrorResponse> {
+    let recipients: Vec<i64> = recipients.iter().map(|r| *r as i64).collect();
+    sqlx::query!(
+        "
+INSERT INTO channel_recipients(channel_id, user_id)
+SELECT $1, UNNEST($2::BIGINT[])
+ON CONFLICT DO NOTHING
+        ",
+        channel_id as i64,
+        &recipients
+    )
+    .execute(db)
+    .await
+    .map_err(|err| {
+        log::error!("Failed to store channel recipients in database: {}", err);
+        error!(SERVER, "Could not add recipients")
+    })?;
+    Ok(())
+}
+
+impl GroupCreate {
+    pub fn ensure_valid(&mut self) {
+        self.name = self.name.as_ref().map(|n| n.trim().to_string());
+    }
+
+    pub fn validate(&self) -> Result<(), ErrorResponse> {
+        if let Some(name) = &self.name {
+            validate_name(name)?;
+        }
+        if self.recipients.len() >= GROUP_MAX_RECIPIENTS {
+            return Err(error!(
+                VALIDATION,
+                "recipients",
+                format!(
+                    "A group can have at most {} recipients",
+                    GROUP_MAX_RECIPIENTS
+                )
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl DirectChannel {
+    /// Open a DM with another user, returns the existing DM between the two users if there's one.
+    ///
+    /// The returned boolean is whether the DM was newly created.
+    pub async fn open(
+        user_id: u64,
+        recipient_id: u64,
+        id_generator: &mut IdGenerator,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<(Self, bool), ErrorResponse> {
+        if user_id == recipient_id {
+            return Err(error!(
+                VALIDATION,
+                "recipient", "You can't open a DM with yourself"
+            ));
+        }
+        if Relationship::is_blocked(user_id, recipient_id, &mut *db).await? {
+            return Err(error!(FORBIDDEN));
+        }
+        if let Some(existing) = sqlx::query!(
+            "
+SELECT c.id
+FROM channels c
+JOIN channel_recipients a
+ON c.id = a.channel_id
+JOIN channel_recipients b
+ON c.id = b.channel_id
+WHERE c.channel_type = 'DM'
+AND a.user_id = $1
+AND b.user_id = $2
+            ",
+            user_id as i64,
+            recipient_id as i64,
+        )
+        .fetch_optional(&mut *db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't fetch existing DM: {}", err);
+            error!(SERVER, "Failed to open DM")
+        })? {
+            return Ok((Self::get(existing.id as u64, user_id, db).await?, false));
+        }
+        if !User::exists(recipient_id, &mut *db).await? {
+            return Err(error!(
+                VALIDATION,
+                "recipient",
+                format!("Unknown user {}", recipient_id)
+            ));
+        }
+        let id = id_generator.generate();
+        sqlx::query!(
+            "
+INSERT INTO channels(id, channel_type)
+VALUES($1, 'DM')
+            ",
+            id as i64,
+        )
+        .execute(&mut *db)
+        .await
+        .map_err(|err| {
+            log::error!("Failed to store DM in database: {}", err);
+            error!(SERVER, "Could not open DM")
+        })?;
+        let mut recipients = vec![user_id, recipient_id];
+        recipients.sort_unstable();
+        add_recipients(id, &recipients, db).await?;
+        Ok((
+            Self {
+                id,
+                channel_type: DirectChannelType::Dm,
+                name: None,
+                owner_id: None,
+                recipients,
+            },
+            true,
+        ))
+    }
+
+    /// Create a group DM owned by the user.
+    pub async fn create_group(
+        mut group: GroupCreate,
+        owner_id: u64,
+        id_generator: &mut IdGenerator,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Self, ErrorResponse> {
+        group.ensure_valid();
+        group.validate()?;
+        let mut recipients = vec![owner_id];
+        for recipient_id in group.recipients {
+            if recipients.contains(&recipient_id) {
+                continue;
+            }
+            if !User::exists(recipient_id, &mut *db).await? {
+                return Err(error!(
+                    VALIDATION,
+                    "recipients",
+                    format!("Unknown user {}", recipient_id)
+                ));
+            }
+            if Relationship::is_blocked(owner_id, recipient_id, &mut *db).await? {
+                return Err(error!(FORBIDDEN));
+            }
+            recipients.push(recipient_id);
+        }
+        recipients.sort_unstable();
+        let id = id_generator.generate();
+        sqlx::query!(
+            "
+INSERT INTO channels(id, channel_type, name, owner_id)
+VALUES($1, 'GROUP', $2, $3)
+            ",
+            id as i64,
+            group.name,
+            owner_id as i64,
+        )
+        .execute(&mut *db)
+        .await
+        .map_err(|err| {
+            log::error!("Failed to store group in database: {}", err);
+            error!(SERVER, "Could not create group")
+        })?;
+        add_recipients(id, &recipients, db).await?;
+        Ok(Self {
+            id,
+            channel_type: DirectChannelType::Group,
+            name: group.name,
+            owner_id: Some(owner_id),
+            recipients,
+        })
+    }
+
+    /// Get a direct channel, direct channels can only be fetched by their recipients.
+    pub async fn get(
+        id: u64,
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Self, ErrorResponse> {
+        let channel = sqlx::query!(
+            r#"
+SELECT
+  c.id,
+  c.channel_type as "channel_type: DirectChannelType",
+  c.name,
+  c.owner_id,
+  ARRAY(
+    SELECT user_id
+    FROM channel_recipients
+    WHERE channel_id = c.id
+    ORDER BY user_id
+  ) AS "recipients!"
+FROM channels c
+WHERE c.id = $1
+AND c.channel_type IN ('DM', 'GROUP')
+            "#,
+            id as i64
+        )
+        .fetch_optional(db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't fetch direct channel from database: {}", err);
+            error!(SERVER, "Failed to fetch direct channel")
+        })?
+        .map(|c| Self {
+            id: c.id as u64,
+            channel_type: c.channel_type,
+            name: c.name,
+            owner_id: c.owner_id.map(|o| o as u64),
+            recipients: c.recipients.into_iter().map(|r| r as u64).collect(),
+        })
+        .ok_or_else(|| error!(NOT_FOUND))?;
+        if !channel.recipients.contains(&user_id) {
+            return Err(error!(NOT_FOUND));
+        }
+        Ok(channel)
+    }
+
+    /// Get all of a user's direct channels.
+    pub async fn get_joined(
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Vec<Self>, ErrorResponse> {
+        sqlx::query!(
+            r#"
+SELECT
+  c.id,
+  c.channel_type as "channel_type: DirectChannelType",
+  c.name,
+  c.owner_id,
+  ARRAY(
+    SELECT user_id
+    FROM channel_recipients
+    WHERE channel_id = c.id
+    ORDER BY user_id
+  ) AS "recipients!"
+FROM channels c
+JOIN channel_recipients r
+ON c.id = r.channel_id
+WHERE r.user_id = $1
+ORDER BY c.id
+            "#,
+            user_id as i64
+        )
+        .fetch_all(db)
+        .await
+        .map(|rows| {
+            rows.into_iter()
+                .map(|c| Self {
+                    id: c.id as u64,
+                    channel_type: c.channel_type,
+                    name: c.name,
+                    owner_id: c.owner_id.map(|o| o as u64),
+                    recipients: c.recipients.into_iter().map(|r| r as u64).collect(),
+                })
+                .collect()
+        })
+        .map_err(|err| {
+            log::error!("Couldn't fetch user direct channels: {}", err);
+            error!(SERVER, "Failed to fetch direct channels")
+        })
+    }
+
+    /// Add a user to a group you're in.
+    pub async fn add_recipient(
+        id: u64,
+        recipient_id: u64,
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Self, ErrorResponse> {
+        let mut channel = Self::get(id, user_id, &mut *db).await?;
+        if channel.channel_type != DirectChannelType::Group {
+            return Err(error!(
+                VALIDATION,
+                "channel", "Recipients can only be added to groups"
+            ));
+        }
+        if channel.recipients.contains(&recipient_id) {
+            return Ok(channel);
+        }
+        if channel.recipients.len() >= GROUP_MAX_RECIPIENTS {
+            return Err(error!(
+                VALIDATION,
+                "recipients",
+                format!(
+                    "A group can have at most {} recipients",
+                    GROUP_MAX_RECIPIENTS
+                )
+            ));
+        }
+        if !User::exists(recipient_id, &mut *db).await? {
+            return Err(error!(
+                VALIDATION,
+                "recipient",
+                format!("Unknown user {}", recipient_id)
+            ));
+        }
+        if Relationship::is_blocked(user_id, recipient_id, &mut *db).await? {
+            return Err(error!(FORBIDDEN));
+        }
+        add_recipients(id, &[recipient_id], db).await?;
+        channel.recipients.push(recipient_id);
+        channel.recipients.sort_unstable();
+        Ok(channel)
+    }
+
+    /// Remove a user from a group. Only the group's owner can remove other users, anyone can
+    /// leave a group.
+    ///
+    /// The group's ownership is transferred to another recipient when its owner leaves it and the
+    /// group is deleted once it has no recipients left, in which case `None` is returned.
+    pub async fn remove_recipient(
+        id: u64,
+        recipient_id: u64,
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Option<Self>, ErrorResponse> {
+        let mut channel = Self::get(id, user_id, &mut *db).await?;
+        if channel.channel_type != DirectChannelType::Group {
+            return Err(error!(
+                VALIDATION,
+                "channel", "Recipients can only be removed from groups"
+            ));
+        }
+        if recipient_id != user_id && channel.owner_id != Some(user_id) {
+            return Err(error!(FORBIDDEN));
+        }
+        if !channel.recipients.contains(&recipient_id) {
+            return Err(error!(NOT_FOUND));
+        }
+        channel.recipients.retain(|r| *r != recipient_id);
+        if channel.recipients.is_empty() {
+            sqlx::query!(
+                "
+DELETE FROM channels
+WHERE id = $1
+                ",
+                id as i64
+            )
+            .execute(db)
+            .await
+            .map_err(|err| {
+                log::error!("Couldn't delete group: {}", err);
+                error!(SERVER, "Failed to remove recipient")
+            })?;
+            return Ok(None);
+        }
+        sqlx::query!(
+            "
+DELETE FROM channel_recipients
+WHERE channel_id = $1
+AND user_id = $2
+            ",
+            id as i64,
+            recipient_id as i64,
+        )
+        .execute(&mut *db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't remove channel recipient: {}", err);
+            error!(SERVER, "Failed to remove recipient")
+        })?;
+        if channel.owner_id == Some(recipient_id) {
+            channel.owner_id = Some(channel.recipients[0]);
+            sqlx::query!(
+                "
+UPDATE channels
+SET owner_id = $1
+WHERE id = $2
+                ",
+                channel.recipients[0] as i64,
+                id as i64,
+            )
+            .execute(db)
+            .await
+            .map_err(|err| {
+                log::error!("Couldn't transfer group ownership: {}", err);
+                error!(SERVER, "Failed to remove recipient")
+            })?;
+        }
+        Ok(Some(channel))
+    }
+
+    /// Get a user's permissions in a direct channel.
+    ///
+    /// Recipients have the [`Permissions::DEFAULT`] permissions, except in DMs with users who
+    /// blocked them where they can only view the channel.
+    pub(crate) async fn get_permissions(
+        id: u64,
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Permissions, ErrorResponse> {
+        let recipient = sqlx::query!(
+            r#"
+SELECT
+  EXISTS(
+    SELECT 1
+    FROM channel_recipients
+    WHERE channel_id = $1
+    AND user_id = $2
+  ) AS "is_recipient!",
+  EXISTS(
+    SELECT 1
+    FROM channels c
+    JOIN channel_recipients cr
+    ON c.id = cr.channel_id
+    JOIN relationships r
+    ON cr.user_id = r.user_id
+    WHERE c.id = $1
+    AND c.channel_type = 'DM'
+    AND r.target_id = $2
+    AND r.relationship_type = 'BLOCKED'
+  ) AS "is_blocked!"
+            "#,
+            id as i64,
+            user_id as i64,
+        )
+        .fetch_one(db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't check channel recipients: {}", err);
+            error!(SERVER, "Failed to check channel recipients")
+        })?;
+        Ok(if !recipient.is_recipient {
+            Permissions::empty()
+        } else if recipient.is_blocked {
+            Permissions::VIEW_CHANNEL
+        } else {
+            Permissions::DEFAULT
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::GroupCreate;
+
+    #[test]
+    fn validate_group() {
+        let mut group = GroupCreate {
+            name: Some("  Woo  ".to_string()),
+            recipients: (0..9).collect(),
+        };
+        group.ensure_valid();
+        assert_eq!(group.name.as_deref(), Some("Woo"));
+        assert!(group.validate().is_ok());
+        group.recipients.push(9);
+        assert!(group.validate().is_err());
+        group.recipients.clear();
+        group.name = Some("".to_string());
+        assert!(group.validate().is_err());
+    }
+}
@@ -0,0 +1,64 @@
+use redis::{AsyncCommands, RedisResult};
+use serde::{Deserialize, Serialize};
+
+use crate::models::ServerPayload;
+
+/// The redis channel events are published on for pandemonium to deliver.
+pub const EVENTS_CHANNEL: &str = "eludris-events";
+
+/// Who an [`Event`] gets delivered to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[serde(tag = "type", content = "id")]
+pub enum EventTarget {
+    /// Every connection of a user.
+    User(u64),
+    /// The connections of every user who can see a channel.
+    Channel(u64),
+    /// The connections of every member of a community.
+    Community(u64),
+    /// The connections subscribed to a user's presence.
+    Presence(u64),
+    /// The connections of the instance's moderators.
+    Moderators,
+    /// Every connection.
+    Everyone,
+}
+
+impl EventTarget {
+    /// Get the target of an event about a message, the events of the messages which were sent
+    /// before channels existed and don't belong to any only go to their author.
+    pub fn message(channel_id: Option<u64>, author_id: u64) -> Self {
+        match channel_id {
+            Some(id) => Self::Channel(id),
+            None => Self::User(author_id),
+        }
+    }
+}
+
+/// An internal event telling pandemonium to dispatch a payload to the connections it targets.
+///
+/// Pandemonium still checks whether each targeted session should receive the payload.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    /// Who the payload is delivered to.
+    pub targets: Vec<EventTarget>,
+    /// The dispatched payload.
+    pub payload: ServerPayload,
+}
+
+impl Event {
+    pub fn new(payload: ServerPayload, targets: Vec<EventTarget>) -> Self {
+        Self { targets, payload }
+    }
+
+    /// Publish the event on the [`EVENTS_CHANNEL`].
+    pub async fn publish<C: AsyncCommands>(&self, cache: &mut C) -> RedisResult<()> {
+        cache
+            .publish(
+                EVENTS_CHANNEL,
+                serde_json::to_string(self).expect("Couldn't serialize event"),
+            )
+            .await
+    }
+}
@@ -14,13 +14,9 @@ use sqlx::{pool::PoolConnection, Postgres};
 use tokio::fs;
 
 #[cfg(feature = "http")]
-use crate::{
-    error,
-    ids::IdGenerator,
-    models::{ErrorResponse, FileData, FileMetadata},
-};
+use crate::{error, ids::IdGenerator, models::ErrorResponse};
 
-use crate::models::File;
+use crate::models::{File, FileData, FileMetadata};
 
 #[cfg(feature = "http")]
 #[derive(Debug, Responder)]
@@ -60,6 +56,7 @@ impl File {
         id_generator: &mut IdGenerator,
         db: &mut PoolConnection<Postgres>,
         spoiler: bool,
+        owner_id: Option<u64>,
     ) -> Result<FileData, ErrorResponse> {
         if file.len() == 0 {
             return Err(error!(
@@ -105,8 +102,8 @@ AND bucket = $2
             fs::remove_file(path).await.unwrap();
             sqlx::query!(
                 "
-INSERT INTO files(id, file_id, name, content_type, hash, bucket, spoiler, width, height)
-VALUES($1, $2, $3, $4, $5, $6, $7, $8, $9)
+INSERT INTO files(id, file_id, name, content_type, hash, bucket, spoiler, width, height, owner_id)
+VALUES($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
                     ",
                 id as i64,
                 file_id as i64,
@@ -117,6 +114,7 @@ VALUES($1, $2, $3, $4, $5, $6, $7, $8, $9)
                 spoiler,
                 width as Option<i32>,
                 height as Option<i32>,
+                owner_id.map(|o| o as i64),
             )
             .execute(&mut *db)
             .await
@@ -132,6 +130,7 @@ VALUES($1, $2, $3, $4, $5, $6, $7, $8, $9)
                 spoiler,
                 width: width.map(|s| s as usize),
                 height: height.map(|s| s as usize),
+                owner_id,
             }
         } else {
             let file = tokio::task::spawn_blocking(move || {
@@ -229,14 +228,15 @@ VALUES($1, $2, $3, $4, $5, $6, $7, $8, $9)
                     spoiler,
                     width,
                     height,
+                    owner_id,
                 })
             })
             .await
             .unwrap()?;
             sqlx::query!(
                 "
-INSERT INTO files(id, file_id, name, content_type, hash, bucket, spoiler, width, height)
-VALUES($1, $2, $3, $4, $5, $6, $7, $8, $9)
+INSERT INTO files(id, file_id, name, content_type, hash, bucket, spoiler, width, height, owner_id)
+VALUES($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
                 ",
                 file.id as i64,
                 file.id as i64,
@@ -247,6 +247,7 @@ VALUES($1, $2, $3, $4, $5, $6, $7, $8, $9)
                 file.spoiler,
                 file.width.map(|s| s as i32),
                 file.height.map(|s| s as i32),
+                file.owner_id.map(|o| o as i64),
             )
             .execute(&mut *db)
             .await
@@ -285,6 +286,7 @@ AND bucket = $2
             spoiler: r.spoiler,
             width: r.width.map(|s| s as usize),
             height: r.height.map(|s| s as usize),
+            owner_id: r.owner_id.map(|o| o as u64),
         })
         .ok()
     }
@@ -361,8 +363,7 @@ AND bucket = $2
             .map(|f| f.get_file_data())
     }
 
-    #[cfg(feature = "http")]
-    fn get_file_data(self) -> FileData {
+    pub fn get_file_data(self) -> FileData {
         let metadata = match self.content_type.as_ref() {
             "image/gif" | "image/jpeg" | "image/png" | "image/webp" => {
                 if self.width.is_some() && self.height.is_some() {
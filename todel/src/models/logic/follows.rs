@@ -0,0 +1,192 @@
+use redis::AsyncCommands;
+use sqlx::{pool::PoolConnection, Postgres};
+
+use crate::models::{ErrorResponse, Relationship, User};
+
+/// The default amount of users returned by [`User::get_followers`] and [`User::get_following`].
+pub const FOLLOW_LIST_DEFAULT_LIMIT: u32 = 50;
+/// The maximum amount of users returned by [`User::get_followers`] and [`User::get_following`].
+pub const FOLLOW_LIST_MAX_LIMIT: u32 = 100;
+
+fn validate_limit(limit: Option<u32>) -> Result<u32, ErrorResponse> {
+    let limit = limit.unwrap_or(FOLLOW_LIST_DEFAULT_LIMIT);
+    if limit == 0 || limit > FOLLOW_LIST_MAX_LIMIT {
+        return Err(error!(
+            VALIDATION,
+            "limit",
+            format!(
+                "The user limit must be between 1 and {}",
+                FOLLOW_LIST_MAX_LIMIT
+            )
+        ));
+    }
+    Ok(limit)
+}
+
+impl User {
+    /// Follow another user.
+    ///
+    /// Returns whether the user wasn't already following them.
+    pub async fn follow(
+        id: u64,
+        follower_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<bool, ErrorResponse> {
+        if id == follower_id {
+            return Err(error!(VALIDATION, "user", "You can't follow yourself"));
+        }
+        if !Self::exists(id, &mut *db).await? {
+            return Err(error!(NOT_FOUND));
+        }
+        if Relationship::is_blocked(follower_id, id, &mut *db).await? {
+            return Err(error!(FORBIDDEN));
+        }
+        sqlx::query!(
+            "
+INSERT INTO follows(follower_id, user_id)
+VALUES($1, $2)
+ON CONFLICT DO NOTHING
+            ",
+            follower_id as i64,
+            id as i64,
+        )
+        .execute(db)
+        .await
+        .map(|r| r.rows_affected() > 0)
+        .map_err(|err| {
+            log::error!("Couldn't store follow in database: {}", err);
+            error!(SERVER, "Failed to follow user")
+        })
+    }
+
+    /// Stop following a user.
+    pub async fn unfollow(
+        id: u64,
+        follower_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<(), ErrorResponse> {
+        let result = sqlx::query!(
+            "
+DELETE FROM follows
+WHERE follower_id = $1
+AND user_id = $2
+            ",
+            follower_id as i64,
+            id as i64,
+        )
+        .execute(db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't remove follow: {}", err);
+            error!(SERVER, "Failed to unfollow user")
+        })?;
+        if result.rows_affected() == 0 {
+            return Err(error!(NOT_FOUND));
+        }
+        Ok(())
+    }
+
+    /// Get a page of the users following a user, ordered from newest to oldest ID.
+    pub async fn get_followers<C: AsyncCommands>(
+        id: u64,
+        before: Option<u64>,
+        limit: Option<u32>,
+        db: &mut PoolConnection<Postgres>,
+        cache: &mut C,
+    ) -> Result<Vec<Self>, ErrorResponse> {
+        let limit = validate_limit(limit)?;
+        if !Self::exists(id, &mut *db).await? {
+            return Err(error!(NOT_FOUND));
+        }
+        let ids = sqlx::query!(
+            "
+SELECT f.follower_id
+FROM follows f
+JOIN users u
+ON f.follower_id = u.id
+WHERE f.user_id = $1
+AND f.follower_id < $2
+AND u.is_deleted = FALSE
+ORDER BY f.follower_id DESC
+LIMIT $3
+            ",
+            id as i64,
+            before.map(|b| b as i64).unwrap_or(i64::MAX),
+            limit as i64,
+        )
+        .fetch_all(&mut *db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't fetch user followers: {}", err);
+            error!(SERVER, "Failed to fetch followers")
+        })?;
+        let mut users = Vec::with_capacity(ids.len());
+        for row in ids {
+            users.push(Self::get(row.follower_id as u64, None, &mut *db, cache).await?);
+        }
+        Ok(users)
+    }
+
+    /// Get a page of the users a user follows, ordered from newest to oldest ID.
+    pub async fn get_following<C: AsyncCommands>(
+        id: u64,
+        before: Option<u64>,
+        limit: Option<u32>,
+        db: &mut PoolConnection<Postgres>,
+        cache: &mut C,
+    ) -> Result<Vec<Self>, ErrorResponse> {
+        let limit = validate_limit(limit)?;
+        if !Self::exists(id, &mut *db).await? {
+            return Err(error!(NOT_FOUND));
+        }
+        let ids = sqlx::query!(
+            "
+SELECT f.user_id
+FROM follows f
+JOIN users u
+ON f.user_id = u.id
+WHERE f.follower_id = $1
+AND f.user_id < $2
+AND u.is_deleted = FALSE
+ORDER BY f.user_id DESC
+LIMIT $3
+            ",
+            id as i64,
+            before.map(|b| b as i64).unwrap_or(i64::MAX),
+            limit as i64,
+        )
+        .fetch_all(&mut *db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't fetch followed users: {}", err);
+            error!(SERVER, "Failed to fetch followed users")
+        })?;
+        let mut users = Vec::with_capacity(ids.len());
+        for row in ids {
+            users.push(Self::get(row.user_id as u64, None, &mut *db, cache).await?);
+        }
+        Ok(users)
+    }
+
+    /// Get the IDs of all the users a user follows.
+    pub async fn get_following_ids(
+        id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Vec<u64>, ErrorResponse> {
+        sqlx::query!(
+            "
+SELECT user_id
+FROM follows
+WHERE follower_id = $1
+            ",
+            id as i64,
+        )
+        .fetch_all(db)
+        .await
+        .map(|rows| rows.into_iter().map(|r| r.user_id as u64).collect())
+        .map_err(|err| {
+            log::error!("Couldn't fetch followed users: {}", err);
+            error!(SERVER, "Failed to fetch followed users")
+        })
+    }
+}
@@ -0,0 +1,207 @@
+use argon2::password_hash::rand_core::CryptoRngCore;
+use rand::{distributions::Alphanumeric, Rng};
+use sqlx::{pool::PoolConnection, postgres::PgConnection, Postgres};
+
+use super::now;
+use crate::models::{ErrorResponse, Invite, InviteCreate, Permissions};
+
+/// The length of generated invite codes.
+pub const INVITE_CODE_LENGTH: usize = 8;
+
+impl InviteCreate {
+    pub fn validate(&self) -> Result<(), ErrorResponse> {
+        if let Some(max_uses) = self.max_uses {
+            if max_uses == 0 || max_uses > i32::MAX as u32 {
+                return Err(error!(
+                    VALIDATION,
+                    "max_uses", "The invite's maximum uses must be at least 1"
+                ));
+            }
+        }
+        if let Some(expires_in) = self.expires_in {
+            if expires_in == 0 || expires_in > i64::MAX as u64 / 2 {
+                return Err(error!(
+                    VALIDATION,
+                    "expires_in", "The invite's expiry must be at least 1 second"
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Invite {
+    /// Create an invite to register on the instance, this requires the `CREATE_INVITES`
+    /// instance-wide permission.
+    pub async fn create<R: CryptoRngCore>(
+        invite: InviteCreate,
+        creator_id: u64,
+        rng: &mut R,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Self, ErrorResponse> {
+        invite.validate()?;
+        Permissions::require_instance(creator_id, Permissions::CREATE_INVITES, &mut *db).await?;
+        let code: String = rng
+            .sample_iter(&Alphanumeric)
+            .take(INVITE_CODE_LENGTH)
+            .map(char::from)
+            .collect();
+        let expires_at = invite.expires_in.map(|e| now() + e);
+        sqlx::query!(
+            "
+INSERT INTO invites(code, creator_id, max_uses, expires_at)
+VALUES($1, $2, $3, $4)
+            ",
+            code,
+            creator_id as i64,
+            invite.max_uses.map(|m| m as i32),
+            expires_at.map(|e| e as i64),
+        )
+        .execute(db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't store invite in database: {}", err);
+            error!(SERVER, "Failed to create invite")
+        })?;
+        Ok(Self {
+            code,
+            creator_id,
+            uses: 0,
+            max_uses: invite.max_uses,
+            expires_at,
+        })
+    }
+
+    /// Get all of the instance's invites, this requires the `ADMINISTRATOR` instance-wide
+    /// permission.
+    pub async fn get_all(
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Vec<Self>, ErrorResponse> {
+        Permissions::require_instance(user_id, Permissions::ADMINISTRATOR, &mut *db).await?;
+        sqlx::query!(
+            "
+SELECT code, creator_id, uses, max_uses, expires_at
+FROM invites
+ORDER BY creator_id, code
+            "
+        )
+        .fetch_all(db)
+        .await
+        .map(|rows| {
+            rows.into_iter()
+                .map(|r| Self {
+                    code: r.code,
+                    creator_id: r.creator_id as u64,
+                    uses: r.uses as u32,
+                    max_uses: r.max_uses.map(|m| m as u32),
+                    expires_at: r.expires_at.map(|e| e as u64),
+                })
+                .collect()
+        })
+        .map_err(|err| {
+            log::error!("Couldn't fetch invites: {}", err);
+            error!(SERVER, "Failed to fetch invites")
+        })
+    }
+
+    /// Revoke an invite, only administrators and the invite's creator can do this.
+    pub async fn delete(
+        code: &str,
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<(), ErrorResponse> {
+        let creator_id = sqlx::query!(
+            "
+SELECT creator_id
+FROM invites
+WHERE code = $1
+            ",
+            code,
+        )
+        .fetch_optional(&mut *db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't fetch invite: {}", err);
+            error!(SERVER, "Failed to revoke invite")
+        })?
+        .ok_or_else(|| error!(NOT_FOUND))?
+        .creator_id as u64;
+        if creator_id != user_id {
+            Permissions::require_instance(user_id, Permissions::ADMINISTRATOR, &mut *db).await?;
+        }
+        sqlx::query!(
+            "
+DELETE FROM invites
+WHERE code = $1
+            ",
+            code,
+        )
+        .execute(db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't delete invite: {}", err);
+            error!(SERVER, "Failed to revoke invite")
+        })?;
+        Ok(())
+    }
+
+    /// Use up an invite while registering, failing if it doesn't exist, expired or has no uses
+    /// left.
+    pub(crate) async fn redeem(code: &str, db: &mut PgConnection) -> Result<(), ErrorResponse> {
+        let redeemed = sqlx::query!(
+            "
+UPDATE invites
+SET uses = uses + 1
+WHERE code = $1
+AND (max_uses IS NULL OR uses < max_uses)
+AND (expires_at IS NULL OR expires_at > $2)
+            ",
+            code,
+            now() as i64,
+        )
+        .execute(db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't redeem invite: {}", err);
+            error!(SERVER, "Could not create user")
+        })?
+        .rows_affected()
+            > 0;
+        if !redeemed {
+            return Err(error!(
+                VALIDATION,
+                "invite_code", "The invite code is invalid or has expired"
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::InviteCreate;
+
+    #[test]
+    fn validate_invite_create() {
+        assert!(InviteCreate::default().validate().is_ok());
+        assert!(InviteCreate {
+            max_uses: Some(5),
+            expires_in: Some(86400),
+        }
+        .validate()
+        .is_ok());
+        assert!(InviteCreate {
+            max_uses: Some(0),
+            expires_in: None,
+        }
+        .validate()
+        .is_err());
+        assert!(InviteCreate {
+            max_uses: None,
+            expires_in: Some(0),
+        }
+        .validate()
+        .is_err());
+    }
+}
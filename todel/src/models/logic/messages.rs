@@ -0,0 +1,1053 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime},
+};
+
+use lazy_static::lazy_static;
+use redis::AsyncCommands;
+use regex::Regex;
+use sqlx::{
+    pool::PoolConnection,
+    postgres::{PgConnection, PgRow},
+    Connection, Postgres, QueryBuilder, Row,
+};
+
+use crate::{
+    ids::IdGenerator,
+    models::{
+        Channel, ErrorResponse, EventTarget, File, FileData, Message, MessageCreate,
+        MessageDisguise, MessageEdit, MessageReference, Mute, Permissions, Reaction, User,
+    },
+    Conf,
+};
+
+/// The default amount of messages returned by [`Message::get_history`].
+pub const MESSAGE_HISTORY_DEFAULT_LIMIT: u32 = 50;
+/// The maximum amount of messages that can be fetched at once by [`Message::get_history`].
+pub const MESSAGE_HISTORY_MAX_LIMIT: u32 = 200;
+/// The maximum amount of attachments a single message can have.
+pub const MESSAGE_MAX_ATTACHMENTS: usize = 10;
+/// The amount of characters a [`MessageReference`]'s content is truncated to.
+pub const MESSAGE_REFERENCE_CONTENT_LIMIT: usize = 100;
+
+/// The base query used to fetch messages, only fetches messages whose author wasn't deleted.
+const MESSAGE_QUERY: &str = "
+SELECT m.id, m.channel_id, m.author_id, m.content, m.disguise_name, m.disguise_avatar, m.edited_at, m.reference_id, m.mention_reference
+FROM messages m
+LEFT JOIN users u
+ON m.author_id = u.id
+WHERE u.is_deleted = FALSE
+";
+
+pub fn validate_content(content: &str, conf: &Conf) -> Result<(), ErrorResponse> {
+    if content.is_empty() || content.len() > conf.oprish.message_limit {
+        Err(error!(
+            VALIDATION,
+            "content",
+            format!(
+                "Message content has to be between 1 and {} characters long",
+                conf.oprish.message_limit
+            )
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+impl MessageCreate {
+    pub fn ensure_valid(&mut self) {
+        self.content = self.content.trim().to_string();
+    }
+
+    pub fn validate(&self, conf: &Conf) -> Result<(), ErrorResponse> {
+        if self.attachments.is_empty() {
+            validate_content(&self.content, conf)?;
+        } else if self.content.len() > conf.oprish.message_limit {
+            return Err(error!(
+                VALIDATION,
+                "content",
+                format!(
+                    "Message content has to be at most {} characters long",
+                    conf.oprish.message_limit
+                )
+            ));
+        }
+        if self.attachments.len() > MESSAGE_MAX_ATTACHMENTS {
+            return Err(error!(
+                VALIDATION,
+                "attachments",
+                format!(
+                    "A message can have at most {} attachments",
+                    MESSAGE_MAX_ATTACHMENTS
+                )
+            ));
+        }
+        if self
+            .attachments
+            .iter()
+            .enumerate()
+            .any(|(i, id)| self.attachments[..i].contains(id))
+        {
+            return Err(error!(
+                VALIDATION,
+                "attachments", "The same attachment can't be added more than once"
+            ));
+        }
+        if let Some(disguise) = &self.disguise {
+            if let Some(name) = &disguise.name {
+                if name.len() < 2 || name.len() > 32 {
+                    return Err(error!(
+                        VALIDATION,
+                        "disguise.name",
+                        "The user's disguise name must be between 2 and 32 characters in length"
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl MessageEdit {
+    pub fn ensure_valid(&mut self) {
+        self.content = self.content.trim().to_string();
+    }
+
+    pub fn validate(&self, conf: &Conf) -> Result<(), ErrorResponse> {
+        validate_content(&self.content, conf)
+    }
+}
+
+fn get_disguise(name: Option<String>, avatar: Option<String>) -> Option<MessageDisguise> {
+    (name.is_some() || avatar.is_some()).then_some(MessageDisguise { name, avatar })
+}
+
+/// Fetch the attachments of the provided messages, keyed by message ID.
+async fn get_attachments(
+    message_ids: &[i64],
+    db: &mut PoolConnection<Postgres>,
+) -> Result<HashMap<u64, Vec<FileData>>, ErrorResponse> {
+    let rows = sqlx::query!(
+        "
+SELECT ma.message_id, f.*
+FROM message_attachments ma
+JOIN files f
+ON ma.attachment_id = f.id
+WHERE ma.message_id = ANY($1)
+ORDER BY f.id
+        ",
+        message_ids
+    )
+    .fetch_all(db)
+    .await
+    .map_err(|err| {
+        log::error!("Couldn't fetch message attachments: {}", err);
+        error!(SERVER, "Failed to fetch message attachments")
+    })?;
+    let mut attachments: HashMap<u64, Vec<FileData>> = HashMap::new();
+    for r in rows {
+        attachments.entry(r.message_id as u64).or_default().push(
+            File {
+                id: r.id as u64,
+                file_id: r.file_id as u64,
+                name: r.name,
+                content_type: r.content_type,
+                hash: r.hash,
+                bucket: r.bucket,
+                spoiler: r.spoiler,
+                width: r.width.map(|s| s as usize),
+                height: r.height.map(|s| s as usize),
+                owner_id: r.owner_id.map(|o| o as u64),
+            }
+            .get_file_data(),
+        );
+    }
+    Ok(attachments)
+}
+
+/// Fetch the IDs of the users mentioned by the provided messages, keyed by message ID.
+async fn get_mentions(
+    message_ids: &[i64],
+    db: &mut PoolConnection<Postgres>,
+) -> Result<HashMap<u64, Vec<u64>>, ErrorResponse> {
+    let rows = sqlx::query!(
+        "
+SELECT message_id, user_id
+FROM message_mentions
+WHERE message_id = ANY($1)
+        ",
+        message_ids
+    )
+    .fetch_all(db)
+    .await
+    .map_err(|err| {
+        log::error!("Couldn't fetch message mentions: {}", err);
+        error!(SERVER, "Failed to fetch message mentions")
+    })?;
+    let mut mentions: HashMap<u64, Vec<u64>> = HashMap::new();
+    for r in rows {
+        mentions
+            .entry(r.message_id as u64)
+            .or_default()
+            .push(r.user_id as u64);
+    }
+    Ok(mentions)
+}
+
+/// Parse the IDs of the users mentioned using `<@user_id>` in a message's content.
+fn parse_mentions(content: &str) -> Vec<u64> {
+    lazy_static! {
+        static ref MENTION_REGEX: Regex =
+            Regex::new(r"<@(\d+)>").expect("Could not compile mention regex");
+    }
+    MENTION_REGEX
+        .captures_iter(content)
+        .filter_map(|c| c[1].parse().ok())
+        .collect()
+}
+
+/// Resolve the users mentioned by a message, only keeping the users that exist and can view the
+/// message's channel.
+///
+/// The author of the referenced message is also included if the reply mentions them. A message's
+/// author never mentions themselves.
+async fn resolve_mentions<C: AsyncCommands>(
+    content: &str,
+    author_id: u64,
+    reference_author_id: Option<u64>,
+    channel_id: Option<u64>,
+    db: &mut PoolConnection<Postgres>,
+    cache: &mut C,
+) -> Result<Vec<u64>, ErrorResponse> {
+    let mut mentions = vec![];
+    for id in reference_author_id
+        .into_iter()
+        .chain(parse_mentions(content))
+    {
+        if id == author_id || mentions.contains(&id) {
+            continue;
+        }
+        if let Some(channel_id) = channel_id {
+            match Permissions::get_channel(channel_id, id, &mut *db).await {
+                Ok(permissions) if permissions.contains(Permissions::VIEW_CHANNEL) => {}
+                Ok(_) | Err(ErrorResponse::NotFound { .. }) => continue,
+                Err(err) => return Err(err),
+            }
+        }
+        match User::get(id, None, &mut *db, cache).await {
+            Ok(_) => mentions.push(id),
+            Err(ErrorResponse::NotFound { .. }) => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(mentions)
+}
+
+/// Replace the stored mentions of a message.
+async fn store_mentions(
+    message_id: u64,
+    mentions: &[u64],
+    db: &mut PgConnection,
+) -> Result<(), ErrorResponse> {
+    let mentions: Vec<i64> = mentions.iter().map(|m| *m as i64).collect();
+    sqlx::query!(
+        "
+DELETE FROM message_mentions
+WHERE message_id = $1
+        ",
+        message_id as i64
+    )
+    .execute(&mut *db)
+    .await
+    .map_err(|err| {
+        log::error!("Failed to clear message mentions: {}", err);
+        error!(SERVER, "Could not save message mentions")
+    })?;
+    sqlx::query!(
+        "
+INSERT INTO message_mentions(message_id, user_id)
+SELECT $1, UNNEST($2::BIGINT[])
+        ",
+        message_id as i64,
+        &mentions
+    )
+    .execute(db)
+    .await
+    .map_err(|err| {
+        log::error!("Failed to store message mentions in database: {}", err);
+        error!(SERVER, "Could not save message mentions")
+    })?;
+    Ok(())
+}
+
+/// Fetch a trimmed copy of the referenced message, returns `None` if it doesn't exist anymore.
+async fn get_reference<C: AsyncCommands>(
+    reference_id: u64,
+    mention: bool,
+    db: &mut PoolConnection<Postgres>,
+    cache: &mut C,
+) -> Result<Option<MessageReference>, ErrorResponse> {
+    let reference = match sqlx::query!(
+        "
+SELECT m.author_id, m.content
+FROM messages m
+LEFT JOIN users u
+ON m.author_id = u.id
+WHERE m.id = $1
+AND u.is_deleted = FALSE
+        ",
+        reference_id as i64
+    )
+    .fetch_optional(&mut *db)
+    .await
+    .map_err(|err| {
+        log::error!("Couldn't fetch referenced message from database: {}", err);
+        error!(SERVER, "Failed to fetch referenced message")
+    })? {
+        Some(reference) => reference,
+        None => return Ok(None),
+    };
+    Ok(Some(MessageReference {
+        id: reference_id,
+        author: User::get(reference.author_id as u64, None, db, cache).await?,
+        content: reference
+            .content
+            .chars()
+            .take(MESSAGE_REFERENCE_CONTENT_LIMIT)
+            .collect(),
+        mention,
+    }))
+}
+
+/// Make sure a channel can have messages sent to it and that the user can see it.
+async fn ensure_text_channel(
+    channel_id: u64,
+    user_id: u64,
+    db: &mut PoolConnection<Postgres>,
+) -> Result<(), ErrorResponse> {
+    Permissions::require_channel(channel_id, user_id, Permissions::VIEW_CHANNEL, &mut *db).await?;
+    let is_category = sqlx::query!(
+        r#"
+SELECT channel_type = 'CATEGORY' AS "is_category!"
+FROM channels
+WHERE id = $1
+        "#,
+        channel_id as i64
+    )
+    .fetch_one(db)
+    .await
+    .map_err(|err| {
+        log::error!("Couldn't fetch channel type: {}", err);
+        error!(SERVER, "Failed to fetch channel")
+    })?
+    .is_category;
+    if is_category {
+        return Err(error!(
+            VALIDATION,
+            "channel", "Messages can only be sent in text channels"
+        ));
+    }
+    Ok(())
+}
+
+/// Make sure a user isn't muted in a channel's community and waited for the channel's slow mode.
+///
+/// Members with the `MANAGE_MESSAGES` or `MODERATE_MEMBERS` permissions bypass slow mode.
+async fn check_restrictions<C: AsyncCommands>(
+    channel_id: u64,
+    user_id: u64,
+    db: &mut PoolConnection<Postgres>,
+    cache: &mut C,
+) -> Result<(), ErrorResponse> {
+    let channel = sqlx::query!(
+        "
+SELECT community_id, slow_mode
+FROM channels
+WHERE id = $1
+        ",
+        channel_id as i64
+    )
+    .fetch_one(&mut *db)
+    .await
+    .map_err(|err| {
+        log::error!("Couldn't fetch channel: {}", err);
+        error!(SERVER, "Failed to fetch channel")
+    })?;
+    let community_id = match channel.community_id {
+        Some(community_id) => community_id as u64,
+        None => return Ok(()),
+    };
+    if let Some(mute) = Mute::get_active(community_id, user_id, &mut *db, cache).await? {
+        return Err(mute.to_error());
+    }
+    if channel.slow_mode > 0
+        && !Permissions::get_channel(channel_id, user_id, db)
+            .await?
+            .intersects(Permissions::MANAGE_MESSAGES | Permissions::MODERATE_MEMBERS)
+    {
+        Channel::check_slow_mode(channel_id, user_id, channel.slow_mode as u32, cache).await?;
+    }
+    Ok(())
+}
+
+/// Check whether a message was sent in a channel.
+async fn is_in_channel(
+    message_id: u64,
+    channel_id: u64,
+    db: &mut PoolConnection<Postgres>,
+) -> Result<bool, ErrorResponse> {
+    sqlx::query!(
+        r#"
+SELECT EXISTS(
+  SELECT 1
+  FROM messages
+  WHERE id = $1
+  AND channel_id = $2
+) AS "exists!"
+        "#,
+        message_id as i64,
+        channel_id as i64,
+    )
+    .fetch_one(db)
+    .await
+    .map(|r| r.exists)
+    .map_err(|err| {
+        log::error!("Couldn't check the message's channel: {}", err);
+        error!(SERVER, "Failed to fetch referenced message")
+    })
+}
+
+impl Message {
+    pub async fn create<C: AsyncCommands>(
+        mut message: MessageCreate,
+        channel_id: u64,
+        author_id: u64,
+        conf: &Conf,
+        id_generator: &mut IdGenerator,
+        db: &mut PoolConnection<Postgres>,
+        cache: &mut C,
+    ) -> Result<Self, ErrorResponse> {
+        message.ensure_valid();
+        message.validate(conf)?;
+        ensure_text_channel(channel_id, author_id, &mut *db).await?;
+        let author = User::get(author_id, None, &mut *db, cache).await?;
+        let mut attachments = Vec::with_capacity(message.attachments.len());
+        for attachment_id in message.attachments.iter() {
+            let file = File::get(*attachment_id, "attachments", &mut *db)
+                .await
+                .ok_or_else(|| {
+                    error!(
+                        VALIDATION,
+                        "attachments",
+                        format!("Unknown attachment {}", attachment_id)
+                    )
+                })?;
+            if file.owner_id != Some(author_id) {
+                return Err(error!(
+                    VALIDATION,
+                    "attachments",
+                    format!("Attachment {} wasn't uploaded by you", attachment_id)
+                ));
+            }
+            attachments.push(file.get_file_data());
+        }
+        if !attachments.is_empty() {
+            let attachment_ids: Vec<i64> = message.attachments.iter().map(|a| *a as i64).collect();
+            if let Some(used) = sqlx::query!(
+                "
+SELECT attachment_id
+FROM message_attachments
+WHERE attachment_id = ANY($1)
+                ",
+                &attachment_ids
+            )
+            .fetch_optional(&mut *db)
+            .await
+            .map_err(|err| {
+                log::error!("Couldn't check message attachments: {}", err);
+                error!(SERVER, "Could not save message")
+            })? {
+                return Err(error!(
+                    VALIDATION,
+                    "attachments",
+                    format!(
+                        "Attachment {} is already attached to another message",
+                        used.attachment_id
+                    )
+                ));
+            }
+        }
+        let reference = match message.reference {
+            Some(reference_id) if !is_in_channel(reference_id, channel_id, &mut *db).await? => {
+                return Err(error!(
+                    VALIDATION,
+                    "reference", "The referenced message doesn't exist"
+                ))
+            }
+            Some(reference_id) => Some(
+                get_reference(reference_id, message.mention_reference, &mut *db, cache)
+                    .await?
+                    .ok_or_else(|| {
+                        error!(
+                            VALIDATION,
+                            "reference", "The referenced message doesn't exist"
+                        )
+                    })?,
+            ),
+            None => None,
+        };
+        check_restrictions(channel_id, author_id, &mut *db, cache).await?;
+        let mentions = resolve_mentions(
+            &message.content,
+            author_id,
+            reference
+                .as_ref()
+                .filter(|r| r.mention)
+                .map(|r| r.author.id),
+            Some(channel_id),
+            &mut *db,
+            cache,
+        )
+        .await?;
+        let id = id_generator.generate();
+        let (disguise_name, disguise_avatar) = match &message.disguise {
+            Some(disguise) => (disguise.name.as_ref(), disguise.avatar.as_ref()),
+            None => (None, None),
+        };
+        // The message is only saved along with all of its attachments and mentions.
+        let mut tx = db.begin().await.map_err(|err| {
+            log::error!("Failed to start message transaction: {}", err);
+            error!(SERVER, "Could not save message")
+        })?;
+        sqlx::query!(
+            "
+INSERT INTO messages(id, channel_id, author_id, content, disguise_name, disguise_avatar, reference_id, mention_reference)
+VALUES($1, $2, $3, $4, $5, $6, $7, $8)
+            ",
+            id as i64,
+            channel_id as i64,
+            author_id as i64,
+            message.content,
+            disguise_name,
+            disguise_avatar,
+            message.reference.map(|r| r as i64),
+            message.mention_reference && message.reference.is_some(),
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|err| {
+            log::error!("Failed to store message in database: {}", err);
+            error!(SERVER, "Could not save message")
+        })?;
+        for attachment_id in message.attachments.iter() {
+            sqlx::query!(
+                "
+INSERT INTO message_attachments(attachment_id, message_id)
+VALUES($1, $2)
+                ",
+                *attachment_id as i64,
+                id as i64,
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(|err| {
+                match err.as_database_error().and_then(|err| err.code()) {
+                    // Another message got the attachment since it was checked.
+                    Some(code) if code == "23505" => error!(
+                        VALIDATION,
+                        "attachments",
+                        format!(
+                            "Attachment {} is already attached to another message",
+                            attachment_id
+                        )
+                    ),
+                    _ => {
+                        log::error!("Failed to store message attachment in database: {}", err);
+                        error!(SERVER, "Could not save message")
+                    }
+                }
+            })?;
+        }
+        if !mentions.is_empty() {
+            store_mentions(id, &mentions, &mut tx).await?;
+        }
+        tx.commit().await.map_err(|err| {
+            log::error!("Failed to commit message: {}", err);
+            error!(SERVER, "Could not save message")
+        })?;
+        Ok(Self {
+            id,
+            channel_id: Some(channel_id),
+            author,
+            content: message.content,
+            disguise: message.disguise,
+            attachments,
+            reference,
+            mentions,
+            reactions: vec![],
+            edited_at: None,
+        })
+    }
+
+    pub async fn get<C: AsyncCommands>(
+        id: u64,
+        db: &mut PoolConnection<Postgres>,
+        cache: &mut C,
+    ) -> Result<Self, ErrorResponse> {
+        let mut query: QueryBuilder<Postgres> = QueryBuilder::new(MESSAGE_QUERY);
+        query.push(" AND m.id = ").push_bind(id as i64);
+        let message = query
+            .build()
+            .fetch_optional(&mut *db)
+            .await
+            .map_err(|err| {
+                log::error!("Couldn't fetch message from database: {}", err);
+                error!(SERVER, "Failed to fetch message")
+            })?
+            .ok_or_else(|| error!(NOT_FOUND))?;
+        Self::from_rows(vec![message], db, cache)
+            .await
+            .map(|mut m| m.remove(0))
+    }
+
+    /// Get who the events about a message are delivered to.
+    pub async fn get_event_target(
+        id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<EventTarget, ErrorResponse> {
+        sqlx::query!(
+            "
+SELECT author_id, channel_id
+FROM messages
+WHERE id = $1
+            ",
+            id as i64
+        )
+        .fetch_optional(db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't fetch message from database: {}", err);
+            error!(SERVER, "Failed to fetch message")
+        })?
+        .map(|m| EventTarget::message(m.channel_id.map(|c| c as u64), m.author_id as u64))
+        .ok_or_else(|| error!(NOT_FOUND))
+    }
+
+    /// Edit one of your messages.
+    ///
+    /// Returns the edited message along with the IDs of the users the edit newly mentions.
+    pub async fn edit<C: AsyncCommands>(
+        id: u64,
+        user_id: u64,
+        mut edit: MessageEdit,
+        conf: &Conf,
+        db: &mut PoolConnection<Postgres>,
+        cache: &mut C,
+    ) -> Result<(Self, Vec<u64>), ErrorResponse> {
+        edit.ensure_valid();
+        edit.validate(conf)?;
+        let mut message = Self::get(id, &mut *db, cache).await?;
+        if message.author.id != user_id {
+            return Err(error!(FORBIDDEN));
+        }
+        if let Some(channel_id) = message.channel_id {
+            Permissions::require_channel(channel_id, user_id, Permissions::SEND_MESSAGES, &mut *db)
+                .await?;
+        }
+        let mentions = resolve_mentions(
+            &edit.content,
+            user_id,
+            message
+                .reference
+                .as_ref()
+                .filter(|r| r.mention)
+                .map(|r| r.author.id),
+            message.channel_id,
+            &mut *db,
+            cache,
+        )
+        .await?;
+        let edited_at = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or(Duration::ZERO)
+            .as_secs();
+        let mut tx = db.begin().await.map_err(|err| {
+            log::error!("Failed to start message edit transaction: {}", err);
+            error!(SERVER, "Failed to edit message")
+        })?;
+        sqlx::query!(
+            "
+UPDATE messages
+SET content = $1, edited_at = $2
+WHERE id = $3
+            ",
+            edit.content,
+            edited_at as i64,
+            id as i64,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't update message: {}", err);
+            error!(SERVER, "Failed to edit message")
+        })?;
+        if mentions != message.mentions {
+            store_mentions(id, &mentions, &mut tx).await?;
+        }
+        tx.commit().await.map_err(|err| {
+            log::error!("Failed to commit message edit: {}", err);
+            error!(SERVER, "Failed to edit message")
+        })?;
+        let new_mentions = mentions
+            .iter()
+            .filter(|m| !message.mentions.contains(m))
+            .copied()
+            .collect();
+        message.mentions = mentions;
+        message.content = edit.content;
+        message.edited_at = Some(edited_at);
+        Ok((message, new_mentions))
+    }
+
+    /// Delete a message, members with the `MANAGE_MESSAGES` permission can delete other members'
+    /// messages.
+    ///
+    /// Returns the ID of the channel the message was sent in.
+    pub async fn delete(
+        id: u64,
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<EventTarget, ErrorResponse> {
+        let message = sqlx::query!(
+            "
+SELECT author_id, channel_id
+FROM messages
+WHERE id = $1
+            ",
+            id as i64
+        )
+        .fetch_optional(&mut *db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't fetch message from database: {}", err);
+            error!(SERVER, "Failed to delete message")
+        })?
+        .ok_or_else(|| error!(NOT_FOUND))?;
+        if message.author_id as u64 != user_id {
+            match message.channel_id {
+                Some(channel_id) => {
+                    Permissions::require_channel(
+                        channel_id as u64,
+                        user_id,
+                        Permissions::MANAGE_MESSAGES,
+                        &mut *db,
+                    )
+                    .await?;
+                }
+                None => return Err(error!(FORBIDDEN)),
+            }
+        }
+        sqlx::query!(
+            "
+DELETE FROM messages
+WHERE id = $1
+            ",
+            id as i64
+        )
+        .execute(db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't delete message: {}", err);
+            error!(SERVER, "Failed to delete message")
+        })?;
+        Ok(EventTarget::message(
+            message.channel_id.map(|c| c as u64),
+            message.author_id as u64,
+        ))
+    }
+
+    /// Get a page of a channel's message history, or of the messages which were sent before
+    /// channels existed if no channel is provided.
+    ///
+    /// The returned messages are ordered from newest to oldest. When only `after` is provided the
+    /// page is made up of the messages directly following it, otherwise it's made up of the most
+    /// recent messages matching the bounds.
+    pub async fn get_history<C: AsyncCommands>(
+        channel_id: Option<u64>,
+        user_id: u64,
+        before: Option<u64>,
+        after: Option<u64>,
+        limit: Option<u32>,
+        db: &mut PoolConnection<Postgres>,
+        cache: &mut C,
+    ) -> Result<Vec<Self>, ErrorResponse> {
+        let limit = limit.unwrap_or(MESSAGE_HISTORY_DEFAULT_LIMIT);
+        if limit == 0 || limit > MESSAGE_HISTORY_MAX_LIMIT {
+            return Err(error!(
+                VALIDATION,
+                "limit",
+                format!(
+                    "The message limit must be between 1 and {}",
+                    MESSAGE_HISTORY_MAX_LIMIT
+                )
+            ));
+        }
+        if let (Some(before), Some(after)) = (before, after) {
+            if after >= before {
+                return Err(error!(
+                    VALIDATION,
+                    "after", "The after bound must be lower than the before bound"
+                ));
+            }
+        }
+
+        let mut query: QueryBuilder<Postgres> = QueryBuilder::new(MESSAGE_QUERY);
+        match channel_id {
+            Some(channel_id) => {
+                ensure_text_channel(channel_id, user_id, &mut *db).await?;
+                query
+                    .push(" AND m.channel_id = ")
+                    .push_bind(channel_id as i64);
+            }
+            None => {
+                query.push(" AND m.channel_id IS NULL");
+            }
+        }
+        if let Some(before) = before {
+            query.push(" AND m.id < ").push_bind(before as i64);
+        }
+        if let Some(after) = after {
+            query.push(" AND m.id > ").push_bind(after as i64);
+        }
+        let ascending = after.is_some() && before.is_none();
+        query
+            .push(if ascending {
+                " ORDER BY m.id ASC"
+            } else {
+                " ORDER BY m.id DESC"
+            })
+            .push(" LIMIT ")
+            .push_bind(limit as i64);
+        let mut rows = query.build().fetch_all(&mut *db).await.map_err(|err| {
+            log::error!("Couldn't fetch message history: {}", err);
+            error!(SERVER, "Failed to fetch message history")
+        })?;
+        if ascending {
+            rows.reverse();
+        }
+
+        Self::from_rows(rows, db, cache).await
+    }
+
+    /// Get a page of the messages mentioning a user, ordered from newest to oldest.
+    pub async fn get_mentions<C: AsyncCommands>(
+        user_id: u64,
+        before: Option<u64>,
+        limit: Option<u32>,
+        db: &mut PoolConnection<Postgres>,
+        cache: &mut C,
+    ) -> Result<Vec<Self>, ErrorResponse> {
+        let limit = limit.unwrap_or(MESSAGE_HISTORY_DEFAULT_LIMIT);
+        if limit == 0 || limit > MESSAGE_HISTORY_MAX_LIMIT {
+            return Err(error!(
+                VALIDATION,
+                "limit",
+                format!(
+                    "The message limit must be between 1 and {}",
+                    MESSAGE_HISTORY_MAX_LIMIT
+                )
+            ));
+        }
+
+        // Whether the user can still see each channel, which also depends on its overwrites.
+        let mut visible: HashMap<u64, bool> = HashMap::new();
+        let mut mentions = Vec::with_capacity(limit as usize);
+        let mut before = before;
+        loop {
+            let mut query: QueryBuilder<Postgres> = QueryBuilder::new(MESSAGE_QUERY);
+            query
+                .push(" AND m.id IN (SELECT message_id FROM message_mentions WHERE user_id = ")
+                .push_bind(user_id as i64)
+                .push(")")
+                .push(
+                    " AND (m.channel_id IS NULL OR m.channel_id IN (SELECT c.id FROM channels c JOIN community_members cm ON c.community_id = cm.community_id WHERE cm.user_id = ",
+                )
+                .push_bind(user_id as i64)
+                .push(" UNION SELECT channel_id FROM channel_recipients WHERE user_id = ")
+                .push_bind(user_id as i64)
+                .push("))");
+            if let Some(before) = before {
+                query.push(" AND m.id < ").push_bind(before as i64);
+            }
+            query
+                .push(" ORDER BY m.id DESC LIMIT ")
+                .push_bind(limit as i64);
+            let rows = query.build().fetch_all(&mut *db).await.map_err(|err| {
+                log::error!("Couldn't fetch user mentions: {}", err);
+                error!(SERVER, "Failed to fetch mentions")
+            })?;
+            let exhausted = rows.len() < limit as usize;
+            for row in rows {
+                before = Some(row.get::<i64, _>("id") as u64);
+                if let Some(channel_id) = row.get::<Option<i64>, _>("channel_id") {
+                    let channel_id = channel_id as u64;
+                    let can_see = match visible.get(&channel_id) {
+                        Some(can_see) => *can_see,
+                        None => {
+                            let can_see =
+                                match Permissions::get_channel(channel_id, user_id, &mut *db).await
+                                {
+                                    Ok(permissions) => {
+                                        permissions.contains(Permissions::VIEW_CHANNEL)
+                                    }
+                                    Err(ErrorResponse::NotFound { .. }) => false,
+                                    Err(err) => return Err(err),
+                                };
+                            visible.insert(channel_id, can_see);
+                            can_see
+                        }
+                    };
+                    if !can_see {
+                        continue;
+                    }
+                }
+                mentions.push(row);
+                if mentions.len() == limit as usize {
+                    break;
+                }
+            }
+            if exhausted || mentions.len() == limit as usize {
+                break;
+            }
+        }
+        Self::from_rows(mentions, db, cache).await
+    }
+
+    /// Build full messages out of rows fetched using [`MESSAGE_QUERY`], keeping their order.
+    async fn from_rows<C: AsyncCommands>(
+        rows: Vec<PgRow>,
+        db: &mut PoolConnection<Postgres>,
+        cache: &mut C,
+    ) -> Result<Vec<Self>, ErrorResponse> {
+        let ids: Vec<i64> = rows.iter().map(|r| r.get("id")).collect();
+        let mut attachments = get_attachments(&ids, &mut *db).await?;
+        let mut reactions = Reaction::get_for_messages(&ids, &mut *db).await?;
+        let mut mentions = get_mentions(&ids, &mut *db).await?;
+        let mut authors: HashMap<u64, User> = HashMap::new();
+        let mut messages = Vec::with_capacity(rows.len());
+        for row in rows {
+            let author_id = row.get::<i64, _>("author_id") as u64;
+            let author = match authors.get(&author_id) {
+                Some(author) => author.clone(),
+                None => {
+                    let author = User::get(author_id, None, &mut *db, cache).await?;
+                    authors.insert(author_id, author.clone());
+                    author
+                }
+            };
+            let id = row.get::<i64, _>("id") as u64;
+            let reference = match row.get::<Option<i64>, _>("reference_id") {
+                Some(reference_id) => {
+                    get_reference(
+                        reference_id as u64,
+                        row.get("mention_reference"),
+                        &mut *db,
+                        cache,
+                    )
+                    .await?
+                }
+                None => None,
+            };
+            messages.push(Self {
+                id,
+                channel_id: row.get::<Option<i64>, _>("channel_id").map(|c| c as u64),
+                author,
+                content: row.get("content"),
+                disguise: get_disguise(row.get("disguise_name"), row.get("disguise_avatar")),
+                attachments: attachments.remove(&id).unwrap_or_default(),
+                reference,
+                mentions: mentions.remove(&id).unwrap_or_default(),
+                reactions: reactions.remove(&id).unwrap_or_default(),
+                edited_at: row.get::<Option<i64>, _>("edited_at").map(|e| e as u64),
+            });
+        }
+        Ok(messages)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        models::{MessageCreate, MessageDisguise, MessageEdit, MESSAGE_MAX_ATTACHMENTS},
+        Conf,
+    };
+
+    use super::parse_mentions;
+
+    #[test]
+    fn mentions() {
+        assert_eq!(
+            parse_mentions("<@48615849987333> hi <@48615849987334><@1>"),
+            vec![48615849987333, 48615849987334, 1]
+        );
+        assert!(parse_mentions("<@> <@foo> @48615849987333 <48615849987333>").is_empty());
+        assert!(parse_mentions("<@99999999999999999999>").is_empty());
+    }
+
+    #[test]
+    fn validate_message_create() {
+        let conf: Conf = toml::from_str("instance_name = \"WooChat\"").unwrap();
+        let mut message = MessageCreate {
+            content: "  Hello, World!  ".to_string(),
+            disguise: None,
+            attachments: vec![],
+            reference: None,
+            mention_reference: true,
+        };
+        message.ensure_valid();
+        assert_eq!(message.content, "Hello, World!");
+        assert!(message.validate(&conf).is_ok());
+
+        message.content = "".to_string();
+        assert!(message.validate(&conf).is_err());
+        message.content = "h".repeat(conf.oprish.message_limit + 1);
+        assert!(message.validate(&conf).is_err());
+        message.content = "h".repeat(conf.oprish.message_limit);
+        assert!(message.validate(&conf).is_ok());
+
+        message.disguise = Some(MessageDisguise {
+            name: Some("J".to_string()),
+            avatar: None,
+        });
+        assert!(message.validate(&conf).is_err());
+        message.disguise = Some(MessageDisguise {
+            name: Some("Jeff".to_string()),
+            avatar: None,
+        });
+        assert!(message.validate(&conf).is_ok());
+
+        message.content = "".to_string();
+        message.attachments = vec![1, 2];
+        assert!(message.validate(&conf).is_ok());
+        message.attachments = vec![1, 1];
+        assert!(message.validate(&conf).is_err());
+        message.attachments = (0..=MESSAGE_MAX_ATTACHMENTS as u64).collect();
+        assert!(message.validate(&conf).is_err());
+    }
+
+    #[test]
+    fn validate_message_edit() {
+        let conf: Conf = toml::from_str("instance_name = \"WooChat\"").unwrap();
+        let mut edit = MessageEdit {
+            content: " Hello, World! (edited) ".to_string(),
+        };
+        edit.ensure_valid();
+        assert_eq!(edit.content, "Hello, World! (edited)");
+        assert!(edit.validate(&conf).is_ok());
+
+        edit.content = "".to_string();
+        assert!(edit.validate(&conf).is_err());
+        edit.content = "h".repeat(conf.oprish.message_limit + 1);
+        assert!(edit.validate(&conf).is_err());
+    }
+}
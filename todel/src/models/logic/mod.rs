@@ -1,11 +1,48 @@
+mod admin;
+mod channels;
+mod communities;
+mod dms;
 mod email;
+mod events;
 mod files;
+mod follows;
+mod invites;
+mod messages;
 mod meta;
+mod mutes;
+mod permissions;
+mod posts;
+mod reactions;
+mod relationships;
+mod reports;
+mod roles;
 mod sessions;
+mod two_factor;
 mod users;
 
+use std::time::{Duration, SystemTime};
+
+pub use admin::*;
+pub use dms::*;
 pub use email::*;
+pub use events::*;
 pub use files::*;
+pub use follows::*;
+pub use invites::*;
+pub use messages::*;
 pub use meta::*;
+pub use mutes::*;
+pub use posts::*;
+pub use reactions::*;
+pub use reports::*;
 pub use sessions::*;
+pub use two_factor::*;
 pub use users::*;
+
+/// Get the current UNIX timestamp in seconds.
+pub(crate) fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_secs()
+}
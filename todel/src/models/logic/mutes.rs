@@ -0,0 +1,321 @@
+use redis::AsyncCommands;
+use sqlx::{pool::PoolConnection, Postgres};
+
+use super::{now, permissions::MemberPermissions};
+use crate::models::{ErrorResponse, Mute, MuteCreate, Permissions};
+
+/// The maximum duration of a mute in seconds.
+pub const MUTE_MAX_DURATION: u64 = 2_419_200;
+/// The maximum length of a mute's reason.
+pub const MUTE_REASON_LIMIT: usize = 512;
+/// How long the absence of a mute stays cached in seconds.
+const UNMUTED_CACHE_TTL: usize = 3600;
+
+fn cache_key(community_id: u64, user_id: u64) -> String {
+    format!("mute:{}:{}", community_id, user_id)
+}
+
+/// Cache a member's mute, or the fact that they aren't muted.
+async fn cache_mute<C: AsyncCommands>(
+    community_id: u64,
+    user_id: u64,
+    mute: Option<&Mute>,
+    cache: &mut C,
+) -> Result<(), ErrorResponse> {
+    let ttl = match mute {
+        Some(mute) => mute.expires_at.saturating_sub(now()).max(1) as usize,
+        None => UNMUTED_CACHE_TTL,
+    };
+    cache
+        .set_ex::<_, _, ()>(
+            cache_key(community_id, user_id),
+            serde_json::to_string(&mute).unwrap(),
+            ttl,
+        )
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't cache mute: {}", err);
+            error!(SERVER, "Failed to update mute")
+        })
+}
+
+/// Make sure a moderator can mute or unmute a member of a community.
+async fn ensure_moderatable(
+    community_id: u64,
+    user_id: u64,
+    moderator_id: u64,
+    db: &mut PoolConnection<Postgres>,
+) -> Result<(), ErrorResponse> {
+    if user_id == moderator_id {
+        return Err(error!(VALIDATION, "user", "You can't mute yourself"));
+    }
+    let moderator = MemberPermissions::get(community_id, moderator_id, &mut *db).await?;
+    moderator.ensure(Permissions::MODERATE_MEMBERS)?;
+    let member = MemberPermissions::get(community_id, user_id, db).await?;
+    if !member.is_member {
+        return Err(error!(NOT_FOUND));
+    }
+    match member.top_position {
+        Some(position) => moderator.ensure_above(position),
+        // Owners and administrators can't be muted.
+        None => Err(error!(FORBIDDEN)),
+    }
+}
+
+impl MuteCreate {
+    pub fn ensure_valid(&mut self) {
+        self.reason = self.reason.as_ref().map(|r| r.trim().to_string());
+    }
+
+    pub fn validate(&self) -> Result<(), ErrorResponse> {
+        if self.duration == 0 || self.duration > MUTE_MAX_DURATION {
+            return Err(error!(
+                VALIDATION,
+                "duration",
+                format!(
+                    "The mute's duration must be between 1 and {} seconds",
+                    MUTE_MAX_DURATION
+                )
+            ));
+        }
+        if let Some(reason) = &self.reason {
+            if reason.is_empty() || reason.len() > MUTE_REASON_LIMIT {
+                return Err(error!(
+                    VALIDATION,
+                    "reason",
+                    format!(
+                        "The mute's reason must be between 1 and {} characters in length",
+                        MUTE_REASON_LIMIT
+                    )
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Mute {
+    /// Get the error muted members get when trying to send messages.
+    pub(crate) fn to_error(&self) -> ErrorResponse {
+        match &self.reason {
+            Some(reason) => error!(
+                FORBIDDEN,
+                format!(
+                    "You are muted in this community until {}: {}",
+                    self.expires_at, reason
+                )
+            ),
+            None => error!(
+                FORBIDDEN,
+                format!("You are muted in this community until {}", self.expires_at)
+            ),
+        }
+    }
+
+    /// Mute a member of a community, replacing their current mute if they have one.
+    pub async fn create<C: AsyncCommands>(
+        community_id: u64,
+        user_id: u64,
+        mut mute: MuteCreate,
+        moderator_id: u64,
+        db: &mut PoolConnection<Postgres>,
+        cache: &mut C,
+    ) -> Result<Self, ErrorResponse> {
+        mute.ensure_valid();
+        mute.validate()?;
+        ensure_moderatable(community_id, user_id, moderator_id, &mut *db).await?;
+        let mute = Self {
+            community_id,
+            user_id,
+            moderator_id,
+            reason: mute.reason,
+            expires_at: now() + mute.duration,
+        };
+        sqlx::query!(
+            "
+INSERT INTO mutes(community_id, user_id, moderator_id, reason, expires_at)
+VALUES($1, $2, $3, $4, $5)
+ON CONFLICT (community_id, user_id)
+DO UPDATE SET moderator_id = $3, reason = $4, expires_at = $5
+            ",
+            community_id as i64,
+            user_id as i64,
+            moderator_id as i64,
+            mute.reason,
+            mute.expires_at as i64,
+        )
+        .execute(db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't store mute in database: {}", err);
+            error!(SERVER, "Failed to mute member")
+        })?;
+        cache_mute(community_id, user_id, Some(&mute), cache).await?;
+        Ok(mute)
+    }
+
+    /// Unmute a member of a community before their mute expires.
+    pub async fn delete<C: AsyncCommands>(
+        community_id: u64,
+        user_id: u64,
+        moderator_id: u64,
+        db: &mut PoolConnection<Postgres>,
+        cache: &mut C,
+    ) -> Result<(), ErrorResponse> {
+        ensure_moderatable(community_id, user_id, moderator_id, &mut *db).await?;
+        let result = sqlx::query!(
+            "
+DELETE FROM mutes
+WHERE community_id = $1
+AND user_id = $2
+AND expires_at > $3
+            ",
+            community_id as i64,
+            user_id as i64,
+            now() as i64,
+        )
+        .execute(db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't delete mute: {}", err);
+            error!(SERVER, "Failed to unmute member")
+        })?;
+        if result.rows_affected() == 0 {
+            return Err(error!(NOT_FOUND));
+        }
+        cache_mute(community_id, user_id, None, cache).await
+    }
+
+    /// Get all of a community's active mutes.
+    pub async fn get_all(
+        community_id: u64,
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Vec<Self>, ErrorResponse> {
+        Permissions::require_community(
+            community_id,
+            user_id,
+            Permissions::MODERATE_MEMBERS,
+            &mut *db,
+        )
+        .await?;
+        sqlx::query!(
+            "
+SELECT user_id, moderator_id, reason, expires_at
+FROM mutes
+WHERE community_id = $1
+AND expires_at > $2
+ORDER BY expires_at
+            ",
+            community_id as i64,
+            now() as i64,
+        )
+        .fetch_all(db)
+        .await
+        .map(|rows| {
+            rows.into_iter()
+                .map(|r| Self {
+                    community_id,
+                    user_id: r.user_id as u64,
+                    moderator_id: r.moderator_id as u64,
+                    reason: r.reason,
+                    expires_at: r.expires_at as u64,
+                })
+                .collect()
+        })
+        .map_err(|err| {
+            log::error!("Couldn't fetch community mutes: {}", err);
+            error!(SERVER, "Failed to fetch mutes")
+        })
+    }
+
+    /// Get a member's active mute, going through the cache first.
+    pub async fn get_active<C: AsyncCommands>(
+        community_id: u64,
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+        cache: &mut C,
+    ) -> Result<Option<Self>, ErrorResponse> {
+        let cached: Option<String> =
+            cache
+                .get(cache_key(community_id, user_id))
+                .await
+                .map_err(|err| {
+                    log::error!("Couldn't fetch mute from cache: {}", err);
+                    error!(SERVER, "Failed to fetch mute")
+                })?;
+        if let Some(cached) = cached {
+            if let Ok(mute) = serde_json::from_str::<Option<Self>>(&cached) {
+                return Ok(mute.filter(|m| m.expires_at > now()));
+            }
+        }
+        let mute = sqlx::query!(
+            "
+SELECT moderator_id, reason, expires_at
+FROM mutes
+WHERE community_id = $1
+AND user_id = $2
+AND expires_at > $3
+            ",
+            community_id as i64,
+            user_id as i64,
+            now() as i64,
+        )
+        .fetch_optional(db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't fetch mute from database: {}", err);
+            error!(SERVER, "Failed to fetch mute")
+        })?
+        .map(|r| Self {
+            community_id,
+            user_id,
+            moderator_id: r.moderator_id as u64,
+            reason: r.reason,
+            expires_at: r.expires_at as u64,
+        });
+        cache_mute(community_id, user_id, mute.as_ref(), cache).await?;
+        Ok(mute)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::{ErrorResponse, Mute, MuteCreate};
+
+    #[test]
+    fn validate_mute_create() {
+        let mut mute = MuteCreate {
+            duration: 3600,
+            reason: Some("  Spamming lanternfly pictures ".to_string()),
+        };
+        mute.ensure_valid();
+        assert_eq!(mute.reason.as_deref(), Some("Spamming lanternfly pictures"));
+        assert!(mute.validate().is_ok());
+        mute.duration = 0;
+        assert!(mute.validate().is_err());
+        mute.duration = super::MUTE_MAX_DURATION + 1;
+        assert!(mute.validate().is_err());
+        mute.duration = 60;
+        mute.reason = Some("".to_string());
+        assert!(mute.validate().is_err());
+    }
+
+    #[test]
+    fn mute_error() {
+        let mute = Mute {
+            community_id: 1,
+            user_id: 2,
+            moderator_id: 3,
+            reason: Some("Spam".to_string()),
+            expires_at: 1690995600,
+        };
+        match mute.to_error() {
+            ErrorResponse::Forbidden { shared } => assert_eq!(
+                shared.message,
+                "You are muted in this community until 1690995600: Spam"
+            ),
+            _ => panic!("Mutes should give a FORBIDDEN error"),
+        }
+    }
+}
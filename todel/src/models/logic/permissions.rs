@@ -0,0 +1,554 @@
+use std::collections::HashMap;
+
+use sqlx::{pool::PoolConnection, postgres::PgConnection, Postgres};
+
+use crate::models::{ChannelOverwrite, DirectChannel, ErrorResponse, OverwriteType, Permissions};
+
+/// A user's community-wide permissions, before any channel overwrites are applied.
+pub(crate) struct MemberPermissions {
+    /// Whether the user is a member of the community.
+    pub is_member: bool,
+    /// The user's permissions in the community.
+    pub permissions: Permissions,
+    /// The IDs of the user's roles, excluding the community's default role.
+    pub roles: Vec<u64>,
+    /// The position of the user's highest role, `None` if the user owns the community or is an
+    /// administrator and is therefore above every role.
+    pub top_position: Option<u32>,
+}
+
+impl MemberPermissions {
+    /// Get a user's permissions in a community, private communities are treated as missing for
+    /// users who aren't members of them.
+    pub async fn get(
+        community_id: u64,
+        user_id: u64,
+        db: &mut PgConnection,
+    ) -> Result<Self, ErrorResponse> {
+        let member = sqlx::query!(
+            r#"
+SELECT
+  c.owner_id,
+  c.private,
+  u.permissions AS instance_permissions,
+  EXISTS(
+    SELECT 1
+    FROM community_members
+    WHERE community_id = $1
+    AND user_id = $2
+  ) AS "is_member!",
+  ARRAY(
+    SELECT role_id
+    FROM member_roles
+    WHERE community_id = $1
+    AND user_id = $2
+  ) AS "roles!",
+  (
+    SELECT COALESCE(BIT_OR(r.permissions), 0)
+    FROM roles r
+    WHERE r.id = $1
+    OR r.id IN (SELECT role_id FROM member_roles WHERE community_id = $1 AND user_id = $2)
+  ) AS "permissions!",
+  (
+    SELECT COALESCE(MAX(r.position), 0)
+    FROM roles r
+    JOIN member_roles m
+    ON r.id = m.role_id
+    WHERE m.community_id = $1
+    AND m.user_id = $2
+  ) AS "top_position!"
+FROM communities c, users u
+WHERE c.id = $1
+AND u.id = $2
+            "#,
+            community_id as i64,
+            user_id as i64,
+        )
+        .fetch_optional(db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't fetch member permissions: {}", err);
+            error!(SERVER, "Failed to resolve permissions")
+        })?
+        .ok_or_else(|| error!(NOT_FOUND))?;
+        let bypass = member.owner_id as u64 == user_id
+            || Permissions::from_bits_truncate(member.instance_permissions as u64)
+                .contains(Permissions::ADMINISTRATOR);
+        if member.private && !member.is_member && !bypass {
+            return Err(error!(NOT_FOUND));
+        }
+        Ok(Self::resolve(
+            member.is_member,
+            bypass,
+            member.permissions as u64,
+            member.roles,
+            member.top_position as u32,
+        ))
+    }
+
+    /// Get the permissions of several users in a community at once, leaving out the ones who
+    /// aren't members of it.
+    pub async fn get_members(
+        community_id: u64,
+        user_ids: &[u64],
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<HashMap<u64, Self>, ErrorResponse> {
+        let members = sqlx::query!(
+            r#"
+SELECT
+  m.user_id,
+  c.owner_id,
+  u.permissions AS instance_permissions,
+  ARRAY(
+    SELECT role_id
+    FROM member_roles
+    WHERE community_id = $1
+    AND user_id = m.user_id
+  ) AS "roles!",
+  (
+    SELECT COALESCE(BIT_OR(r.permissions), 0)
+    FROM roles r
+    WHERE r.id = $1
+    OR r.id IN (SELECT role_id FROM member_roles WHERE community_id = $1 AND user_id = m.user_id)
+  ) AS "permissions!",
+  (
+    SELECT COALESCE(MAX(r.position), 0)
+    FROM roles r
+    JOIN member_roles mr
+    ON r.id = mr.role_id
+    WHERE mr.community_id = $1
+    AND mr.user_id = m.user_id
+  ) AS "top_position!"
+FROM community_members m
+JOIN communities c
+ON m.community_id = c.id
+JOIN users u
+ON m.user_id = u.id
+WHERE m.community_id = $1
+AND m.user_id = ANY($2)
+            "#,
+            community_id as i64,
+            &user_ids.iter().map(|id| *id as i64).collect::<Vec<i64>>(),
+        )
+        .fetch_all(db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't fetch member permissions: {}", err);
+            error!(SERVER, "Failed to resolve permissions")
+        })?;
+        Ok(members
+            .into_iter()
+            .map(|member| {
+                let user_id = member.user_id as u64;
+                let bypass = member.owner_id as u64 == user_id
+                    || Permissions::from_bits_truncate(member.instance_permissions as u64)
+                        .contains(Permissions::ADMINISTRATOR);
+                (
+                    user_id,
+                    Self::resolve(
+                        true,
+                        bypass,
+                        member.permissions as u64,
+                        member.roles,
+                        member.top_position as u32,
+                    ),
+                )
+            })
+            .collect())
+    }
+
+    /// Build a user's permissions out of the bits of all of their roles, `bypass` being whether
+    /// they own the community or are an administrator of the instance.
+    fn resolve(
+        is_member: bool,
+        bypass: bool,
+        permissions: u64,
+        roles: Vec<i64>,
+        top_position: u32,
+    ) -> Self {
+        let roles = roles.into_iter().map(|r| r as u64).collect();
+        let permissions = if !is_member {
+            Permissions::empty()
+        } else {
+            Permissions::from_bits_truncate(permissions)
+        };
+        if bypass || permissions.contains(Permissions::ADMINISTRATOR) {
+            return Self {
+                is_member: is_member || bypass,
+                permissions: Permissions::all(),
+                roles,
+                top_position: if bypass { None } else { Some(top_position) },
+            };
+        }
+        Self {
+            is_member,
+            permissions,
+            roles,
+            top_position: Some(top_position),
+        }
+    }
+
+    /// Make sure the user is a member of the community with all of the required permissions.
+    pub fn ensure(&self, required: Permissions) -> Result<(), ErrorResponse> {
+        if !self.is_member {
+            return Err(error!(FORBIDDEN));
+        }
+        self.permissions.ensure(required)
+    }
+
+    /// Make sure the user is above a role in the community's role hierarchy.
+    pub fn ensure_above(&self, position: u32) -> Result<(), ErrorResponse> {
+        match self.top_position {
+            Some(top_position) if top_position <= position => Err(error!(FORBIDDEN)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Make sure the user isn't granting or revoking permissions they don't have themselves.
+    pub fn ensure_grantable(&self, permissions: u64) -> Result<(), ErrorResponse> {
+        self.permissions
+            .ensure(Permissions::from_bits_truncate(permissions))
+    }
+}
+
+/// Fetch the IDs of a community's channels.
+async fn get_channel_ids(
+    community_id: u64,
+    db: &mut PoolConnection<Postgres>,
+) -> Result<Vec<i64>, ErrorResponse> {
+    Ok(sqlx::query!(
+        "
+SELECT id
+FROM channels
+WHERE community_id = $1
+        ",
+        community_id as i64
+    )
+    .fetch_all(db)
+    .await
+    .map_err(|err| {
+        log::error!("Couldn't fetch community channels: {}", err);
+        error!(SERVER, "Failed to resolve permissions")
+    })?
+    .into_iter()
+    .map(|c| c.id)
+    .collect())
+}
+
+/// Fetch the overwrites of a set of channels.
+pub(crate) async fn get_overwrites(
+    channel_ids: &[i64],
+    db: &mut PoolConnection<Postgres>,
+) -> Result<HashMap<u64, Vec<ChannelOverwrite>>, ErrorResponse> {
+    let mut overwrites: HashMap<u64, Vec<ChannelOverwrite>> = HashMap::new();
+    for overwrite in sqlx::query!(
+        r#"
+SELECT channel_id, target_id, overwrite_type as "overwrite_type: OverwriteType", allow, deny
+FROM channel_overwrites
+WHERE channel_id = ANY($1)
+ORDER BY target_id
+        "#,
+        channel_ids
+    )
+    .fetch_all(db)
+    .await
+    .map_err(|err| {
+        log::error!("Couldn't fetch channel overwrites: {}", err);
+        error!(SERVER, "Failed to fetch channel overwrites")
+    })? {
+        overwrites
+            .entry(overwrite.channel_id as u64)
+            .or_default()
+            .push(ChannelOverwrite {
+                target_id: overwrite.target_id as u64,
+                overwrite_type: overwrite.overwrite_type,
+                allow: overwrite.allow as u64,
+                deny: overwrite.deny as u64,
+            });
+    }
+    Ok(overwrites)
+}
+
+impl Permissions {
+    /// Make sure these permissions contain all of the required ones.
+    pub fn ensure(self, required: Self) -> Result<(), ErrorResponse> {
+        if self.contains(required) {
+            Ok(())
+        } else {
+            Err(error!(FORBIDDEN))
+        }
+    }
+
+    /// Apply a channel's overwrites on top of a member's community-wide permissions.
+    ///
+    /// The overwrite of the community's default role is applied first, then the overwrites of
+    /// the member's other roles and finally the member's own overwrite.
+    pub fn apply_overwrites(
+        self,
+        overwrites: &[ChannelOverwrite],
+        community_id: u64,
+        user_id: u64,
+        roles: &[u64],
+    ) -> Self {
+        if self.contains(Self::ADMINISTRATOR) {
+            return self;
+        }
+        let apply = |permissions: Self, allow: u64, deny: u64| {
+            (permissions - Self::from_bits_truncate(deny)) | Self::from_bits_truncate(allow)
+        };
+        let mut permissions = self;
+        if let Some(overwrite) = overwrites
+            .iter()
+            .find(|o| o.overwrite_type == OverwriteType::Role && o.target_id == community_id)
+        {
+            permissions = apply(permissions, overwrite.allow, overwrite.deny);
+        }
+        let (allow, deny) = overwrites
+            .iter()
+            .filter(|o| o.overwrite_type == OverwriteType::Role && roles.contains(&o.target_id))
+            .fold((0, 0), |(allow, deny), o| (allow | o.allow, deny | o.deny));
+        permissions = apply(permissions, allow, deny);
+        if let Some(overwrite) = overwrites
+            .iter()
+            .find(|o| o.overwrite_type == OverwriteType::Member && o.target_id == user_id)
+        {
+            permissions = apply(permissions, overwrite.allow, overwrite.deny);
+        }
+        permissions
+    }
+
+    /// Get a user's instance-wide permissions, administrators have every permission.
+    pub async fn get_instance(
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Self, ErrorResponse> {
+        let permissions = sqlx::query!(
+            "
+SELECT permissions
+FROM users
+WHERE id = $1
+            ",
+            user_id as i64,
+        )
+        .fetch_optional(db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't fetch user permissions: {}", err);
+            error!(SERVER, "Failed to fetch permissions")
+        })?
+        .map(|u| Self::from_bits_truncate(u.permissions as u64))
+        .ok_or_else(|| error!(NOT_FOUND))?;
+        if permissions.contains(Self::ADMINISTRATOR) {
+            Ok(Self::all())
+        } else {
+            Ok(permissions)
+        }
+    }
+
+    /// Resolve a user's instance-wide permissions and make sure they have all of the required
+    /// ones, returning a `FORBIDDEN` error otherwise.
+    pub async fn require_instance(
+        user_id: u64,
+        required: Self,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Self, ErrorResponse> {
+        let permissions = Self::get_instance(user_id, db).await?;
+        permissions.ensure(required)?;
+        Ok(permissions)
+    }
+
+    /// Resolve a user's permissions in a community and make sure they have all of the required
+    /// ones, returning a `FORBIDDEN` error otherwise.
+    ///
+    /// Users who aren't members of the community never pass this check.
+    pub async fn require_community(
+        community_id: u64,
+        user_id: u64,
+        required: Self,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Self, ErrorResponse> {
+        let member = MemberPermissions::get(community_id, user_id, db).await?;
+        member.ensure(required)?;
+        Ok(member.permissions)
+    }
+
+    /// Resolve a user's permissions in a channel and make sure they have all of the required
+    /// ones, returning a `FORBIDDEN` error otherwise.
+    ///
+    /// Channels the user can't see are treated as missing.
+    pub async fn require_channel(
+        channel_id: u64,
+        user_id: u64,
+        required: Self,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Self, ErrorResponse> {
+        let permissions = Self::get_channel(channel_id, user_id, db).await?;
+        if !permissions.contains(Self::VIEW_CHANNEL) {
+            return Err(error!(NOT_FOUND));
+        }
+        permissions.ensure(required)?;
+        Ok(permissions)
+    }
+
+    /// Resolve a user's permissions in a channel.
+    ///
+    /// The recipients of a direct channel have the [`Permissions::DEFAULT`] permissions in it,
+    /// unless it's a DM with a user who blocked them.
+    pub async fn get_channel(
+        channel_id: u64,
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Self, ErrorResponse> {
+        let community_id = sqlx::query!(
+            "
+SELECT community_id
+FROM channels
+WHERE id = $1
+            ",
+            channel_id as i64
+        )
+        .fetch_optional(&mut *db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't fetch channel from database: {}", err);
+            error!(SERVER, "Failed to resolve permissions")
+        })?
+        .ok_or_else(|| error!(NOT_FOUND))?
+        .community_id;
+        let community_id = match community_id {
+            Some(community_id) => community_id as u64,
+            None => return DirectChannel::get_permissions(channel_id, user_id, db).await,
+        };
+        let member = match MemberPermissions::get(community_id, user_id, &mut *db).await {
+            Ok(member) => member,
+            Err(ErrorResponse::NotFound { .. }) => return Err(error!(NOT_FOUND)),
+            Err(err) => return Err(err),
+        };
+        if !member.is_member {
+            return Ok(Self::empty());
+        }
+        let overwrites = get_overwrites(&[channel_id as i64], db)
+            .await?
+            .remove(&channel_id)
+            .unwrap_or_default();
+        Ok(member
+            .permissions
+            .apply_overwrites(&overwrites, community_id, user_id, &member.roles))
+    }
+
+    /// Resolve a user's permissions in all of a community's channels.
+    pub async fn get_community_channels(
+        community_id: u64,
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<HashMap<u64, Self>, ErrorResponse> {
+        let member = MemberPermissions::get(community_id, user_id, &mut *db).await?;
+        let channel_ids = get_channel_ids(community_id, &mut *db).await?;
+        if !member.is_member {
+            return Ok(channel_ids
+                .into_iter()
+                .map(|id| (id as u64, Self::empty()))
+                .collect());
+        }
+        let mut overwrites = get_overwrites(&channel_ids, db).await?;
+        Ok(channel_ids
+            .into_iter()
+            .map(|id| {
+                let overwrites = overwrites.remove(&(id as u64)).unwrap_or_default();
+                (
+                    id as u64,
+                    member.permissions.apply_overwrites(
+                        &overwrites,
+                        community_id,
+                        user_id,
+                        &member.roles,
+                    ),
+                )
+            })
+            .collect())
+    }
+
+    /// Resolve which of a community's channels each of a set of users can see, along with the IDs
+    /// of all of the community's channels.
+    ///
+    /// This only takes a few queries no matter how many users it's resolved for, users who aren't
+    /// members of the community are left out.
+    pub async fn get_visible_community_channels(
+        community_id: u64,
+        user_ids: &[u64],
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<(Vec<u64>, HashMap<u64, Vec<u64>>), ErrorResponse> {
+        let members = MemberPermissions::get_members(community_id, user_ids, &mut *db).await?;
+        let channel_ids = get_channel_ids(community_id, &mut *db).await?;
+        let overwrites = get_overwrites(&channel_ids, db).await?;
+        let visible = members
+            .into_iter()
+            .map(|(user_id, member)| {
+                let channels = channel_ids
+                    .iter()
+                    .map(|id| *id as u64)
+                    .filter(|id| {
+                        member
+                            .permissions
+                            .apply_overwrites(
+                                overwrites.get(id).map(Vec::as_slice).unwrap_or_default(),
+                                community_id,
+                                user_id,
+                                &member.roles,
+                            )
+                            .contains(Self::VIEW_CHANNEL)
+                    })
+                    .collect();
+                (user_id, channels)
+            })
+            .collect();
+        Ok((
+            channel_ids.into_iter().map(|id| id as u64).collect(),
+            visible,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::{ChannelOverwrite, OverwriteType, Permissions};
+
+    #[test]
+    fn apply_overwrites() {
+        let overwrites = vec![
+            ChannelOverwrite {
+                target_id: 1,
+                overwrite_type: OverwriteType::Role,
+                allow: 0,
+                deny: (Permissions::VIEW_CHANNEL | Permissions::SEND_MESSAGES).bits(),
+            },
+            ChannelOverwrite {
+                target_id: 2,
+                overwrite_type: OverwriteType::Role,
+                allow: Permissions::VIEW_CHANNEL.bits(),
+                deny: 0,
+            },
+            ChannelOverwrite {
+                target_id: 3,
+                overwrite_type: OverwriteType::Member,
+                allow: Permissions::SEND_MESSAGES.bits(),
+                deny: 0,
+            },
+        ];
+
+        let permissions = Permissions::DEFAULT.apply_overwrites(&overwrites, 1, 4, &[]);
+        assert_eq!(permissions, Permissions::ADD_REACTIONS);
+
+        let permissions = Permissions::DEFAULT.apply_overwrites(&overwrites, 1, 4, &[2]);
+        assert_eq!(
+            permissions,
+            Permissions::VIEW_CHANNEL | Permissions::ADD_REACTIONS
+        );
+
+        let permissions = Permissions::DEFAULT.apply_overwrites(&overwrites, 1, 3, &[2]);
+        assert_eq!(permissions, Permissions::DEFAULT);
+
+        let permissions = Permissions::ADMINISTRATOR.apply_overwrites(&overwrites, 1, 4, &[]);
+        assert_eq!(permissions, Permissions::ADMINISTRATOR);
+    }
+}
@@ -0,0 +1,751 @@
+use std::collections::HashMap;
+
+use lazy_static::lazy_static;
+use redis::AsyncCommands;
+use regex::Regex;
+use sqlx::{
+    pool::PoolConnection,
+    postgres::{PgConnection, PgRow},
+    Connection, Postgres, QueryBuilder, Row,
+};
+
+use crate::{
+    ids::IdGenerator,
+    models::{
+        Comment, CommentCreate, ErrorResponse, Post, PostCreate, PostSort, PostVote, User, VoteType,
+    },
+};
+
+/// The default amount of posts returned by [`Post::get_page`].
+pub const POST_PAGE_DEFAULT_LIMIT: u32 = 25;
+/// The maximum amount of posts returned by [`Post::get_page`].
+pub const POST_PAGE_MAX_LIMIT: u32 = 100;
+/// The maximum length of a post's content.
+pub const POST_CONTENT_LIMIT: usize = 10000;
+/// The maximum length of a comment's content.
+pub const COMMENT_CONTENT_LIMIT: usize = 4000;
+
+fn validate_topic(topic: &str) -> Result<(), ErrorResponse> {
+    lazy_static! {
+        static ref TOPIC_REGEX: Regex =
+            Regex::new(r"^[a-z0-9_-]+$").expect("Could not compile topic regex");
+    };
+    if topic.is_empty() || topic.len() > 32 {
+        Err(error!(
+            VALIDATION,
+            "topic", "The post's topic must be between 1 and 32 characters in length"
+        ))
+    } else if !TOPIC_REGEX.is_match(topic) {
+        Err(error!(
+            VALIDATION,
+            "topic",
+            "The post's topic must only consist of lowercase letters, numbers, underscores and dashes"
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+fn validate_title(title: &str) -> Result<(), ErrorResponse> {
+    if title.is_empty() || title.len() > 256 {
+        Err(error!(
+            VALIDATION,
+            "title", "The post's title must be between 1 and 256 characters in length"
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+impl PostCreate {
+    pub fn ensure_valid(&mut self) {
+        self.topic = self.topic.trim().to_string();
+        self.title = self.title.trim().to_string();
+        self.content = self.content.as_ref().map(|c| c.trim().to_string());
+    }
+
+    pub fn validate(&self) -> Result<(), ErrorResponse> {
+        validate_topic(&self.topic)?;
+        validate_title(&self.title)?;
+        if let Some(content) = &self.content {
+            if content.is_empty() || content.len() > POST_CONTENT_LIMIT {
+                return Err(error!(
+                    VALIDATION,
+                    "content",
+                    format!(
+                        "The post's content must be between 1 and {} characters in length",
+                        POST_CONTENT_LIMIT
+                    )
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl CommentCreate {
+    pub fn ensure_valid(&mut self) {
+        self.content = self.content.trim().to_string();
+    }
+
+    pub fn validate(&self) -> Result<(), ErrorResponse> {
+        if self.content.is_empty() || self.content.len() > COMMENT_CONTENT_LIMIT {
+            return Err(error!(
+                VALIDATION,
+                "content",
+                format!(
+                    "The comment's content must be between 1 and {} characters in length",
+                    COMMENT_CONTENT_LIMIT
+                )
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Start a query fetching posts along with the requester's votes on them, only fetches posts
+/// whose author wasn't deleted.
+fn post_query(user_id: u64) -> QueryBuilder<'static, Postgres> {
+    let mut query = QueryBuilder::new(
+        "
+SELECT
+  p.id,
+  p.author_id,
+  p.topic,
+  p.title,
+  p.content,
+  p.score,
+  (SELECT COUNT(*) FROM comments WHERE post_id = p.id) AS comment_count,
+  (SELECT vote FROM post_votes WHERE post_id = p.id AND user_id = ",
+    );
+    query.push_bind(user_id as i64).push(
+        ") AS vote
+FROM posts p
+JOIN users u
+ON p.author_id = u.id
+WHERE u.is_deleted = FALSE
+",
+    );
+    query
+}
+
+/// Get the author of a post, making sure the post exists.
+async fn get_author_id(id: u64, db: &mut PoolConnection<Postgres>) -> Result<u64, ErrorResponse> {
+    sqlx::query!(
+        "
+SELECT author_id
+FROM posts
+WHERE id = $1
+        ",
+        id as i64
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(|err| {
+        log::error!("Couldn't fetch post from database: {}", err);
+        error!(SERVER, "Failed to fetch post")
+    })?
+    .map(|p| p.author_id as u64)
+    .ok_or_else(|| error!(NOT_FOUND))
+}
+
+/// Lock a post for the rest of a transaction so that votes on it are applied one at a time,
+/// returning its author.
+async fn lock_post(id: u64, db: &mut PgConnection) -> Result<u64, ErrorResponse> {
+    sqlx::query!(
+        "
+SELECT author_id
+FROM posts
+WHERE id = $1
+FOR UPDATE
+        ",
+        id as i64
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(|err| {
+        log::error!("Couldn't lock post: {}", err);
+        error!(SERVER, "Failed to vote on post")
+    })?
+    .map(|p| p.author_id as u64)
+    .ok_or_else(|| error!(NOT_FOUND))
+}
+
+/// Change a post's score by a user's vote, also changing the social credit of its author unless
+/// they voted on their own post.
+async fn apply_vote(
+    id: u64,
+    author_id: u64,
+    user_id: u64,
+    delta: i32,
+    db: &mut PgConnection,
+) -> Result<(), ErrorResponse> {
+    sqlx::query!(
+        "
+UPDATE posts
+SET score = score + $1
+WHERE id = $2
+        ",
+        delta,
+        id as i64,
+    )
+    .execute(&mut *db)
+    .await
+    .map_err(|err| {
+        log::error!("Couldn't update post score: {}", err);
+        error!(SERVER, "Failed to vote on post")
+    })?;
+    if author_id != user_id {
+        sqlx::query!(
+            "
+UPDATE users
+SET social_credit = social_credit + $1
+WHERE id = $2
+            ",
+            delta,
+            author_id as i64,
+        )
+        .execute(db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't update user social credit: {}", err);
+            error!(SERVER, "Failed to vote on post")
+        })?;
+    }
+    Ok(())
+}
+
+/// Nest a post's comments under the comments they reply to.
+///
+/// The comments are expected to be ordered by ID, so replies always come after their parent.
+fn build_comment_tree(comments: Vec<Comment>) -> Vec<Comment> {
+    fn attach(mut comment: Comment, replies: &mut HashMap<u64, Vec<Comment>>) -> Comment {
+        comment.replies = replies
+            .remove(&comment.id)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|r| attach(r, replies))
+            .collect();
+        comment
+    }
+
+    let mut roots = vec![];
+    let mut replies: HashMap<u64, Vec<Comment>> = HashMap::new();
+    for comment in comments {
+        match comment.parent_id {
+            Some(parent_id) => replies.entry(parent_id).or_default().push(comment),
+            None => roots.push(comment),
+        }
+    }
+    roots.into_iter().map(|c| attach(c, &mut replies)).collect()
+}
+
+impl Post {
+    /// Build full posts out of rows fetched using [`post_query`], keeping their order.
+    async fn from_rows<C: AsyncCommands>(
+        rows: Vec<PgRow>,
+        db: &mut PoolConnection<Postgres>,
+        cache: &mut C,
+    ) -> Result<Vec<Self>, ErrorResponse> {
+        let mut authors: HashMap<u64, User> = HashMap::new();
+        let mut posts = Vec::with_capacity(rows.len());
+        for row in rows {
+            let author_id = row.get::<i64, _>("author_id") as u64;
+            let author = match authors.get(&author_id) {
+                Some(author) => author.clone(),
+                None => {
+                    let author = User::get(author_id, None, &mut *db, cache).await?;
+                    authors.insert(author_id, author.clone());
+                    author
+                }
+            };
+            posts.push(Self {
+                id: row.get::<i64, _>("id") as u64,
+                author,
+                topic: row.get("topic"),
+                title: row.get("title"),
+                content: row.get("content"),
+                score: row.get("score"),
+                comment_count: row.get::<i64, _>("comment_count") as u64,
+                vote: match row.get::<Option<i16>, _>("vote") {
+                    Some(1) => Some(VoteType::Up),
+                    Some(_) => Some(VoteType::Down),
+                    None => None,
+                },
+            });
+        }
+        Ok(posts)
+    }
+
+    pub async fn create<C: AsyncCommands>(
+        mut post: PostCreate,
+        author_id: u64,
+        id_generator: &mut IdGenerator,
+        db: &mut PoolConnection<Postgres>,
+        cache: &mut C,
+    ) -> Result<Self, ErrorResponse> {
+        post.ensure_valid();
+        post.validate()?;
+        let author = User::get(author_id, None, &mut *db, cache).await?;
+        let id = id_generator.generate();
+        sqlx::query!(
+            "
+INSERT INTO posts(id, author_id, topic, title, content)
+VALUES($1, $2, $3, $4, $5)
+            ",
+            id as i64,
+            author_id as i64,
+            post.topic,
+            post.title,
+            post.content,
+        )
+        .execute(db)
+        .await
+        .map_err(|err| {
+            log::error!("Failed to store post in database: {}", err);
+            error!(SERVER, "Could not create post")
+        })?;
+        Ok(Self {
+            id,
+            author,
+            topic: post.topic,
+            title: post.title,
+            content: post.content,
+            score: 0,
+            comment_count: 0,
+            vote: None,
+        })
+    }
+
+    pub async fn get<C: AsyncCommands>(
+        id: u64,
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+        cache: &mut C,
+    ) -> Result<Self, ErrorResponse> {
+        let mut query = post_query(user_id);
+        query.push(" AND p.id = ").push_bind(id as i64);
+        let row = query
+            .build()
+            .fetch_optional(&mut *db)
+            .await
+            .map_err(|err| {
+                log::error!("Couldn't fetch post from database: {}", err);
+                error!(SERVER, "Failed to fetch post")
+            })?
+            .ok_or_else(|| error!(NOT_FOUND))?;
+        Ok(Self::from_rows(vec![row], db, cache).await?.remove(0))
+    }
+
+    /// Get a page of posts, optionally only from a single topic.
+    ///
+    /// `before` is the ID of the last post of the previous page, the next page starts right after
+    /// it in the requested order.
+    pub async fn get_page<C: AsyncCommands>(
+        topic: Option<&str>,
+        sort: PostSort,
+        before: Option<u64>,
+        limit: Option<u32>,
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+        cache: &mut C,
+    ) -> Result<Vec<Self>, ErrorResponse> {
+        let limit = limit.unwrap_or(POST_PAGE_DEFAULT_LIMIT);
+        if limit == 0 || limit > POST_PAGE_MAX_LIMIT {
+            return Err(error!(
+                VALIDATION,
+                "limit",
+                format!(
+                    "The post limit must be between 1 and {}",
+                    POST_PAGE_MAX_LIMIT
+                )
+            ));
+        }
+        let mut query = post_query(user_id);
+        if let Some(topic) = topic {
+            query.push(" AND p.topic = ").push_bind(topic.to_string());
+        }
+        let key = match sort {
+            PostSort::Hot => Some("hot"),
+            PostSort::New => None,
+            PostSort::Top => Some("score"),
+        };
+        if let Some(before) = before {
+            match key {
+                Some(key) => query
+                    .push(format!(
+                        " AND (p.{}, p.id) < (SELECT {}, id FROM posts WHERE id = ",
+                        key, key
+                    ))
+                    .push_bind(before as i64)
+                    .push(")"),
+                None => query.push(" AND p.id < ").push_bind(before as i64),
+            };
+        }
+        match key {
+            Some(key) => query.push(format!(" ORDER BY p.{} DESC, p.id DESC", key)),
+            None => query.push(" ORDER BY p.id DESC"),
+        };
+        query.push(" LIMIT ").push_bind(limit as i64);
+        let rows = query.build().fetch_all(&mut *db).await.map_err(|err| {
+            log::error!("Couldn't fetch posts: {}", err);
+            error!(SERVER, "Failed to fetch posts")
+        })?;
+        Self::from_rows(rows, db, cache).await
+    }
+
+    /// Delete a post you created.
+    pub async fn delete(
+        id: u64,
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<(), ErrorResponse> {
+        if get_author_id(id, &mut *db).await? != user_id {
+            return Err(error!(FORBIDDEN));
+        }
+        sqlx::query!(
+            "
+DELETE FROM posts
+WHERE id = $1
+            ",
+            id as i64
+        )
+        .execute(db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't delete post: {}", err);
+            error!(SERVER, "Failed to delete post")
+        })?;
+        Ok(())
+    }
+
+    /// Vote on a post, replacing your previous vote on it.
+    pub async fn vote(
+        id: u64,
+        user_id: u64,
+        vote: PostVote,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<(), ErrorResponse> {
+        let value: i16 = match vote.vote_type {
+            VoteType::Up => 1,
+            VoteType::Down => -1,
+        };
+        let mut tx = db.begin().await.map_err(|err| {
+            log::error!("Couldn't start post vote transaction: {}", err);
+            error!(SERVER, "Failed to vote on post")
+        })?;
+        let author_id = lock_post(id, &mut tx).await?;
+        let old = sqlx::query!(
+            "
+SELECT vote
+FROM post_votes
+WHERE post_id = $1
+AND user_id = $2
+            ",
+            id as i64,
+            user_id as i64,
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't fetch post vote: {}", err);
+            error!(SERVER, "Failed to vote on post")
+        })?
+        .map(|v| v.vote)
+        .unwrap_or(0);
+        if old == value {
+            return Ok(());
+        }
+        sqlx::query!(
+            "
+INSERT INTO post_votes(post_id, user_id, vote)
+VALUES($1, $2, $3)
+ON CONFLICT (post_id, user_id)
+DO UPDATE SET vote = $3
+            ",
+            id as i64,
+            user_id as i64,
+            value,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't store post vote: {}", err);
+            error!(SERVER, "Failed to vote on post")
+        })?;
+        apply_vote(id, author_id, user_id, (value - old) as i32, &mut tx).await?;
+        tx.commit().await.map_err(|err| {
+            log::error!("Couldn't commit post vote: {}", err);
+            error!(SERVER, "Failed to vote on post")
+        })
+    }
+
+    /// Remove your vote on a post.
+    pub async fn remove_vote(
+        id: u64,
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<(), ErrorResponse> {
+        let mut tx = db.begin().await.map_err(|err| {
+            log::error!("Couldn't start post vote transaction: {}", err);
+            error!(SERVER, "Failed to remove vote")
+        })?;
+        let author_id = lock_post(id, &mut tx).await?;
+        let old = sqlx::query!(
+            "
+DELETE FROM post_votes
+WHERE post_id = $1
+AND user_id = $2
+RETURNING vote
+            ",
+            id as i64,
+            user_id as i64,
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't remove post vote: {}", err);
+            error!(SERVER, "Failed to remove vote")
+        })?
+        .ok_or_else(|| error!(NOT_FOUND))?
+        .vote;
+        apply_vote(id, author_id, user_id, -old as i32, &mut tx).await?;
+        tx.commit().await.map_err(|err| {
+            log::error!("Couldn't commit post vote removal: {}", err);
+            error!(SERVER, "Failed to remove vote")
+        })
+    }
+}
+
+impl Comment {
+    pub async fn create<C: AsyncCommands>(
+        post_id: u64,
+        mut comment: CommentCreate,
+        author_id: u64,
+        id_generator: &mut IdGenerator,
+        db: &mut PoolConnection<Postgres>,
+        cache: &mut C,
+    ) -> Result<Self, ErrorResponse> {
+        comment.ensure_valid();
+        comment.validate()?;
+        get_author_id(post_id, &mut *db).await?;
+        if let Some(parent_id) = comment.parent_id {
+            let exists = sqlx::query!(
+                r#"
+SELECT EXISTS(
+  SELECT 1
+  FROM comments
+  WHERE id = $1
+  AND post_id = $2
+) AS "exists!"
+                "#,
+                parent_id as i64,
+                post_id as i64,
+            )
+            .fetch_one(&mut *db)
+            .await
+            .map_err(|err| {
+                log::error!("Couldn't fetch parent comment: {}", err);
+                error!(SERVER, "Failed to create comment")
+            })?
+            .exists;
+            if !exists {
+                return Err(error!(
+                    VALIDATION,
+                    "parent_id", "The parent comment must exist on the same post"
+                ));
+            }
+        }
+        let author = User::get(author_id, None, &mut *db, cache).await?;
+        let id = id_generator.generate();
+        sqlx::query!(
+            "
+INSERT INTO comments(id, post_id, parent_id, author_id, content)
+VALUES($1, $2, $3, $4, $5)
+            ",
+            id as i64,
+            post_id as i64,
+            comment.parent_id.map(|p| p as i64),
+            author_id as i64,
+            comment.content,
+        )
+        .execute(db)
+        .await
+        .map_err(|err| {
+            log::error!("Failed to store comment in database: {}", err);
+            error!(SERVER, "Could not create comment")
+        })?;
+        Ok(Self {
+            id,
+            post_id,
+            parent_id: comment.parent_id,
+            author,
+            content: comment.content,
+            replies: vec![],
+        })
+    }
+
+    /// Get all of a post's comments as a tree, oldest first.
+    ///
+    /// Comments whose author was deleted are left out along with their replies.
+    pub async fn get_tree<C: AsyncCommands>(
+        post_id: u64,
+        db: &mut PoolConnection<Postgres>,
+        cache: &mut C,
+    ) -> Result<Vec<Self>, ErrorResponse> {
+        get_author_id(post_id, &mut *db).await?;
+        let rows = sqlx::query!(
+            "
+SELECT c.id, c.parent_id, c.author_id, c.content
+FROM comments c
+JOIN users u
+ON c.author_id = u.id
+WHERE c.post_id = $1
+AND u.is_deleted = FALSE
+ORDER BY c.id
+            ",
+            post_id as i64,
+        )
+        .fetch_all(&mut *db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't fetch post comments: {}", err);
+            error!(SERVER, "Failed to fetch comments")
+        })?;
+        let mut authors: HashMap<u64, User> = HashMap::new();
+        let mut comments = Vec::with_capacity(rows.len());
+        for row in rows {
+            let author_id = row.author_id as u64;
+            let author = match authors.get(&author_id) {
+                Some(author) => author.clone(),
+                None => {
+                    let author = User::get(author_id, None, &mut *db, cache).await?;
+                    authors.insert(author_id, author.clone());
+                    author
+                }
+            };
+            comments.push(Self {
+                id: row.id as u64,
+                post_id,
+                parent_id: row.parent_id.map(|p| p as u64),
+                author,
+                content: row.content,
+                replies: vec![],
+            });
+        }
+        Ok(build_comment_tree(comments))
+    }
+
+    /// Delete a comment you created along with its replies.
+    pub async fn delete(
+        id: u64,
+        post_id: u64,
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<(), ErrorResponse> {
+        let author_id = sqlx::query!(
+            "
+SELECT author_id
+FROM comments
+WHERE id = $1
+AND post_id = $2
+            ",
+            id as i64,
+            post_id as i64,
+        )
+        .fetch_optional(&mut *db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't fetch comment from database: {}", err);
+            error!(SERVER, "Failed to fetch comment")
+        })?
+        .ok_or_else(|| error!(NOT_FOUND))?
+        .author_id as u64;
+        if author_id != user_id {
+            return Err(error!(FORBIDDEN));
+        }
+        sqlx::query!(
+            "
+DELETE FROM comments
+WHERE id = $1
+            ",
+            id as i64
+        )
+        .execute(db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't delete comment: {}", err);
+            error!(SERVER, "Failed to delete comment")
+        })?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::{Comment, PostCreate, Status, StatusType, User};
+
+    fn comment(id: u64, parent_id: Option<u64>) -> Comment {
+        Comment {
+            id,
+            post_id: 1,
+            parent_id,
+            author: User {
+                id: 1,
+                username: "yendri".to_string(),
+                display_name: None,
+                social_credit: 0,
+                follower_count: 0,
+                following_count: 0,
+                status: Status {
+                    status_type: StatusType::Offline,
+                    text: None,
+                },
+                bio: None,
+                avatar: None,
+                banner: None,
+                badges: 0,
+                permissions: 0,
+                email: None,
+                verified: None,
+                two_factor_auth: None,
+            },
+            content: "Stomp it.".to_string(),
+            replies: vec![],
+        }
+    }
+
+    #[test]
+    fn build_comment_tree() {
+        let tree = super::build_comment_tree(vec![
+            comment(2, None),
+            comment(3, Some(2)),
+            comment(4, None),
+            comment(5, Some(3)),
+            comment(6, Some(2)),
+        ]);
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree[0].id, 2);
+        assert_eq!(
+            tree[0].replies.iter().map(|c| c.id).collect::<Vec<_>>(),
+            vec![3, 6]
+        );
+        assert_eq!(tree[0].replies[0].replies[0].id, 5);
+        assert!(tree[1].replies.is_empty());
+    }
+
+    #[test]
+    fn validate_post() {
+        let mut post = PostCreate {
+            topic: " lanternflies ".to_string(),
+            title: " Found another one ".to_string(),
+            content: None,
+        };
+        post.ensure_valid();
+        assert_eq!(post.topic, "lanternflies");
+        assert_eq!(post.title, "Found another one");
+        assert!(post.validate().is_ok());
+        assert!(super::validate_topic("Lanternflies").is_err());
+        assert!(super::validate_title("").is_err());
+    }
+}
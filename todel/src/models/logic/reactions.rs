@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+
+use sqlx::{pool::PoolConnection, Connection, Postgres};
+
+use crate::models::{Emoji, ErrorResponse, Permissions, Reaction};
+
+/// The maximum amount of distinct emojis a single message can be reacted with.
+pub const MESSAGE_MAX_REACTIONS: i64 = 20;
+
+impl Emoji {
+    /// Parse an emoji from its raw form, as used in the reaction routes.
+    pub fn parse(emoji: &str) -> Result<Self, ErrorResponse> {
+        match emojis::get(emoji) {
+            Some(emoji) => Ok(Self::Unicode {
+                emoji: emoji.as_str().to_string(),
+            }),
+            None => Err(error!(VALIDATION, "emoji", "Invalid emoji")),
+        }
+    }
+
+    /// The key the emoji is stored as in the database.
+    fn key(&self) -> &str {
+        match self {
+            Self::Unicode { emoji } => emoji,
+        }
+    }
+
+    fn from_key(key: String) -> Self {
+        Self::Unicode { emoji: key }
+    }
+}
+
+impl Reaction {
+    /// Add a reaction to a message.
+    ///
+    /// Returns whether the reaction is new, reacting twice with the same emoji does nothing.
+    pub async fn add(
+        message_id: u64,
+        user_id: u64,
+        emoji: &Emoji,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<bool, ErrorResponse> {
+        let channel_id = sqlx::query!(
+            "
+SELECT channel_id
+FROM messages
+WHERE id = $1
+            ",
+            message_id as i64,
+        )
+        .fetch_optional(&mut *db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't fetch message: {}", err);
+            error!(SERVER, "Failed to add reaction")
+        })?
+        .ok_or_else(|| error!(NOT_FOUND))?
+        .channel_id;
+        if let Some(channel_id) = channel_id {
+            Permissions::require_channel(
+                channel_id as u64,
+                user_id,
+                Permissions::ADD_REACTIONS,
+                &mut *db,
+            )
+            .await?;
+        }
+        let mut tx = db.begin().await.map_err(|err| {
+            log::error!("Couldn't start reaction transaction: {}", err);
+            error!(SERVER, "Failed to add reaction")
+        })?;
+        // Lock the message so that concurrent reactions can't go over the limit, the reactions
+        // are counted in a separate query so that they're counted after the lock is acquired.
+        sqlx::query!(
+            "
+SELECT id
+FROM messages
+WHERE id = $1
+FOR UPDATE
+            ",
+            message_id as i64,
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't lock message: {}", err);
+            error!(SERVER, "Failed to add reaction")
+        })?
+        .ok_or_else(|| error!(NOT_FOUND))?;
+        let reactions = sqlx::query!(
+            r#"
+SELECT
+  COUNT(DISTINCT emoji) AS "emoji_count!",
+  BOOL_OR(emoji = $2) AS emoji_used
+FROM reactions
+WHERE message_id = $1
+            "#,
+            message_id as i64,
+            emoji.key(),
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't fetch message reactions: {}", err);
+            error!(SERVER, "Failed to add reaction")
+        })?;
+        if !reactions.emoji_used.unwrap_or(false) && reactions.emoji_count >= MESSAGE_MAX_REACTIONS
+        {
+            return Err(error!(
+                VALIDATION,
+                "emoji",
+                format!(
+                    "A message can only have {} different reactions",
+                    MESSAGE_MAX_REACTIONS
+                )
+            ));
+        }
+        let result = sqlx::query!(
+            "
+INSERT INTO reactions(message_id, user_id, emoji)
+VALUES($1, $2, $3)
+ON CONFLICT DO NOTHING
+            ",
+            message_id as i64,
+            user_id as i64,
+            emoji.key(),
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't add reaction: {}", err);
+            error!(SERVER, "Failed to add reaction")
+        })?;
+        tx.commit().await.map_err(|err| {
+            log::error!("Couldn't commit reaction: {}", err);
+            error!(SERVER, "Failed to add reaction")
+        })?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Remove a reaction from a message.
+    ///
+    /// Returns whether there was a reaction to remove.
+    pub async fn remove(
+        message_id: u64,
+        user_id: u64,
+        emoji: &Emoji,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<bool, ErrorResponse> {
+        let result = sqlx::query!(
+            "
+DELETE FROM reactions
+WHERE message_id = $1
+AND user_id = $2
+AND emoji = $3
+            ",
+            message_id as i64,
+            user_id as i64,
+            emoji.key(),
+        )
+        .execute(db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't remove reaction: {}", err);
+            error!(SERVER, "Failed to remove reaction")
+        })?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Get the aggregated reactions of the provided messages, keyed by message ID.
+    pub async fn get_for_messages(
+        message_ids: &[i64],
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<HashMap<u64, Vec<Self>>, ErrorResponse> {
+        let rows = sqlx::query!(
+            r#"
+SELECT message_id, emoji, COUNT(*) AS "count!"
+FROM reactions
+WHERE message_id = ANY($1)
+GROUP BY message_id, emoji
+ORDER BY MIN(created_at)
+            "#,
+            message_ids
+        )
+        .fetch_all(db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't fetch message reactions: {}", err);
+            error!(SERVER, "Failed to fetch message reactions")
+        })?;
+        let mut reactions: HashMap<u64, Vec<Self>> = HashMap::new();
+        for r in rows {
+            reactions
+                .entry(r.message_id as u64)
+                .or_default()
+                .push(Self {
+                    emoji: Emoji::from_key(r.emoji),
+                    count: r.count as u64,
+                });
+        }
+        Ok(reactions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::Emoji;
+
+    #[test]
+    fn parse_emoji() {
+        assert_eq!(
+            Emoji::parse("👍").unwrap(),
+            Emoji::Unicode {
+                emoji: "👍".to_string()
+            }
+        );
+        assert!(Emoji::parse("🏳️‍⚧️").is_ok());
+        assert!(Emoji::parse("a").is_err());
+        assert!(Emoji::parse("👍👍").is_err());
+        assert!(Emoji::parse("").is_err());
+    }
+}
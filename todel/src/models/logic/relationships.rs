@@ -0,0 +1,402 @@
+use sqlx::{pool::PoolConnection, Postgres};
+
+use crate::models::{ErrorResponse, Relationship, RelationshipType, ServerPayload, User};
+
+/// Get a user's relationship with another user.
+async fn get_type(
+    user_id: u64,
+    target_id: u64,
+    db: &mut PoolConnection<Postgres>,
+) -> Result<Option<RelationshipType>, ErrorResponse> {
+    sqlx::query!(
+        r#"
+SELECT relationship_type as "relationship_type: RelationshipType"
+FROM relationships
+WHERE user_id = $1
+AND target_id = $2
+        "#,
+        user_id as i64,
+        target_id as i64,
+    )
+    .fetch_optional(db)
+    .await
+    .map(|r| r.map(|r| r.relationship_type))
+    .map_err(|err| {
+        log::error!("Couldn't fetch relationship from database: {}", err);
+        error!(SERVER, "Failed to fetch relationship")
+    })
+}
+
+/// Create or replace a user's relationship with another user.
+async fn set_type(
+    user_id: u64,
+    target_id: u64,
+    relationship_type: RelationshipType,
+    db: &mut PoolConnection<Postgres>,
+) -> Result<(), ErrorResponse> {
+    sqlx::query!(
+        "
+INSERT INTO relationships(user_id, target_id, relationship_type)
+VALUES($1, $2, $3)
+ON CONFLICT (user_id, target_id)
+DO UPDATE SET relationship_type = $3
+        ",
+        user_id as i64,
+        target_id as i64,
+        relationship_type as RelationshipType,
+    )
+    .execute(db)
+    .await
+    .map_err(|err| {
+        log::error!("Failed to store relationship in database: {}", err);
+        error!(SERVER, "Could not update relationship")
+    })?;
+    Ok(())
+}
+
+/// Remove a user's relationship with another user, blocks are kept if `keep_blocks` is set.
+///
+/// Returns whether a relationship was removed.
+async fn remove_type(
+    user_id: u64,
+    target_id: u64,
+    keep_blocks: bool,
+    db: &mut PoolConnection<Postgres>,
+) -> Result<bool, ErrorResponse> {
+    sqlx::query!(
+        "
+DELETE FROM relationships
+WHERE user_id = $1
+AND target_id = $2
+AND (relationship_type != 'BLOCKED' OR NOT $3)
+        ",
+        user_id as i64,
+        target_id as i64,
+        keep_blocks,
+    )
+    .execute(db)
+    .await
+    .map(|r| r.rows_affected() > 0)
+    .map_err(|err| {
+        log::error!("Couldn't remove relationship: {}", err);
+        error!(SERVER, "Could not update relationship")
+    })
+}
+
+/// Get the `RELATIONSHIP_*` event describing how a user's relationship changed, if it did.
+fn get_event(
+    user_id: u64,
+    target_id: u64,
+    old: Option<RelationshipType>,
+    new: Option<RelationshipType>,
+) -> Option<ServerPayload> {
+    let relationship = |relationship_type| Relationship {
+        id: target_id,
+        relationship_type,
+    };
+    match (old, new) {
+        (None, Some(new)) => Some(ServerPayload::RelationshipCreate {
+            user_id,
+            relationship: relationship(new),
+        }),
+        (Some(old), Some(new)) if old != new => Some(ServerPayload::RelationshipUpdate {
+            user_id,
+            relationship: relationship(new),
+        }),
+        (Some(_), None) => Some(ServerPayload::RelationshipDelete {
+            user_id,
+            id: target_id,
+        }),
+        _ => None,
+    }
+}
+
+impl Relationship {
+    /// Get all of a user's relationships.
+    pub async fn get_all(
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Vec<Self>, ErrorResponse> {
+        sqlx::query!(
+            r#"
+SELECT target_id, relationship_type as "relationship_type: RelationshipType"
+FROM relationships
+WHERE user_id = $1
+ORDER BY target_id
+            "#,
+            user_id as i64,
+        )
+        .fetch_all(db)
+        .await
+        .map(|rows| {
+            rows.into_iter()
+                .map(|r| Self {
+                    id: r.target_id as u64,
+                    relationship_type: r.relationship_type,
+                })
+                .collect()
+        })
+        .map_err(|err| {
+            log::error!("Couldn't fetch user relationships: {}", err);
+            error!(SERVER, "Failed to fetch relationships")
+        })
+    }
+
+    /// Get the IDs of the users who blocked a user.
+    pub async fn get_blocked_by(
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Vec<u64>, ErrorResponse> {
+        sqlx::query!(
+            "
+SELECT user_id
+FROM relationships
+WHERE target_id = $1
+AND relationship_type = 'BLOCKED'
+            ",
+            user_id as i64,
+        )
+        .fetch_all(db)
+        .await
+        .map(|rows| rows.into_iter().map(|r| r.user_id as u64).collect())
+        .map_err(|err| {
+            log::error!("Couldn't fetch user blocks: {}", err);
+            error!(SERVER, "Failed to fetch relationships")
+        })
+    }
+
+    /// Check whether a user was blocked by another user.
+    pub async fn is_blocked(
+        user_id: u64,
+        by_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<bool, ErrorResponse> {
+        Ok(get_type(by_id, user_id, db).await? == Some(RelationshipType::Blocked))
+    }
+
+    /// Send a friend request to another user, accepting theirs if they already sent you one.
+    ///
+    /// Returns the `RELATIONSHIP_*` events that need to be dispatched.
+    pub async fn send_request(
+        user_id: u64,
+        target_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Vec<ServerPayload>, ErrorResponse> {
+        if user_id == target_id {
+            return Err(error!(
+                VALIDATION,
+                "user", "You can't send a friend request to yourself"
+            ));
+        }
+        match get_type(user_id, target_id, &mut *db).await? {
+            Some(RelationshipType::Incoming) => {
+                return Self::accept_request(user_id, target_id, db).await
+            }
+            Some(RelationshipType::Outgoing) => return Ok(vec![]),
+            Some(RelationshipType::Friend) => {
+                return Err(error!(
+                    VALIDATION,
+                    "user", "You're already friends with this user"
+                ))
+            }
+            Some(RelationshipType::Blocked) => {
+                return Err(error!(
+                    VALIDATION,
+                    "user", "You can't send a friend request to a user you blocked"
+                ))
+            }
+            None => {}
+        }
+        if !User::exists(target_id, &mut *db).await? {
+            return Err(error!(NOT_FOUND));
+        }
+        if Self::is_blocked(user_id, target_id, &mut *db).await? {
+            return Err(error!(FORBIDDEN));
+        }
+        set_type(user_id, target_id, RelationshipType::Outgoing, &mut *db).await?;
+        set_type(target_id, user_id, RelationshipType::Incoming, db).await?;
+        Ok(vec![
+            ServerPayload::RelationshipCreate {
+                user_id,
+                relationship: Self {
+                    id: target_id,
+                    relationship_type: RelationshipType::Outgoing,
+                },
+            },
+            ServerPayload::RelationshipCreate {
+                user_id: target_id,
+                relationship: Self {
+                    id: user_id,
+                    relationship_type: RelationshipType::Incoming,
+                },
+            },
+        ])
+    }
+
+    /// Accept a friend request another user sent you.
+    ///
+    /// Returns the `RELATIONSHIP_*` events that need to be dispatched.
+    pub async fn accept_request(
+        user_id: u64,
+        target_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Vec<ServerPayload>, ErrorResponse> {
+        if get_type(user_id, target_id, &mut *db).await? != Some(RelationshipType::Incoming) {
+            return Err(error!(NOT_FOUND));
+        }
+        set_type(user_id, target_id, RelationshipType::Friend, &mut *db).await?;
+        set_type(target_id, user_id, RelationshipType::Friend, db).await?;
+        Ok(vec![
+            ServerPayload::RelationshipUpdate {
+                user_id,
+                relationship: Self {
+                    id: target_id,
+                    relationship_type: RelationshipType::Friend,
+                },
+            },
+            ServerPayload::RelationshipUpdate {
+                user_id: target_id,
+                relationship: Self {
+                    id: user_id,
+                    relationship_type: RelationshipType::Friend,
+                },
+            },
+        ])
+    }
+
+    /// Decline a friend request another user sent you.
+    ///
+    /// Returns the `RELATIONSHIP_*` events that need to be dispatched.
+    pub async fn decline_request(
+        user_id: u64,
+        target_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Vec<ServerPayload>, ErrorResponse> {
+        if get_type(user_id, target_id, &mut *db).await? != Some(RelationshipType::Incoming) {
+            return Err(error!(NOT_FOUND));
+        }
+        Self::remove(user_id, target_id, db).await
+    }
+
+    /// Remove your relationship with another user. This unfriends them, cancels or declines a
+    /// pending friend request or unblocks them.
+    ///
+    /// Returns the `RELATIONSHIP_*` events that need to be dispatched.
+    pub async fn remove(
+        user_id: u64,
+        target_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Vec<ServerPayload>, ErrorResponse> {
+        let relationship_type = get_type(user_id, target_id, &mut *db)
+            .await?
+            .ok_or_else(|| error!(NOT_FOUND))?;
+        remove_type(user_id, target_id, false, &mut *db).await?;
+        let mut events = vec![ServerPayload::RelationshipDelete {
+            user_id,
+            id: target_id,
+        }];
+        // Blocks are one-sided so the other user's relationship is only tied to yours otherwise.
+        if relationship_type != RelationshipType::Blocked
+            && remove_type(target_id, user_id, true, db).await?
+        {
+            events.push(ServerPayload::RelationshipDelete {
+                user_id: target_id,
+                id: user_id,
+            });
+        }
+        Ok(events)
+    }
+
+    /// Block another user, removing any other relationship you have with them and any follow
+    /// between the two of you.
+    ///
+    /// Returns the `RELATIONSHIP_*` and `FOLLOW_DELETE` events that need to be dispatched.
+    pub async fn block(
+        user_id: u64,
+        target_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Vec<ServerPayload>, ErrorResponse> {
+        if user_id == target_id {
+            return Err(error!(VALIDATION, "user", "You can't block yourself"));
+        }
+        if !User::exists(target_id, &mut *db).await? {
+            return Err(error!(NOT_FOUND));
+        }
+        let old = get_type(user_id, target_id, &mut *db).await?;
+        let target_old = get_type(target_id, user_id, &mut *db).await?;
+        set_type(user_id, target_id, RelationshipType::Blocked, &mut *db).await?;
+        let target_new = match remove_type(target_id, user_id, true, &mut *db).await? {
+            true => None,
+            false => target_old,
+        };
+        let unfollows = sqlx::query!(
+            "
+DELETE FROM follows
+WHERE (follower_id = $1 AND user_id = $2)
+OR (follower_id = $2 AND user_id = $1)
+RETURNING follower_id, user_id
+            ",
+            user_id as i64,
+            target_id as i64,
+        )
+        .fetch_all(db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't remove follows: {}", err);
+            error!(SERVER, "Failed to block user")
+        })?
+        .into_iter()
+        .map(|f| ServerPayload::FollowDelete {
+            follower_id: f.follower_id as u64,
+            user_id: f.user_id as u64,
+        });
+        Ok([
+            get_event(user_id, target_id, old, Some(RelationshipType::Blocked)),
+            get_event(target_id, user_id, target_old, target_new),
+        ]
+        .into_iter()
+        .flatten()
+        .chain(unfollows)
+        .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::{Relationship, RelationshipType, ServerPayload};
+
+    #[test]
+    fn get_event() {
+        assert!(super::get_event(1, 2, None, None).is_none());
+        assert!(super::get_event(
+            1,
+            2,
+            Some(RelationshipType::Blocked),
+            Some(RelationshipType::Blocked)
+        )
+        .is_none());
+        assert!(matches!(
+            super::get_event(1, 2, None, Some(RelationshipType::Blocked)),
+            Some(ServerPayload::RelationshipCreate {
+                user_id: 1,
+                relationship: Relationship {
+                    id: 2,
+                    relationship_type: RelationshipType::Blocked
+                }
+            })
+        ));
+        assert!(matches!(
+            super::get_event(
+                1,
+                2,
+                Some(RelationshipType::Friend),
+                Some(RelationshipType::Blocked)
+            ),
+            Some(ServerPayload::RelationshipUpdate { user_id: 1, .. })
+        ));
+        assert!(matches!(
+            super::get_event(1, 2, Some(RelationshipType::Friend), None),
+            Some(ServerPayload::RelationshipDelete { user_id: 1, id: 2 })
+        ));
+    }
+}
@@ -0,0 +1,348 @@
+use sqlx::{pool::PoolConnection, Postgres};
+
+use crate::{
+    ids::IdGenerator,
+    models::{
+        ErrorResponse, Permissions, Report, ReportCategory, ReportCreate, ReportResolve,
+        ReportStatus, ReportTargetType, User,
+    },
+};
+
+/// The maximum length of a report's details.
+pub const REPORT_DETAILS_LIMIT: usize = 2000;
+/// The default amount of reports returned by [`Report::get_page`].
+pub const REPORTS_DEFAULT_LIMIT: u32 = 50;
+/// The maximum amount of reports returned by [`Report::get_page`].
+pub const REPORTS_MAX_LIMIT: u32 = 100;
+
+/// Make sure the target of a report exists and is visible to the reporter.
+async fn ensure_reportable(
+    target_type: ReportTargetType,
+    target_id: u64,
+    reporter_id: u64,
+    db: &mut PoolConnection<Postgres>,
+) -> Result<(), ErrorResponse> {
+    match target_type {
+        ReportTargetType::User => {
+            if target_id == reporter_id {
+                return Err(error!(VALIDATION, "target_id", "You can't report yourself"));
+            }
+            if !User::exists(target_id, db).await? {
+                return Err(error!(NOT_FOUND));
+            }
+        }
+        ReportTargetType::Message => {
+            let message = sqlx::query!(
+                "
+SELECT author_id, channel_id
+FROM messages
+WHERE id = $1
+                ",
+                target_id as i64,
+            )
+            .fetch_optional(&mut *db)
+            .await
+            .map_err(|err| {
+                log::error!("Couldn't fetch reported message: {}", err);
+                error!(SERVER, "Failed to create report")
+            })?
+            .ok_or_else(|| error!(NOT_FOUND))?;
+            if message.author_id as u64 == reporter_id {
+                return Err(error!(
+                    VALIDATION,
+                    "target_id", "You can't report your own message"
+                ));
+            }
+            if let Some(channel_id) = message.channel_id {
+                Permissions::require_channel(
+                    channel_id as u64,
+                    reporter_id,
+                    Permissions::VIEW_CHANNEL,
+                    db,
+                )
+                .await?;
+            }
+        }
+        ReportTargetType::File => {
+            let exists = sqlx::query!(
+                r#"
+SELECT EXISTS(
+  SELECT 1
+  FROM files
+  WHERE id = $1
+) AS "exists!"
+                "#,
+                target_id as i64,
+            )
+            .fetch_one(db)
+            .await
+            .map(|r| r.exists)
+            .map_err(|err| {
+                log::error!("Couldn't check reported file existence: {}", err);
+                error!(SERVER, "Failed to create report")
+            })?;
+            if !exists {
+                return Err(error!(NOT_FOUND));
+            }
+        }
+    }
+    Ok(())
+}
+
+impl ReportCreate {
+    pub fn ensure_valid(&mut self) {
+        self.details = self
+            .details
+            .as_ref()
+            .map(|d| d.trim().to_string())
+            .filter(|d| !d.is_empty());
+    }
+
+    pub fn validate(&self) -> Result<(), ErrorResponse> {
+        if let Some(details) = &self.details {
+            if details.len() > REPORT_DETAILS_LIMIT {
+                return Err(error!(
+                    VALIDATION,
+                    "details",
+                    format!(
+                        "The report's details must be between 1 and {} characters in length",
+                        REPORT_DETAILS_LIMIT
+                    )
+                ));
+            }
+        }
+        if self.category == ReportCategory::Other && self.details.is_none() {
+            return Err(error!(
+                VALIDATION,
+                "details", "Reports in the OTHER category need details"
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl ReportResolve {
+    pub fn validate(&self) -> Result<(), ErrorResponse> {
+        if self.status == ReportStatus::Open {
+            return Err(error!(
+                VALIDATION,
+                "status", "Reports can only be resolved as ACTIONED or DISMISSED"
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Report {
+    /// Report a user, message or file to the instance's moderators.
+    ///
+    /// Users can only have one open report about the same target at once.
+    pub async fn create(
+        mut report: ReportCreate,
+        reporter_id: u64,
+        id_generator: &mut IdGenerator,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Self, ErrorResponse> {
+        report.ensure_valid();
+        report.validate()?;
+        ensure_reportable(report.target_type, report.target_id, reporter_id, &mut *db).await?;
+        let id = id_generator.generate();
+        let created = sqlx::query!(
+            "
+INSERT INTO reports(id, reporter_id, target_type, target_id, category, details)
+VALUES($1, $2, $3, $4, $5, $6)
+ON CONFLICT (reporter_id, target_type, target_id) WHERE status = 'OPEN'
+DO NOTHING
+            ",
+            id as i64,
+            reporter_id as i64,
+            report.target_type as ReportTargetType,
+            report.target_id as i64,
+            report.category as ReportCategory,
+            report.details,
+        )
+        .execute(db)
+        .await
+        .map(|r| r.rows_affected() > 0)
+        .map_err(|err| {
+            log::error!("Couldn't store report in database: {}", err);
+            error!(SERVER, "Failed to create report")
+        })?;
+        if !created {
+            return Err(error!(CONFLICT, "report"));
+        }
+        Ok(Self {
+            id,
+            reporter_id,
+            target_type: report.target_type,
+            target_id: report.target_id,
+            category: report.category,
+            details: report.details,
+            status: ReportStatus::Open,
+            resolved_by: None,
+        })
+    }
+
+    /// Get a page of the instance's reports, ordered from newest to oldest and optionally
+    /// filtered by their status.
+    pub async fn get_page(
+        status: Option<ReportStatus>,
+        before: Option<u64>,
+        limit: Option<u32>,
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Vec<Self>, ErrorResponse> {
+        let limit = limit.unwrap_or(REPORTS_DEFAULT_LIMIT);
+        if limit == 0 || limit > REPORTS_MAX_LIMIT {
+            return Err(error!(
+                VALIDATION,
+                "limit",
+                format!(
+                    "The report limit must be between 1 and {}",
+                    REPORTS_MAX_LIMIT
+                )
+            ));
+        }
+        Permissions::require_instance(user_id, Permissions::MANAGE_REPORTS, &mut *db).await?;
+        sqlx::query!(
+            r#"
+SELECT id, reporter_id, target_type as "target_type: ReportTargetType", target_id,
+  category as "category: ReportCategory", details, status as "status: ReportStatus", resolved_by
+FROM reports
+WHERE id < $1
+AND ($2::report_status IS NULL OR status = $2)
+ORDER BY id DESC
+LIMIT $3
+            "#,
+            before.map(|b| b as i64).unwrap_or(i64::MAX),
+            status as Option<ReportStatus>,
+            limit as i64,
+        )
+        .fetch_all(db)
+        .await
+        .map(|rows| {
+            rows.into_iter()
+                .map(|r| Self {
+                    id: r.id as u64,
+                    reporter_id: r.reporter_id as u64,
+                    target_type: r.target_type,
+                    target_id: r.target_id as u64,
+                    category: r.category,
+                    details: r.details,
+                    status: r.status,
+                    resolved_by: r.resolved_by.map(|r| r as u64),
+                })
+                .collect()
+        })
+        .map_err(|err| {
+            log::error!("Couldn't fetch reports: {}", err);
+            error!(SERVER, "Failed to fetch reports")
+        })
+    }
+
+    /// Resolve an open report, marking it as actioned or dismissed.
+    pub async fn resolve(
+        id: u64,
+        resolve: ReportResolve,
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Self, ErrorResponse> {
+        resolve.validate()?;
+        Permissions::require_instance(user_id, Permissions::MANAGE_REPORTS, &mut *db).await?;
+        let report = sqlx::query!(
+            r#"
+UPDATE reports
+SET status = $1, resolved_by = $2
+WHERE id = $3
+AND status = 'OPEN'
+RETURNING reporter_id, target_type as "target_type: ReportTargetType", target_id,
+  category as "category: ReportCategory", details
+            "#,
+            resolve.status as ReportStatus,
+            user_id as i64,
+            id as i64,
+        )
+        .fetch_optional(&mut *db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't resolve report: {}", err);
+            error!(SERVER, "Failed to resolve report")
+        })?;
+        match report {
+            Some(report) => Ok(Self {
+                id,
+                reporter_id: report.reporter_id as u64,
+                target_type: report.target_type,
+                target_id: report.target_id as u64,
+                category: report.category,
+                details: report.details,
+                status: resolve.status,
+                resolved_by: Some(user_id),
+            }),
+            None => {
+                let exists = sqlx::query!(
+                    r#"
+SELECT EXISTS(
+  SELECT 1
+  FROM reports
+  WHERE id = $1
+) AS "exists!"
+                    "#,
+                    id as i64,
+                )
+                .fetch_one(db)
+                .await
+                .map(|r| r.exists)
+                .map_err(|err| {
+                    log::error!("Couldn't check report existence: {}", err);
+                    error!(SERVER, "Failed to resolve report")
+                })?;
+                match exists {
+                    true => Err(error!(
+                        VALIDATION,
+                        "status", "This report has already been resolved"
+                    )),
+                    false => Err(error!(NOT_FOUND)),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::{
+        ReportCategory, ReportCreate, ReportResolve, ReportStatus, ReportTargetType,
+    };
+
+    #[test]
+    fn validate_reports() {
+        let mut report = ReportCreate {
+            target_type: ReportTargetType::Message,
+            target_id: 2391730577409,
+            category: ReportCategory::Other,
+            details: Some("   ".to_string()),
+        };
+        report.ensure_valid();
+        assert_eq!(report.details, None);
+        assert!(report.validate().is_err());
+        report.details = Some("They keep telling me to stomp on lanternflies".to_string());
+        assert!(report.validate().is_ok());
+        report.details = Some("a".repeat(2001));
+        assert!(report.validate().is_err());
+        report.category = ReportCategory::Spam;
+        report.details = None;
+        assert!(report.validate().is_ok());
+
+        assert!(ReportResolve {
+            status: ReportStatus::Open
+        }
+        .validate()
+        .is_err());
+        assert!(ReportResolve {
+            status: ReportStatus::Dismissed
+        }
+        .validate()
+        .is_ok());
+    }
+}
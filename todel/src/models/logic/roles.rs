@@ -0,0 +1,485 @@
+use sqlx::{
+    pool::PoolConnection,
+    postgres::{PgConnection, PgRow},
+    Connection, Postgres, Row,
+};
+
+use super::permissions::MemberPermissions;
+use crate::{
+    ids::IdGenerator,
+    models::{Community, ErrorResponse, Permissions, Role, RoleCreate, UpdateRole},
+};
+
+fn validate_name(name: &str) -> Result<(), ErrorResponse> {
+    if name.is_empty() || name.len() > 32 {
+        Err(error!(
+            VALIDATION,
+            "name", "The role's name must be between 1 and 32 characters in length"
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Get the IDs of a member's roles, excluding the community's default role.
+async fn get_member_roles(
+    community_id: u64,
+    user_id: u64,
+    db: &mut PoolConnection<Postgres>,
+) -> Result<Vec<u64>, ErrorResponse> {
+    sqlx::query!(
+        "
+SELECT m.role_id
+FROM member_roles m
+JOIN roles r
+ON m.role_id = r.id
+WHERE m.community_id = $1
+AND m.user_id = $2
+ORDER BY r.position DESC
+        ",
+        community_id as i64,
+        user_id as i64,
+    )
+    .fetch_all(db)
+    .await
+    .map(|rows| rows.into_iter().map(|r| r.role_id as u64).collect())
+    .map_err(|err| {
+        log::error!("Couldn't fetch member roles: {}", err);
+        error!(SERVER, "Failed to fetch member roles")
+    })
+}
+
+/// Lock a community's row so that its roles can be reordered without racing other changes to
+/// their positions.
+async fn lock_community(community_id: u64, db: &mut PgConnection) -> Result<(), ErrorResponse> {
+    sqlx::query!(
+        "
+SELECT id
+FROM communities
+WHERE id = $1
+FOR UPDATE
+        ",
+        community_id as i64
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(|err| {
+        log::error!("Couldn't lock community: {}", err);
+        error!(SERVER, "Failed to reorder roles")
+    })?
+    .map(|_| ())
+    .ok_or_else(|| error!(NOT_FOUND))
+}
+
+impl RoleCreate {
+    pub fn ensure_valid(&mut self) {
+        self.name = self.name.trim().to_string();
+    }
+
+    pub fn validate(&self) -> Result<(), ErrorResponse> {
+        validate_name(&self.name)
+    }
+}
+
+impl UpdateRole {
+    pub fn ensure_valid(&mut self) {
+        self.name = self.name.as_ref().map(|n| n.trim().to_string());
+    }
+
+    pub fn validate(&self) -> Result<(), ErrorResponse> {
+        if self.name.is_none() && self.permissions.is_none() && self.position.is_none() {
+            return Err(error!(VALIDATION, "body", "At least one field must exist"));
+        }
+        if let Some(name) = &self.name {
+            validate_name(name)?;
+        }
+        if self.position == Some(0) {
+            return Err(error!(
+                VALIDATION,
+                "position", "Only the default role can be at position 0"
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Role {
+    fn from_row(row: PgRow) -> Self {
+        Self {
+            id: row.get::<i64, _>("id") as u64,
+            community_id: row.get::<i64, _>("community_id") as u64,
+            name: row.get("name"),
+            permissions: row.get::<i64, _>("permissions") as u64,
+            position: row.get::<i32, _>("position") as u32,
+        }
+    }
+
+    /// Create a community's default role, which shares the community's ID.
+    pub(crate) async fn create_default(
+        community_id: u64,
+        db: &mut PgConnection,
+    ) -> Result<(), ErrorResponse> {
+        sqlx::query!(
+            "
+INSERT INTO roles(id, community_id, name, permissions, position)
+VALUES($1, $1, 'everyone', $2, 0)
+            ",
+            community_id as i64,
+            Permissions::DEFAULT.bits() as i64,
+        )
+        .execute(db)
+        .await
+        .map_err(|err| {
+            log::error!("Failed to create community default role: {}", err);
+            error!(SERVER, "Could not create community")
+        })?;
+        Ok(())
+    }
+
+    /// Create a role right above the community's default role.
+    pub async fn create(
+        community_id: u64,
+        mut role: RoleCreate,
+        user_id: u64,
+        id_generator: &mut IdGenerator,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Self, ErrorResponse> {
+        role.ensure_valid();
+        role.validate()?;
+        let mut tx = db.begin().await.map_err(|err| {
+            log::error!("Couldn't start role creation transaction: {}", err);
+            error!(SERVER, "Could not create role")
+        })?;
+        lock_community(community_id, &mut tx).await?;
+        MemberPermissions::get(community_id, user_id, &mut tx)
+            .await?
+            .ensure_grantable(role.permissions)?;
+        sqlx::query!(
+            "
+UPDATE roles
+SET position = position + 1
+WHERE community_id = $1
+AND position > 0
+            ",
+            community_id as i64,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't shift community roles: {}", err);
+            error!(SERVER, "Could not create role")
+        })?;
+        let id = id_generator.generate();
+        sqlx::query!(
+            "
+INSERT INTO roles(id, community_id, name, permissions, position)
+VALUES($1, $2, $3, $4, 1)
+            ",
+            id as i64,
+            community_id as i64,
+            role.name,
+            role.permissions as i64,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|err| {
+            log::error!("Failed to store role in database: {}", err);
+            error!(SERVER, "Could not create role")
+        })?;
+        tx.commit().await.map_err(|err| {
+            log::error!("Couldn't commit role creation: {}", err);
+            error!(SERVER, "Could not create role")
+        })?;
+        Ok(Self {
+            id,
+            community_id,
+            name: role.name,
+            permissions: role.permissions,
+            position: 1,
+        })
+    }
+
+    async fn get(id: u64, community_id: u64, db: &mut PgConnection) -> Result<Self, ErrorResponse> {
+        sqlx::query(
+            "
+SELECT *
+FROM roles
+WHERE id = $1
+AND community_id = $2
+            ",
+        )
+        .bind(id as i64)
+        .bind(community_id as i64)
+        .fetch_optional(db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't fetch role from database: {}", err);
+            error!(SERVER, "Failed to fetch role")
+        })?
+        .map(Self::from_row)
+        .ok_or_else(|| error!(NOT_FOUND))
+    }
+
+    /// Get all of a community's roles, ordered by position.
+    pub async fn get_community_roles(
+        community_id: u64,
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Vec<Self>, ErrorResponse> {
+        Permissions::require_community(community_id, user_id, Permissions::empty(), &mut *db)
+            .await?;
+        sqlx::query(
+            "
+SELECT *
+FROM roles
+WHERE community_id = $1
+ORDER BY position
+            ",
+        )
+        .bind(community_id as i64)
+        .fetch_all(db)
+        .await
+        .map(|rows| rows.into_iter().map(Self::from_row).collect())
+        .map_err(|err| {
+            log::error!("Couldn't fetch community roles: {}", err);
+            error!(SERVER, "Failed to fetch roles")
+        })
+    }
+
+    /// Update a role below your highest role.
+    pub async fn update(
+        id: u64,
+        community_id: u64,
+        user_id: u64,
+        mut update: UpdateRole,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Self, ErrorResponse> {
+        update.ensure_valid();
+        update.validate()?;
+        let mut tx = db.begin().await.map_err(|err| {
+            log::error!("Couldn't start role update transaction: {}", err);
+            error!(SERVER, "Failed to update role")
+        })?;
+        lock_community(community_id, &mut tx).await?;
+        let mut role = Self::get(id, community_id, &mut tx).await?;
+        let member = MemberPermissions::get(community_id, user_id, &mut tx).await?;
+        member.ensure_above(role.position)?;
+        if let Some(permissions) = update.permissions {
+            member.ensure_grantable(permissions)?;
+        }
+        if let Some(position) = update.position {
+            if role.id == community_id {
+                return Err(error!(
+                    VALIDATION,
+                    "position", "The default role can't be moved"
+                ));
+            }
+            member.ensure_above(position)?;
+            let max_position = sqlx::query!(
+                r#"
+SELECT MAX(position) AS "max!"
+FROM roles
+WHERE community_id = $1
+                "#,
+                community_id as i64,
+            )
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|err| {
+                log::error!("Couldn't fetch community roles: {}", err);
+                error!(SERVER, "Failed to update role")
+            })?
+            .max as u32;
+            let position = position.min(max_position);
+            // Shift the roles between the role's old and new positions to make room for it.
+            let (shift, low, high) = if position > role.position {
+                (-1, role.position + 1, position)
+            } else {
+                (1, position, role.position - 1)
+            };
+            sqlx::query!(
+                "
+UPDATE roles
+SET position = position + $1
+WHERE community_id = $2
+AND position BETWEEN $3 AND $4
+                ",
+                shift,
+                community_id as i64,
+                low as i32,
+                high as i32,
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(|err| {
+                log::error!("Couldn't shift community roles: {}", err);
+                error!(SERVER, "Failed to update role")
+            })?;
+            role.position = position;
+        }
+        if let Some(name) = update.name {
+            role.name = name;
+        }
+        if let Some(permissions) = update.permissions {
+            role.permissions = permissions;
+        }
+        sqlx::query!(
+            "
+UPDATE roles
+SET name = $1, permissions = $2, position = $3
+WHERE id = $4
+            ",
+            role.name,
+            role.permissions as i64,
+            role.position as i32,
+            id as i64,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't update role: {}", err);
+            error!(SERVER, "Failed to update role")
+        })?;
+        tx.commit().await.map_err(|err| {
+            log::error!("Couldn't commit role update: {}", err);
+            error!(SERVER, "Failed to update role")
+        })?;
+        Ok(role)
+    }
+
+    /// Delete a role below your highest role. The default role can't be deleted.
+    pub async fn delete(
+        id: u64,
+        community_id: u64,
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<(), ErrorResponse> {
+        if id == community_id {
+            return Err(error!(
+                VALIDATION,
+                "role", "The default role can't be deleted"
+            ));
+        }
+        let mut tx = db.begin().await.map_err(|err| {
+            log::error!("Couldn't start role deletion transaction: {}", err);
+            error!(SERVER, "Failed to delete role")
+        })?;
+        lock_community(community_id, &mut tx).await?;
+        let role = Self::get(id, community_id, &mut tx).await?;
+        MemberPermissions::get(community_id, user_id, &mut tx)
+            .await?
+            .ensure_above(role.position)?;
+        sqlx::query!(
+            "
+DELETE FROM roles
+WHERE id = $1
+            ",
+            id as i64
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't delete role: {}", err);
+            error!(SERVER, "Failed to delete role")
+        })?;
+        sqlx::query!(
+            "
+UPDATE roles
+SET position = position - 1
+WHERE community_id = $1
+AND position > $2
+            ",
+            community_id as i64,
+            role.position as i32,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't shift community roles: {}", err);
+            error!(SERVER, "Failed to delete role")
+        })?;
+        tx.commit().await.map_err(|err| {
+            log::error!("Couldn't commit role deletion: {}", err);
+            error!(SERVER, "Failed to delete role")
+        })
+    }
+
+    /// Give a role below your highest role to a member.
+    ///
+    /// Returns the IDs of the member's roles.
+    pub async fn add_member(
+        id: u64,
+        community_id: u64,
+        member_id: u64,
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Vec<u64>, ErrorResponse> {
+        if id == community_id {
+            return Err(error!(
+                VALIDATION,
+                "role", "The default role can't be given to members"
+            ));
+        }
+        let role = Self::get(id, community_id, &mut *db).await?;
+        MemberPermissions::get(community_id, user_id, &mut *db)
+            .await?
+            .ensure_above(role.position)?;
+        if !Community::is_member(community_id, member_id, &mut *db).await? {
+            return Err(error!(NOT_FOUND));
+        }
+        sqlx::query!(
+            "
+INSERT INTO member_roles(community_id, user_id, role_id)
+VALUES($1, $2, $3)
+ON CONFLICT DO NOTHING
+            ",
+            community_id as i64,
+            member_id as i64,
+            id as i64,
+        )
+        .execute(&mut *db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't add member role: {}", err);
+            error!(SERVER, "Failed to add member role")
+        })?;
+        get_member_roles(community_id, member_id, db).await
+    }
+
+    /// Take a role below your highest role from a member.
+    ///
+    /// Returns the IDs of the member's remaining roles.
+    pub async fn remove_member(
+        id: u64,
+        community_id: u64,
+        member_id: u64,
+        user_id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<Vec<u64>, ErrorResponse> {
+        let role = Self::get(id, community_id, &mut *db).await?;
+        MemberPermissions::get(community_id, user_id, &mut *db)
+            .await?
+            .ensure_above(role.position)?;
+        let result = sqlx::query!(
+            "
+DELETE FROM member_roles
+WHERE community_id = $1
+AND user_id = $2
+AND role_id = $3
+            ",
+            community_id as i64,
+            member_id as i64,
+            id as i64,
+        )
+        .execute(&mut *db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't remove member role: {}", err);
+            error!(SERVER, "Failed to remove member role")
+        })?;
+        if result.rows_affected() == 0 {
+            return Err(error!(NOT_FOUND));
+        }
+        get_member_roles(community_id, member_id, db).await
+    }
+}
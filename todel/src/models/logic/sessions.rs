@@ -2,17 +2,64 @@ use std::net::IpAddr;
 
 use argon2::{PasswordHash, PasswordVerifier};
 use jwt::{SignWithKey, VerifyWithKey};
+use redis::AsyncCommands;
 use serde::{Deserialize, Serialize};
 use sqlx::{pool::PoolConnection, types::ipnetwork::IpNetwork, Postgres};
 
 use crate::{
+    conf::OprishConf,
     ids::IdGenerator,
     models::{
         ErrorResponse, PasswordDeleteCredentials, Session, SessionCreate, SessionCreated, User,
     },
+    Conf,
 };
 
-use super::Secret;
+use super::{now, Secret};
+
+/// The minimum amount of seconds between two updates of a session's `last_used_at`.
+const LAST_USED_PRECISION: u64 = 60;
+
+/// The redis channel revoked sessions are published on.
+pub const SESSION_REVOCATION_CHANNEL: &str = "eludris-session-revocations";
+
+/// An internal event telling pandemonium to close the gateway connections of revoked sessions.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionRevocation {
+    /// The ID of the user the sessions belonged to.
+    pub user_id: u64,
+    /// The IDs of the revoked sessions.
+    pub session_ids: Vec<u64>,
+    /// The reason the gateway connections get closed with.
+    pub reason: String,
+}
+
+/// Tell pandemonium to close the gateway connections of a user's sessions.
+async fn publish_revocation<C: AsyncCommands>(
+    user_id: u64,
+    session_ids: Vec<u64>,
+    reason: &str,
+    cache: &mut C,
+) -> Result<(), ErrorResponse> {
+    if session_ids.is_empty() {
+        return Ok(());
+    }
+    cache
+        .publish::<_, _, ()>(
+            SESSION_REVOCATION_CHANNEL,
+            serde_json::to_string(&SessionRevocation {
+                user_id,
+                session_ids,
+                reason: reason.to_string(),
+            })
+            .expect("Couldn't serialize session revocation"),
+        )
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't publish session revocation: {}", err);
+            error!(SERVER, "Failed to revoke sessions")
+        })
+}
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct SessionTokenClaims {
@@ -20,6 +67,13 @@ pub struct SessionTokenClaims {
     session_id: u64,
 }
 
+/// Whether a session expired, either by going unused for too long or by outliving the instance's
+/// maximum session lifetime.
+fn is_expired(created_at: u64, last_used_at: u64, conf: &OprishConf, now: u64) -> bool {
+    (conf.session_idle_timeout != 0 && now.saturating_sub(last_used_at) > conf.session_idle_timeout)
+        || (conf.session_lifetime != 0 && now.saturating_sub(created_at) > conf.session_lifetime)
+}
+
 impl SessionCreate {
     pub fn ensure_valid(&mut self) {
         self.platform = self.platform.to_lowercase();
@@ -39,7 +93,7 @@ impl Session {
         session.ensure_valid();
         let user = sqlx::query!(
             "
-SELECT id, password
+SELECT id, password, suspended_until
 FROM users
 WHERE (username = $1
 OR email = $1)
@@ -63,17 +117,24 @@ AND is_deleted = FALSE
                 })?,
             )
             .map_err(|_| error!(UNAUTHORIZED))?;
+        if let Some(suspended_until) = user.suspended_until.filter(|s| *s as u64 > now()) {
+            return Err(User::suspension_error(suspended_until as u64));
+        }
+        User::validate_two_factor(user.id as u64, session.two_factor_code.as_deref(), &mut *db)
+            .await?;
         let id = id_generator.generate();
+        let created_at = now();
         sqlx::query!(
             "
-INSERT INTO sessions(id, user_id, platform, client, ip)
-VALUES($1, $2, $3, $4, $5)
+INSERT INTO sessions(id, user_id, platform, client, ip, created_at, last_used_at)
+VALUES($1, $2, $3, $4, $5, $6, $6)
             ",
             id as i64,
             user.id as i64,
             session.platform,
             session.client,
             IpNetwork::from(ip),
+            created_at as i64,
         )
         .execute(db)
         .await
@@ -97,13 +158,18 @@ VALUES($1, $2, $3, $4, $5)
                 platform: session.platform,
                 client: session.client,
                 ip,
+                created_at,
+                last_used_at: created_at,
             },
         })
     }
 
+    /// Validate a session token, expiring the session if it went unused for too long or is older
+    /// than the instance's maximum session lifetime.
     pub async fn validate_token(
         token: &str,
         secret: &Secret,
+        conf: &Conf,
         db: &mut PoolConnection<Postgres>,
     ) -> Result<Self, ErrorResponse> {
         let claims: SessionTokenClaims = token
@@ -111,7 +177,7 @@ VALUES($1, $2, $3, $4, $5)
             .map_err(|_| error!(UNAUTHORIZED))?;
         let session = sqlx::query!(
             "
-SELECT s.id, s.user_id, s.platform, s.client, s.ip
+SELECT s.id, s.user_id, s.platform, s.client, s.ip, s.created_at, s.last_used_at, u.suspended_until
 FROM sessions s
 LEFT JOIN users u
 ON s.user_id = u.id
@@ -122,21 +188,62 @@ AND u.is_deleted = FALSE
             claims.session_id as i64,
             claims.user_id as i64
         )
-        .fetch_optional(db)
+        .fetch_optional(&mut *db)
         .await
         .map_err(|err| {
             log::error!("Could not fetch the user's session: {}", err);
             error!(SERVER, "Failed to fetch the user's session")
         })?
-        .map(|s| Self {
-            id: s.id as u64,
-            user_id: s.user_id as u64,
-            platform: s.platform,
-            client: s.client,
-            ip: s.ip.ip(),
-        })
         .ok_or_else(|| error!(UNAUTHORIZED))?; // no such session exists
-        Ok(session)
+        if let Some(suspended_until) = session.suspended_until.filter(|s| *s as u64 > now()) {
+            return Err(User::suspension_error(suspended_until as u64));
+        }
+        let now = now();
+        let created_at = session.created_at as u64;
+        let mut last_used_at = session.last_used_at as u64;
+        if is_expired(created_at, last_used_at, &conf.oprish, now) {
+            sqlx::query!(
+                "
+DELETE FROM sessions
+WHERE id = $1
+                ",
+                session.id,
+            )
+            .execute(db)
+            .await
+            .map_err(|err| {
+                log::error!("Couldn't delete expired session: {}", err);
+                error!(SERVER, "Failed to fetch the user's session")
+            })?;
+            return Err(error!(UNAUTHORIZED));
+        }
+        if now.saturating_sub(last_used_at) >= LAST_USED_PRECISION {
+            sqlx::query!(
+                "
+UPDATE sessions
+SET last_used_at = $1
+WHERE id = $2
+                ",
+                now as i64,
+                session.id,
+            )
+            .execute(db)
+            .await
+            .map_err(|err| {
+                log::error!("Couldn't update session last use: {}", err);
+                error!(SERVER, "Failed to fetch the user's session")
+            })?;
+            last_used_at = now;
+        }
+        Ok(Self {
+            id: session.id as u64,
+            user_id: session.user_id as u64,
+            platform: session.platform,
+            client: session.client,
+            ip: session.ip.ip(),
+            created_at,
+            last_used_at,
+        })
     }
 
     pub async fn get_sessions(
@@ -164,6 +271,8 @@ AND u.is_deleted = FALSE
             platform: s.platform,
             client: s.client,
             ip: s.ip.ip(),
+            created_at: s.created_at as u64,
+            last_used_at: s.last_used_at as u64,
         })
         .collect())
     }
@@ -175,7 +284,8 @@ AND u.is_deleted = FALSE
         verifier: &V,
         db: &mut PoolConnection<Postgres>,
     ) -> Result<(), ErrorResponse> {
-        User::validate_password(user_id, &delete.password, verifier, db).await?;
+        User::validate_password(user_id, &delete.password, verifier, &mut *db).await?;
+        User::validate_two_factor(user_id, delete.two_factor_code.as_deref(), &mut *db).await?;
         sqlx::query!(
             "
 DELETE FROM sessions
@@ -200,4 +310,125 @@ AND user_id = $2 -- This should be unnecessary but eh
         })??;
         Ok(())
     }
+
+    /// Delete all of a user's sessions except for the current one, logging them out everywhere
+    /// else.
+    pub async fn delete_others<V: PasswordVerifier, C: AsyncCommands>(
+        id: u64,
+        user_id: u64,
+        delete: PasswordDeleteCredentials,
+        verifier: &V,
+        db: &mut PoolConnection<Postgres>,
+        cache: &mut C,
+    ) -> Result<(), ErrorResponse> {
+        User::validate_password(user_id, &delete.password, verifier, &mut *db).await?;
+        User::validate_two_factor(user_id, delete.two_factor_code.as_deref(), &mut *db).await?;
+        Self::revoke(user_id, Some(id), "Session logged out", db, cache).await?;
+        Ok(())
+    }
+
+    /// Revoke all of a user's sessions except for `except`, telling pandemonium to close their
+    /// gateway connections.
+    ///
+    /// Returns the amount of revoked sessions.
+    pub(crate) async fn revoke<C: AsyncCommands>(
+        user_id: u64,
+        except: Option<u64>,
+        reason: &str,
+        db: &mut PoolConnection<Postgres>,
+        cache: &mut C,
+    ) -> Result<u64, ErrorResponse> {
+        let session_ids: Vec<u64> = sqlx::query!(
+            "
+DELETE FROM sessions
+WHERE user_id = $1
+AND ($2::BIGINT IS NULL OR id != $2)
+RETURNING id
+            ",
+            user_id as i64,
+            except.map(|e| e as i64),
+        )
+        .fetch_all(db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't revoke user sessions: {}", err);
+            error!(SERVER, "Failed to revoke sessions")
+        })?
+        .into_iter()
+        .map(|s| s.id as u64)
+        .collect();
+        let revoked = session_ids.len() as u64;
+        publish_revocation(user_id, session_ids, reason, cache).await?;
+        Ok(revoked)
+    }
+
+    /// Close the gateway connections of all of a user's sessions without deleting them, since
+    /// they can't be used again until the user's suspension ends anyway.
+    pub(crate) async fn disconnect_all<C: AsyncCommands>(
+        user_id: u64,
+        reason: &str,
+        db: &mut PoolConnection<Postgres>,
+        cache: &mut C,
+    ) -> Result<(), ErrorResponse> {
+        let session_ids = sqlx::query!(
+            "
+SELECT id
+FROM sessions
+WHERE user_id = $1
+            ",
+            user_id as i64,
+        )
+        .fetch_all(db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't fetch user sessions: {}", err);
+            error!(SERVER, "Failed to disconnect sessions")
+        })?
+        .into_iter()
+        .map(|s| s.id as u64)
+        .collect();
+        publish_revocation(user_id, session_ids, reason, cache).await
+    }
+
+    pub async fn clean_up_expired(
+        conf: &Conf,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<(), sqlx::Error> {
+        let now = now() as i64;
+        sqlx::query!(
+            "
+DELETE FROM sessions
+WHERE ($1 != 0 AND $2 - last_used_at > $1)
+OR ($3 != 0 AND $2 - created_at > $3)
+            ",
+            conf.oprish.session_idle_timeout as i64,
+            now,
+            conf.oprish.session_lifetime as i64,
+        )
+        .execute(db)
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::conf::OprishConf;
+
+    #[test]
+    fn is_expired() {
+        let mut conf = OprishConf {
+            session_idle_timeout: 3600,
+            session_lifetime: 0,
+            ..Default::default()
+        };
+        assert!(!super::is_expired(0, 1000, &conf, 4600));
+        assert!(super::is_expired(0, 1000, &conf, 4601));
+        conf.session_lifetime = 4000;
+        assert!(!super::is_expired(0, 4000, &conf, 4000));
+        assert!(super::is_expired(0, 4000, &conf, 4001));
+        conf.session_idle_timeout = 0;
+        conf.session_lifetime = 0;
+        assert!(!super::is_expired(0, 0, &conf, u64::MAX));
+    }
 }
@@ -0,0 +1,412 @@
+use argon2::{password_hash::rand_core::CryptoRngCore, PasswordVerifier};
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use redis::AsyncCommands;
+use sha1::Sha1;
+use sqlx::{pool::PoolConnection, Postgres};
+
+use super::now;
+use crate::{
+    models::{
+        ErrorResponse, PasswordDeleteCredentials, TwoFactorConfirm, TwoFactorEnrollment,
+        TwoFactorRecoveryCodes, User,
+    },
+    Conf,
+};
+
+/// The amount of seconds a TOTP code is valid for.
+const TOTP_PERIOD: u64 = 30;
+/// The amount of digits in a TOTP code.
+const TOTP_DIGITS: u32 = 6;
+/// The amount of time steps around the current one whose codes are still accepted to account for
+/// clock drift.
+const TOTP_SKEW: u64 = 1;
+/// The length of a TOTP secret in bytes, which is 16 characters once base32-encoded.
+const TOTP_SECRET_LENGTH: usize = 10;
+/// The amount of seconds a user has to confirm enabling two-factor authentication.
+const ENROLLMENT_EXPIRY: usize = 600;
+/// The amount of recovery codes a user gets when enabling two-factor authentication.
+pub const RECOVERY_CODE_COUNT: usize = 10;
+/// The characters recovery codes are made of.
+const RECOVERY_CODE_CHARSET: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+
+/// Generate the TOTP code of a time step.
+fn generate_totp(secret: &[u8], step: u64) -> u32 {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("HMAC can take keys of any size");
+    mac.update(&step.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+    let offset = (hash[hash.len() - 1] & 0xf) as usize;
+    let code = u32::from_be_bytes([
+        hash[offset],
+        hash[offset + 1],
+        hash[offset + 2],
+        hash[offset + 3],
+    ]) & 0x7fff_ffff;
+    code % 10_u32.pow(TOTP_DIGITS)
+}
+
+/// Check a TOTP code against a base32-encoded secret, returning the time step it belongs to.
+///
+/// Codes from steps at or before `last_step` are rejected so that every code can only be used
+/// once.
+fn verify_totp(secret: &str, code: &str, timestamp: u64, last_step: Option<u64>) -> Option<u64> {
+    if code.len() != TOTP_DIGITS as usize || !code.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let code: u32 = code.parse().ok()?;
+    let secret = BASE32_NOPAD.decode(secret.as_bytes()).ok()?;
+    let current = timestamp / TOTP_PERIOD;
+    (current.saturating_sub(TOTP_SKEW)..=current + TOTP_SKEW)
+        .filter(|step| !matches!(last_step, Some(last) if *step <= last))
+        .find(|step| generate_totp(&secret, *step) == code)
+}
+
+fn generate_recovery_code<R: CryptoRngCore>(rng: &mut R) -> String {
+    let code: String = (0..10)
+        .map(|_| RECOVERY_CODE_CHARSET[rng.gen_range(0..RECOVERY_CODE_CHARSET.len())] as char)
+        .collect();
+    format!("{}-{}", &code[..5], &code[5..])
+}
+
+/// Hash a recovery code, ignoring its casing and dashes.
+fn hash_recovery_code(code: &str) -> String {
+    sha256::digest(
+        code.chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_lowercase(),
+    )
+}
+
+fn get_otpauth_uri(issuer: &str, username: &str, secret: &str) -> String {
+    let issuer = url::form_urlencoded::byte_serialize(issuer.as_bytes())
+        .collect::<String>()
+        .replace('+', "%20");
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        issuer, username, secret, issuer, TOTP_DIGITS, TOTP_PERIOD
+    )
+}
+
+impl User {
+    /// Check a user's two-factor authentication code, which can either be a TOTP code or one of
+    /// their recovery codes. Recovery codes get used up.
+    ///
+    /// Returns whether the user has two-factor authentication enabled, users without it always
+    /// pass this check.
+    pub(crate) async fn validate_two_factor(
+        id: u64,
+        code: Option<&str>,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<bool, ErrorResponse> {
+        let user = sqlx::query!(
+            "
+SELECT two_factor_auth, two_factor_last_step
+FROM users
+WHERE id = $1
+            ",
+            id as i64
+        )
+        .fetch_one(&mut *db)
+        .await
+        .map_err(|err| {
+            log::error!(
+                "Couldn't fetch user two-factor authentication data: {}",
+                err
+            );
+            error!(SERVER, "Failed to validate two-factor authentication code")
+        })?;
+        let secret = match user.two_factor_auth {
+            Some(secret) => secret,
+            None => return Ok(false),
+        };
+        let code = code
+            .map(|c| c.trim())
+            .filter(|c| !c.is_empty())
+            .ok_or_else(|| {
+                error!(
+                    VALIDATION,
+                    "two_factor_code", "A two-factor authentication code is required"
+                )
+            })?;
+        let valid = match verify_totp(
+            &secret,
+            code,
+            now(),
+            user.two_factor_last_step.map(|s| s as u64),
+        ) {
+            // The step is only stored if it's newer to not accept a code used at the same time.
+            Some(step) => sqlx::query!(
+                "
+UPDATE users
+SET two_factor_last_step = $1
+WHERE id = $2
+AND (two_factor_last_step IS NULL OR two_factor_last_step < $1)
+                ",
+                step as i64,
+                id as i64,
+            )
+            .execute(db)
+            .await
+            .map(|r| r.rows_affected() > 0),
+            None => sqlx::query!(
+                "
+DELETE FROM recovery_codes
+WHERE user_id = $1
+AND code_hash = $2
+                ",
+                id as i64,
+                hash_recovery_code(code),
+            )
+            .execute(db)
+            .await
+            .map(|r| r.rows_affected() > 0),
+        }
+        .map_err(|err| {
+            log::error!("Couldn't use two-factor authentication code: {}", err);
+            error!(SERVER, "Failed to validate two-factor authentication code")
+        })?;
+        if !valid {
+            return Err(error!(UNAUTHORIZED));
+        }
+        Ok(true)
+    }
+
+    /// Start enabling two-factor authentication, generating a TOTP secret which has to be
+    /// confirmed using [`User::confirm_two_factor`].
+    pub async fn enroll_two_factor<R: CryptoRngCore, C: AsyncCommands>(
+        id: u64,
+        conf: &Conf,
+        rng: &mut R,
+        db: &mut PoolConnection<Postgres>,
+        cache: &mut C,
+    ) -> Result<TwoFactorEnrollment, ErrorResponse> {
+        let user = sqlx::query!(
+            "
+SELECT username, two_factor_auth
+FROM users
+WHERE id = $1
+AND is_deleted = FALSE
+            ",
+            id as i64
+        )
+        .fetch_one(db)
+        .await
+        .map_err(|err| {
+            log::error!(
+                "Couldn't fetch user two-factor authentication data: {}",
+                err
+            );
+            error!(SERVER, "Failed to enable two-factor authentication")
+        })?;
+        if user.two_factor_auth.is_some() {
+            return Err(error!(
+                VALIDATION,
+                "two_factor_auth", "Two-factor authentication is already enabled"
+            ));
+        }
+        let mut secret = [0; TOTP_SECRET_LENGTH];
+        rng.fill_bytes(&mut secret);
+        let secret = BASE32_NOPAD.encode(&secret);
+        cache
+            .set_ex::<_, _, ()>(
+                format!("two-factor-enrollment:{}", id),
+                &secret,
+                ENROLLMENT_EXPIRY,
+            )
+            .await
+            .map_err(|err| {
+                log::error!("Failed to store TOTP secret in cache: {}", err);
+                error!(SERVER, "Failed to enable two-factor authentication")
+            })?;
+        Ok(TwoFactorEnrollment {
+            uri: get_otpauth_uri(&conf.instance_name, &user.username, &secret),
+            secret,
+        })
+    }
+
+    /// Finish enabling two-factor authentication with a code generated from the secret returned
+    /// by [`User::enroll_two_factor`].
+    ///
+    /// Returns the user's recovery codes, which are only stored hashed.
+    pub async fn confirm_two_factor<V: PasswordVerifier, R: CryptoRngCore, C: AsyncCommands>(
+        id: u64,
+        confirm: TwoFactorConfirm,
+        verifier: &V,
+        rng: &mut R,
+        db: &mut PoolConnection<Postgres>,
+        cache: &mut C,
+    ) -> Result<TwoFactorRecoveryCodes, ErrorResponse> {
+        Self::validate_password(id, &confirm.password, verifier, &mut *db).await?;
+        let secret: Option<String> = cache
+            .get(format!("two-factor-enrollment:{}", id))
+            .await
+            .map_err(|err| {
+                log::error!("Failed to get TOTP secret from cache: {}", err);
+                error!(SERVER, "Failed to enable two-factor authentication")
+            })?;
+        let secret = secret.ok_or_else(|| {
+            error!(
+                VALIDATION,
+                "code", "Enabling two-factor authentication wasn't started or has expired"
+            )
+        })?;
+        let step = verify_totp(&secret, confirm.code.trim(), now(), None).ok_or_else(|| {
+            error!(
+                VALIDATION,
+                "code", "Incorrect two-factor authentication code"
+            )
+        })?;
+        let enabled = sqlx::query!(
+            "
+UPDATE users
+SET two_factor_auth = $1, two_factor_last_step = $2
+WHERE id = $3
+AND two_factor_auth IS NULL
+            ",
+            secret,
+            step as i64,
+            id as i64,
+        )
+        .execute(&mut *db)
+        .await
+        .map(|r| r.rows_affected() > 0)
+        .map_err(|err| {
+            log::error!("Couldn't store TOTP secret in database: {}", err);
+            error!(SERVER, "Failed to enable two-factor authentication")
+        })?;
+        if !enabled {
+            return Err(error!(
+                VALIDATION,
+                "two_factor_auth", "Two-factor authentication is already enabled"
+            ));
+        }
+        let recovery_codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+            .map(|_| generate_recovery_code(rng))
+            .collect();
+        sqlx::query!(
+            "
+INSERT INTO recovery_codes(user_id, code_hash)
+SELECT $1, UNNEST($2::VARCHAR[])
+ON CONFLICT DO NOTHING
+            ",
+            id as i64,
+            &recovery_codes
+                .iter()
+                .map(|c| hash_recovery_code(c))
+                .collect::<Vec<String>>(),
+        )
+        .execute(db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't store recovery codes in database: {}", err);
+            error!(SERVER, "Failed to enable two-factor authentication")
+        })?;
+        cache
+            .del::<_, ()>(format!("two-factor-enrollment:{}", id))
+            .await
+            .map_err(|err| {
+                log::error!("Failed to remove TOTP secret from cache: {}", err);
+                error!(SERVER, "Failed to enable two-factor authentication")
+            })?;
+        Ok(TwoFactorRecoveryCodes { recovery_codes })
+    }
+
+    /// Disable two-factor authentication, this requires the user's password and a TOTP or
+    /// recovery code.
+    pub async fn disable_two_factor<V: PasswordVerifier>(
+        id: u64,
+        credentials: PasswordDeleteCredentials,
+        verifier: &V,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<(), ErrorResponse> {
+        Self::validate_password(id, &credentials.password, verifier, &mut *db).await?;
+        if !Self::validate_two_factor(id, credentials.two_factor_code.as_deref(), &mut *db).await? {
+            return Err(error!(
+                VALIDATION,
+                "two_factor_auth", "Two-factor authentication isn't enabled"
+            ));
+        }
+        sqlx::query!(
+            "
+UPDATE users
+SET two_factor_auth = NULL, two_factor_last_step = NULL
+WHERE id = $1
+            ",
+            id as i64
+        )
+        .execute(&mut *db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't remove TOTP secret from database: {}", err);
+            error!(SERVER, "Failed to disable two-factor authentication")
+        })?;
+        sqlx::query!(
+            "
+DELETE FROM recovery_codes
+WHERE user_id = $1
+            ",
+            id as i64
+        )
+        .execute(db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't remove recovery codes from database: {}", err);
+            error!(SERVER, "Failed to disable two-factor authentication")
+        })?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use data_encoding::BASE32_NOPAD;
+
+    #[test]
+    fn generate_totp() {
+        // RFC 6238's SHA-1 test vectors, truncated to 6 digits.
+        let secret = b"12345678901234567890";
+        assert_eq!(super::generate_totp(secret, 59 / 30), 287082);
+        assert_eq!(super::generate_totp(secret, 1111111109 / 30), 81804);
+        assert_eq!(super::generate_totp(secret, 1234567890 / 30), 5924);
+        assert_eq!(super::generate_totp(secret, 2000000000 / 30), 279037);
+    }
+
+    #[test]
+    fn verify_totp() {
+        let secret = BASE32_NOPAD.encode(b"12345678901234567890");
+        assert_eq!(
+            super::verify_totp(&secret, "081804", 1111111109, None),
+            Some(1111111109 / 30)
+        );
+        // Codes from the previous and next steps are accepted.
+        assert!(super::verify_totp(&secret, "081804", 1111111109 + 30, None).is_some());
+        assert!(super::verify_totp(&secret, "081804", 1111111109 - 30, None).is_some());
+        assert!(super::verify_totp(&secret, "081804", 1111111109 + 60, None).is_none());
+        // Codes can't be reused.
+        assert!(super::verify_totp(&secret, "081804", 1111111109, Some(1111111109 / 30)).is_none());
+        assert!(super::verify_totp(&secret, "81804", 1111111109, None).is_none());
+        assert!(super::verify_totp(&secret, "08180a", 1111111109, None).is_none());
+    }
+
+    #[test]
+    fn hash_recovery_code() {
+        assert_eq!(
+            super::hash_recovery_code("k3v9q-x7m2p"),
+            super::hash_recovery_code(" K3V9QX7M2P")
+        );
+        assert_ne!(
+            super::hash_recovery_code("k3v9q-x7m2p"),
+            super::hash_recovery_code("b8n4r-t6w1z")
+        );
+    }
+
+    #[test]
+    fn get_otpauth_uri() {
+        assert_eq!(
+            super::get_otpauth_uri("My Instance", "yendri", "JBSWY3DPEHPK3PXP"),
+            "otpauth://totp/My%20Instance:yendri?secret=JBSWY3DPEHPK3PXP&issuer=My%20Instance&algorithm=SHA1&digits=6&period=30"
+        );
+    }
+}
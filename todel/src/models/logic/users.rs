@@ -8,19 +8,52 @@ use lazy_static::lazy_static;
 use rand::Rng;
 use redis::AsyncCommands;
 use regex::Regex;
-use sqlx::{pool::PoolConnection, Database, Decode, Postgres, QueryBuilder, Row};
+use sqlx::{pool::PoolConnection, Connection, Database, Decode, Postgres, QueryBuilder, Row};
 
 use crate::{
+    conf::RegistrationMode,
     ids::{IdGenerator, ELUDRIS_EPOCH},
     models::{
-        CreatePasswordResetCode, ErrorResponse, File, PasswordDeleteCredentials, ResetPassword,
-        Session, Status, StatusType, UpdateUser, UpdateUserProfile, User, UserCreate,
+        CreatePasswordResetCode, ErrorResponse, File, Invite, PasswordDeleteCredentials,
+        ResetPassword, Session, Status, StatusType, UpdateUser, UpdateUserProfile, User,
+        UserCreate,
     },
     Conf,
 };
 
 use super::{EmailPreset, Emailer};
 
+/// Get the status a user appears with to other users.
+///
+/// Users who aren't connected to pandemonium appear offline and `ONLINE` users who are inactive on
+/// every one of their connections appear idle.
+async fn get_presence<C: AsyncCommands>(
+    id: u64,
+    status: Status,
+    cache: &mut C,
+) -> Result<Status, ErrorResponse> {
+    let (online, idle): (bool, bool) = redis::pipe()
+        .sismember("sessions", id)
+        .sismember("idle-users", id)
+        .query_async(cache)
+        .await
+        .map_err(|err| {
+            log::error!("Failed to determine if user is online: {}", err);
+            error!(SERVER, "Couldn't provide user data")
+        })?;
+    Ok(match status.status_type {
+        _ if !online => Status {
+            status_type: StatusType::Offline,
+            text: None,
+        },
+        StatusType::Online if idle => Status {
+            status_type: StatusType::Idle,
+            ..status
+        },
+        _ => status,
+    })
+}
+
 pub fn validate_username(username: &str) -> Result<(), ErrorResponse> {
     lazy_static! {
         static ref USERNAME_REGEX: Regex =
@@ -81,6 +114,16 @@ impl UserCreate {
     }
 }
 
+pub fn validate_status(status: &str) -> Result<(), ErrorResponse> {
+    if status.is_empty() || status.len() > 150 {
+        return Err(error!(
+            VALIDATION,
+            "status", "The user's status name must be between 1 and 150 characters in length"
+        ));
+    }
+    Ok(())
+}
+
 impl UpdateUserProfile {
     pub async fn validate(
         &self,
@@ -118,13 +161,7 @@ impl UpdateUserProfile {
             }
         }
         if let Some(Some(status)) = &self.status {
-            if status.is_empty() || status.len() > 150 {
-                return Err(error!(
-                    VALIDATION,
-                    "status",
-                    "The user's status name must be between 1 and 150 characters in length"
-                ));
-            }
+            validate_status(status)?;
         }
         if let Some(Some(avatar)) = self.avatar {
             if File::get(avatar, "avatars", &mut *db).await.is_none() {
@@ -220,6 +257,29 @@ impl User {
         cache: &mut C,
     ) -> Result<Self, ErrorResponse> {
         user.validate()?;
+        let invite_code = match conf.oprish.registration_mode {
+            RegistrationMode::Open => None,
+            RegistrationMode::InviteOnly => Some(user.invite_code.as_deref().ok_or_else(|| {
+                error!(
+                    VALIDATION,
+                    "invite_code", "An invite code is required to register on this instance"
+                )
+            })?),
+            RegistrationMode::Closed => return Err(error!(FORBIDDEN)),
+        };
+        let salt = SaltString::generate(&mut *rng);
+        let hash = hasher
+            .hash_password(user.password.as_bytes(), &salt)
+            .map_err(|err| {
+                log::error!("Failed to hash password: {}", err);
+                error!(SERVER, "Could not hash password")
+            })?
+            .to_string();
+        // The invite is only used up if the user is actually created.
+        let mut tx = db.begin().await.map_err(|err| {
+            log::error!("Couldn't start user creation transaction: {}", err);
+            error!(SERVER, "Could not create user")
+        })?;
         if let Some(existing_user) = sqlx::query!(
             "
 SELECT username, email, is_deleted
@@ -230,7 +290,7 @@ OR email = $2
             user.username,
             user.email,
         )
-        .fetch_optional(&mut *db)
+        .fetch_optional(&mut *tx)
         .await
         .map_err(|err| {
             log::error!(
@@ -249,7 +309,7 @@ OR email= $2
                     user.username,
                     user.email
                 )
-                .execute(&mut *db)
+                .execute(&mut *tx)
                 .await
                 .map_err(|err| {
                     log::error!("Failed to clean up pre-existing deleted user: {}", err);
@@ -261,7 +321,31 @@ OR email= $2
                 return Err(error!(CONFLICT, "email"));
             }
         }
+        if let Some(invite_code) = invite_code {
+            Invite::redeem(invite_code, &mut tx).await?;
+        }
         let id = id_generator.generate();
+        sqlx::query!(
+            "
+INSERT INTO users(id, username, verified, email, password)
+VALUES($1, $2, $3, $4, $5)
+            ",
+            id as i64,
+            user.username,
+            conf.email.is_none(),
+            user.email,
+            hash
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|err| {
+            log::error!("Failed to store user in database: {}", err);
+            error!(SERVER, "Could not save user data")
+        })?;
+        tx.commit().await.map_err(|err| {
+            log::error!("Couldn't commit user creation: {}", err);
+            error!(SERVER, "Could not create user")
+        })?;
 
         if let Some(email) = &conf.email {
             let code = rng.gen_range(100000..999999);
@@ -284,36 +368,13 @@ OR email= $2
                 .await?;
         }
 
-        let salt = SaltString::generate(rng);
-        let hash = hasher
-            .hash_password(user.password.as_bytes(), &salt)
-            .map_err(|err| {
-                log::error!("Failed to hash password: {}", err);
-                error!(SERVER, "Could not hash password")
-            })?
-            .to_string();
-        sqlx::query!(
-            "
-INSERT INTO users(id, username, verified, email, password)
-VALUES($1, $2, $3, $4, $5)
-            ",
-            id as i64,
-            user.username,
-            conf.email.is_none(),
-            user.email,
-            hash
-        )
-        .execute(db)
-        .await
-        .map_err(|err| {
-            log::error!("Failed to store user in database: {}", err);
-            error!(SERVER, "Could not save user data")
-        })?;
         Ok(Self {
             id,
             username: user.username,
             display_name: None,
             social_credit: 0,
+            follower_count: 0,
+            following_count: 0,
             status: Status {
                 status_type: StatusType::Offline,
                 text: None,
@@ -325,6 +386,7 @@ VALUES($1, $2, $3, $4, $5)
             permissions: 0,
             email: Some(user.email),
             verified: Some(conf.email.is_none()),
+            two_factor_auth: Some(false),
         })
     }
 
@@ -420,6 +482,31 @@ WHERE id = $1
         Ok(())
     }
 
+    /// Check whether a user exists and wasn't deleted.
+    pub(crate) async fn exists(
+        id: u64,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<bool, ErrorResponse> {
+        sqlx::query!(
+            r#"
+SELECT EXISTS(
+  SELECT 1
+  FROM users
+  WHERE id = $1
+  AND is_deleted = FALSE
+) AS "exists!"
+            "#,
+            id as i64
+        )
+        .fetch_one(db)
+        .await
+        .map(|r| r.exists)
+        .map_err(|err| {
+            log::error!("Couldn't check user existence: {}", err);
+            error!(SERVER, "Failed to get user data")
+        })
+    }
+
     #[allow(clippy::blocks_in_if_conditions)] // it's supposedly bad beacuse of code cleanness but
                                               // in this case it's cleaner
     pub async fn get<C: AsyncCommands>(
@@ -430,7 +517,23 @@ WHERE id = $1
     ) -> Result<Self, ErrorResponse> {
         sqlx::query!(
             r#"
-SELECT id, username, display_name, social_credit, status, status_type as "status_type: StatusType", bio, avatar, banner, badges, permissions, email, verified
+SELECT
+  id,
+  username,
+  display_name,
+  social_credit,
+  (SELECT COUNT(*) FROM follows WHERE user_id = users.id) AS "follower_count!",
+  (SELECT COUNT(*) FROM follows WHERE follower_id = users.id) AS "following_count!",
+  status,
+  status_type as "status_type: StatusType",
+  bio,
+  avatar,
+  banner,
+  badges,
+  permissions,
+  email,
+  verified,
+  two_factor_auth IS NOT NULL AS "two_factor_auth!"
 FROM users
 WHERE id = $1
 AND is_deleted = FALSE
@@ -449,23 +552,23 @@ AND is_deleted = FALSE
                 username: u.username,
                 display_name: u.display_name,
                 social_credit: u.social_credit,
-                status: if  Some(id) == requester_id  ||
-                    cache
-                    .sismember::<_, _, bool>("sessions", u.id as u64)
-                    .await
-                    .map_err(|err| {
-                        log::error!("Failed to determine if user is online: {}", err);
-                        error!(SERVER, "Couldn't provide user data")
-                    })? {
-                        Status {
-                        status_type: u.status_type,
-                            text: u.status,
-                        }
-                } else {
+                follower_count: u.follower_count as u64,
+                following_count: u.following_count as u64,
+                status: if Some(id) == requester_id {
                     Status {
-                        status_type: StatusType::Offline,
-                        text: None,
+                        status_type: u.status_type,
+                        text: u.status,
                     }
+                } else {
+                    get_presence(
+                        u.id as u64,
+                        Status {
+                            status_type: u.status_type,
+                            text: u.status,
+                        },
+                        cache,
+                    )
+                    .await?
                 },
                 bio: u.bio,
                 avatar: u.avatar.map(|a| a as u64),
@@ -473,10 +576,12 @@ AND is_deleted = FALSE
                 badges: u.badges as u64,
                 permissions: u.permissions as u64,
                 email: (Some(id) == requester_id).then_some(u.email),
-                verified: (Some(id) == requester_id).then_some(u.verified)
+                verified: (Some(id) == requester_id).then_some(u.verified),
+                two_factor_auth: (Some(id) == requester_id).then_some(u.two_factor_auth),
             })
         })
-        .ok_or_else(|| error!(NOT_FOUND))?.await
+        .ok_or_else(|| error!(NOT_FOUND))?
+        .await
     }
 
     #[allow(clippy::blocks_in_if_conditions)]
@@ -488,7 +593,23 @@ AND is_deleted = FALSE
     ) -> Result<Self, ErrorResponse> {
         sqlx::query!(
             r#"
-SELECT id, username, display_name, social_credit, status, status_type as "status_type: StatusType", bio, avatar, banner, badges, permissions, email, verified
+SELECT
+  id,
+  username,
+  display_name,
+  social_credit,
+  (SELECT COUNT(*) FROM follows WHERE user_id = users.id) AS "follower_count!",
+  (SELECT COUNT(*) FROM follows WHERE follower_id = users.id) AS "following_count!",
+  status,
+  status_type as "status_type: StatusType",
+  bio,
+  avatar,
+  banner,
+  badges,
+  permissions,
+  email,
+  verified,
+  two_factor_auth IS NOT NULL AS "two_factor_auth!"
 FROM users
 WHERE username = $1
 AND is_deleted = FALSE
@@ -507,22 +628,23 @@ AND is_deleted = FALSE
                 username: u.username,
                 display_name: u.display_name,
                 social_credit: u.social_credit,
-                status: if Some(u.id as u64) == requester_id || cache
-                    .sismember::<_, _, bool>("sessions", u.id as u64)
-                    .await
-                    .map_err(|err| {
-                        log::error!("Failed to determine if user is online: {}", err);
-                        error!(SERVER, "Couldn't provide user data")
-                    })? {
+                follower_count: u.follower_count as u64,
+                following_count: u.following_count as u64,
+                status: if Some(u.id as u64) == requester_id {
                     Status {
                         status_type: u.status_type,
                         text: u.status,
                     }
                 } else {
-                    Status {
-                        status_type: StatusType::Offline,
-                        text: None,
-                    }
+                    get_presence(
+                        u.id as u64,
+                        Status {
+                            status_type: u.status_type,
+                            text: u.status,
+                        },
+                        cache,
+                    )
+                    .await?
                 },
                 bio: u.bio,
                 avatar: u.avatar.map(|a| a as u64),
@@ -530,24 +652,30 @@ AND is_deleted = FALSE
                 badges: u.badges as u64,
                 permissions: u.permissions as u64,
                 email: (Some(u.id as u64) == requester_id).then_some(u.email),
-                verified: (Some(u.id as u64) == requester_id).then_some(u.verified)
+                verified: (Some(u.id as u64) == requester_id).then_some(u.verified),
+                two_factor_auth: (Some(u.id as u64) == requester_id).then_some(u.two_factor_auth),
             })
         })
         .ok_or_else(|| error!(NOT_FOUND))?
         .await
     }
 
-    pub async fn update<H: PasswordHasher, R: CryptoRngCore>(
+    /// Update a user's account, changing their password revokes all of their other sessions.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn update<H: PasswordHasher, R: CryptoRngCore, C: AsyncCommands>(
         id: u64,
+        session_id: u64,
         update: UpdateUser,
         mailer: &Emailer,
         conf: &Conf,
         hasher: &H,
         rng: &mut R,
         db: &mut PoolConnection<Postgres>,
+        cache: &mut C,
     ) -> Result<Self, ErrorResponse> {
         update.validate(&mut *db).await?;
-        Self::validate_password(id, &update.password, hasher, db).await?;
+        Self::validate_password(id, &update.password, hasher, &mut *db).await?;
+        Self::validate_two_factor(id, update.two_factor_code.as_deref(), &mut *db).await?;
         let mut query: QueryBuilder<Postgres> = QueryBuilder::new("UPDATE users SET ");
         let mut seperated = query.separated(", ");
         if let Some(username) = &update.username {
@@ -573,16 +701,18 @@ AND is_deleted = FALSE
             .push(" WHERE id = ")
             .push_bind(id as i64)
             .push(
-                " RETURNING id, username, display_name, social_credit, status, status_type, bio, avatar, banner, badges, permissions, email, verified",
+                " RETURNING id, username, display_name, social_credit, (SELECT COUNT(*) FROM follows WHERE user_id = users.id) AS follower_count, (SELECT COUNT(*) FROM follows WHERE follower_id = users.id) AS following_count, status, status_type, bio, avatar, banner, badges, permissions, email, verified, two_factor_auth IS NOT NULL AS two_factor_auth",
             )
             .build()
-            .fetch_one(db)
+            .fetch_one(&mut *db)
             .await
             .map(|u| Self {
                 id: u.get::<i64, _>("id") as u64,
                 username: u.get("username"),
                 display_name: u.get("display_name"),
                 social_credit: u.get("social_credit"),
+                follower_count: u.get::<i64, _>("follower_count") as u64,
+                following_count: u.get::<i64, _>("following_count") as u64,
                 status: Status {
                     status_type: u.get("status_type"),
                     text: u.get("status"),
@@ -594,11 +724,15 @@ AND is_deleted = FALSE
                 permissions: u.get::<i64, _>("permissions") as u64,
                 email: Some(u.get("email")),
                 verified: Some(u.get("verified")),
+                two_factor_auth: Some(u.get("two_factor_auth")),
             })
             .map_err(|err| {
                 log::error!("Couldn't update user profile: {}", err);
                 error!(SERVER, "Failed to update user profile")
             })?;
+        if update.new_password.is_some() {
+            Session::revoke(id, Some(session_id), "Password changed", db, cache).await?;
+        }
         if let Some(email) = &conf.email {
             mailer
                 .send_email(
@@ -659,16 +793,18 @@ AND is_deleted = FALSE
             .push(" WHERE id = ")
             .push_bind(id as i64)
             .push(
-                " RETURNING id, username, display_name, social_credit, status, status_type, bio, avatar, banner, badges, permissions, email, verified",
+                " RETURNING id, username, display_name, social_credit, (SELECT COUNT(*) FROM follows WHERE user_id = users.id) AS follower_count, (SELECT COUNT(*) FROM follows WHERE follower_id = users.id) AS following_count, status, status_type, bio, avatar, banner, badges, permissions, email, verified, two_factor_auth IS NOT NULL AS two_factor_auth",
             )
             .build()
-            .fetch_one(db)
+            .fetch_one(&mut *db)
             .await
             .map(|u| Self {
                 id: u.get::<i64, _>("id") as u64,
                 username: u.get("username"),
                 display_name: u.get("display_name"),
                 social_credit: u.get("social_credit"),
+                follower_count: u.get::<i64, _>("follower_count") as u64,
+                following_count: u.get::<i64, _>("following_count") as u64,
                 status: Status {
                     status_type: u.get("status_type"),
                     text: u.get("status"),
@@ -680,6 +816,7 @@ AND is_deleted = FALSE
                 permissions: u.get::<i64, _>("permissions") as u64,
                 email: Some(u.get("email")),
                 verified: Some(u.get("verified")),
+                two_factor_auth: Some(u.get("two_factor_auth")),
             })
             .map_err(|err| {
                 log::error!("Couldn't update user profile: {}", err);
@@ -687,6 +824,34 @@ AND is_deleted = FALSE
             })
     }
 
+    /// Change a user's status, as done by the gateway's `UPDATE_PRESENCE` payload.
+    pub async fn update_status(
+        id: u64,
+        status: &Status,
+        db: &mut PoolConnection<Postgres>,
+    ) -> Result<(), ErrorResponse> {
+        if let Some(text) = &status.text {
+            validate_status(text)?;
+        }
+        sqlx::query!(
+            "
+UPDATE users
+SET status = $1, status_type = $2
+WHERE id = $3
+            ",
+            status.text,
+            status.status_type.clone() as StatusType,
+            id as i64,
+        )
+        .execute(db)
+        .await
+        .map_err(|err| {
+            log::error!("Couldn't update user status: {}", err);
+            error!(SERVER, "Failed to update user status")
+        })?;
+        Ok(())
+    }
+
     pub async fn delete<V: PasswordVerifier>(
         id: u64,
         delete: PasswordDeleteCredentials,
@@ -695,7 +860,8 @@ AND is_deleted = FALSE
         conf: &Conf,
         db: &mut PoolConnection<Postgres>,
     ) -> Result<(), ErrorResponse> {
-        Self::validate_password(id, &delete.password, verifier, db).await?;
+        Self::validate_password(id, &delete.password, verifier, &mut *db).await?;
+        Self::validate_two_factor(id, delete.two_factor_code.as_deref(), &mut *db).await?;
         let user = sqlx::query!(
             "
 UPDATE users
@@ -778,6 +944,7 @@ WHERE email = $1
         }
     }
 
+    /// Reset a user's password using a code sent to their email, revoking all of their sessions.
     pub async fn reset_password<H: PasswordHasher, R: CryptoRngCore, C: AsyncCommands>(
         reset: ResetPassword,
         hasher: &H,
@@ -811,12 +978,12 @@ WHERE email = $1
 UPDATE users
 SET password = $1
 WHERE email = $2
-returning username, email
+RETURNING id, username, email
             ",
+            hash,
             reset.email,
-            hash
         )
-        .fetch_one(db)
+        .fetch_one(&mut *db)
         .await
         .map_err(|err| {
             log::error!("Failed to set user password hash in database: {}", err);
@@ -832,6 +999,7 @@ returning username, email
                 );
                 error!(SERVER, "Couldn't reset the user's  password")
             })?;
+        Session::revoke(user.id as u64, None, "Password reset", db, cache).await?;
         if let Some(email) = &conf.email {
             mailer
                 .send_email(
@@ -883,6 +1051,7 @@ WHERE is_deleted = TRUE
 
 #[cfg(test)]
 mod tests {
+    use super::validate_status;
     use crate::models::UserCreate;
 
     macro_rules! test_user_create_error {
@@ -891,6 +1060,7 @@ mod tests {
                 username: $username.to_string(),
                 email: "yendri@llamoyendri.io".to_string(),
                 password: "autentícame por favor".to_string(),
+                invite_code: None,
             };
             assert!(user.validate().is_err());
         };
@@ -899,6 +1069,7 @@ mod tests {
                 username: "yendri".to_string(),
                 email: $email.to_string(),
                 password: "autentícame por favor".to_string(),
+                invite_code: None,
             };
             assert!(user.validate().is_err());
         };
@@ -907,6 +1078,7 @@ mod tests {
                 username: "yendri".to_string(),
                 email: "yendri@llamoyendri.io".to_string(),
                 password: $password.to_string(),
+                invite_code: None,
             };
             assert!(user.validate().is_err());
         };
@@ -918,6 +1090,7 @@ mod tests {
             username: "yendri".to_string(),
             email: "yendri@llamoyendri.io".to_string(),
             password: "autentícame por favor".to_string(),
+            invite_code: None,
         };
 
         assert!(user.validate().is_ok());
@@ -932,4 +1105,11 @@ mod tests {
 
         test_user_create_error!(password: "1234"); // too short
     }
+
+    #[test]
+    fn validate_statuses() {
+        assert!(validate_status("ayúdame por favor").is_ok());
+        assert!(validate_status("").is_err());
+        assert!(validate_status(&"a".repeat(151)).is_err());
+    }
 }
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use super::User;
+use super::{FileData, Reaction, User};
 
 /// The MessageCreate payload. This is used when you want to create a message using the REST API.
 ///
@@ -10,20 +10,61 @@ use super::User;
 ///
 /// ```json
 /// {
-///   "content": "Hello, World!"
+///   "content": "Hello, World!",
+///   "attachments": [2199681302540],
+///   "reference": 2373120361473,
+///   "mention_reference": false
 /// }
 /// ```
 #[autodoc(category = "Messaging")]
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct MessageCreate {
-    /// The message's content. This field has to be at-least 2 characters long. The upper limit
-    /// is the instance's [`InstanceInfo`] `message_limit`.
+    /// The message's content. This field has to be at-least 1 character long unless the message
+    /// has attachments. The upper limit is the instance's [`InstanceInfo`] `message_limit`.
     ///
     /// The content will be trimmed from leading and trailing whitespace.
+    #[serde(default)]
     pub content: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "_disguise")]
     pub disguise: Option<MessageDisguise>,
+    /// The IDs of the Effis attachments to add to the message.
+    ///
+    /// The files have to be uploaded to the `attachments` bucket by the message's author and can't
+    /// already be attached to another message. A message can have up to 10 attachments.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<u64>,
+    /// The ID of the message this message is replying to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reference: Option<u64>,
+    /// Whether the author of the referenced message should be mentioned. Defaults to `true`.
+    #[serde(default = "mention_reference_default")]
+    pub mention_reference: bool,
+}
+
+fn mention_reference_default() -> bool {
+    true
+}
+
+/// The MessageEdit payload. This is used when you want to edit one of your messages using the
+/// REST API.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// {
+///   "content": "Hello, World! (edited)"
+/// }
+/// ```
+#[autodoc(category = "Messaging")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MessageEdit {
+    /// The message's new content. This field follows the same rules as the [`MessageCreate`]
+    /// `content` field.
+    pub content: String,
 }
 
 /// A temporary way to mask the message's author's name and avatar. This is mainly used for
@@ -48,6 +89,39 @@ pub struct MessageDisguise {
     pub avatar: Option<String>,
 }
 
+/// A trimmed down copy of the message another message is replying to.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// {
+///   "id": 2373120361473,
+///   "author": {
+///      "id": 48615849987333,
+///      "username": "mlynar",
+///      "social_credit": 9999.
+///      "badges": 256,
+///      "permissions": 8
+///   }
+///   "content": "Hello, World!",
+///   "mention": true
+/// }
+/// ```
+#[autodoc(category = "Messaging")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MessageReference {
+    /// The referenced message's ID.
+    pub id: u64,
+    /// The referenced message's author.
+    pub author: User,
+    /// The referenced message's content, truncated to 100 characters.
+    pub content: String,
+    /// Whether the referenced message's author is mentioned by the reply.
+    pub mention: bool,
+}
+
 /// The Message payload. This is returned when you're provided information about a pre-existing
 /// message.
 ///
@@ -57,6 +131,7 @@ pub struct MessageDisguise {
 ///
 /// ```json
 /// {
+///   "id": 2373120361473,
 ///   "author": {
 ///      "id": 48615849987333,
 ///      "username": "mlynar",
@@ -64,15 +139,81 @@ pub struct MessageDisguise {
 ///      "badges": 256,
 ///      "permissions": 8
 ///   }
-///   "content": "Hello, World!"
+///   "content": "Hello, World!",
+///   "attachments": [
+///     {
+///       "id": 2199681302540,
+///       "name": "thang-big.png",
+///       "bucket": "attachments",
+///       "metadata": {
+///         "type": "IMAGE",
+///         "width": 702,
+///         "height": 702
+///       }
+///     }
+///   ],
+///   "reference": {
+///     "id": 2373098217473,
+///     "author": {
+///        "id": 48615849987334,
+///        "username": "olivier",
+///        "social_credit": 42,
+///        "badges": 0,
+///        "permissions": 0
+///     },
+///     "content": "Hi, anyone around?",
+///     "mention": true
+///   },
+///   "mentions": [48615849987334],
+///   "reactions": [
+///     {
+///       "emoji": {
+///         "type": "UNICODE",
+///         "emoji": "👍"
+///       },
+///       "count": 3
+///     }
+///   ],
+///   "edited_at": 1687634530
 /// }
 /// ```
 #[autodoc(category = "Messaging")]
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Message {
+    /// The message's ID.
+    pub id: u64,
+    /// The ID of the channel the message was sent in.
+    ///
+    /// Messages sent before channels existed don't belong to any channel.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel_id: Option<u64>,
     /// The message's author.
     pub author: User,
-    /// There message's data.
-    #[serde(flatten)]
-    pub message: MessageCreate,
+    /// The message's content.
+    pub content: String,
+    /// The message's disguise, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "_disguise")]
+    pub disguise: Option<MessageDisguise>,
+    /// The files attached to the message.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<FileData>,
+    /// The message this message is replying to, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reference: Option<MessageReference>,
+    /// The IDs of the users mentioned by the message.
+    ///
+    /// Users are mentioned using `<@user_id>` in the message's content or by being the author of
+    /// the message this message is replying to when `mention_reference` is set.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub mentions: Vec<u64>,
+    /// The reactions on the message.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub reactions: Vec<Reaction>,
+    /// The UNIX timestamp (in seconds) of when the message was last edited, if ever.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub edited_at: Option<u64>,
 }
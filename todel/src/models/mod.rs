@@ -1,18 +1,42 @@
 //! A collection of models and some related function implementations for eludris.
 
+mod admin;
+mod channels;
+mod communities;
+mod dms;
 mod files;
 mod gateway;
 mod info;
+mod invites;
 mod messages;
+mod mutes;
+mod permissions;
+mod posts;
+mod reactions;
+mod relationships;
+mod reports;
 mod response;
+mod roles;
 mod sessions;
 mod users;
 
+pub use admin::*;
+pub use channels::*;
+pub use communities::*;
+pub use dms::*;
 pub use files::*;
 pub use gateway::*;
 pub use info::*;
+pub use invites::*;
 pub use messages::*;
+pub use mutes::*;
+pub use permissions::*;
+pub use posts::*;
+pub use reactions::*;
+pub use relationships::*;
+pub use reports::*;
 pub use response::*;
+pub use roles::*;
 pub use sessions::*;
 pub use users::*;
 
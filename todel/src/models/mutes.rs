@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+
+/// The Mute payload. Muted members can't send messages in the community until their mute expires.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// {
+///   "community_id": 2384913657857,
+///   "user_id": 48615849987334,
+///   "moderator_id": 48615849987333,
+///   "reason": "Spamming lanternfly pictures",
+///   "expires_at": 1690995600
+/// }
+/// ```
+#[autodoc(category = "Communities")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Mute {
+    /// The ID of the community the member is muted in.
+    pub community_id: u64,
+    /// The ID of the muted member.
+    pub user_id: u64,
+    /// The ID of the moderator who muted the member.
+    pub moderator_id: u64,
+    /// The reason the member was muted for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// The UNIX timestamp (in seconds) at which the mute expires.
+    pub expires_at: u64,
+}
+
+/// The MuteCreate payload. This is used to mute a member of a community.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// {
+///   "duration": 3600,
+///   "reason": "Spamming lanternfly pictures"
+/// }
+/// ```
+#[autodoc(category = "Communities")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MuteCreate {
+    /// The amount of seconds the member will be muted for. This field has to be between 1 and
+    /// 2419200 (28 days).
+    pub duration: u64,
+    /// The reason the member is muted for. This field has to be between 1 and 512 characters
+    /// long.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
@@ -0,0 +1,149 @@
+use bitflags::bitflags;
+use serde::{Deserialize, Serialize};
+
+bitflags! {
+    /// The permissions a user has, either instance-wide through their [`User`] `permissions` or
+    /// inside of a community through its [`Role`]s and [`ChannelOverwrite`]s.
+    ///
+    /// Permissions are represented as a bitfield of the following flags:
+    ///
+    /// | Permission         | Value |
+    /// | ------------------ | ----- |
+    /// | `ADMINISTRATOR`    | 1     |
+    /// | `MANAGE_COMMUNITY` | 2     |
+    /// | `MANAGE_CHANNELS`  | 4     |
+    /// | `MANAGE_ROLES`     | 8     |
+    /// | `MANAGE_MESSAGES`  | 16    |
+    /// | `KICK_MEMBERS`     | 32    |
+    /// | `BAN_MEMBERS`      | 64    |
+    /// | `VIEW_CHANNEL`     | 128   |
+    /// | `SEND_MESSAGES`    | 256   |
+    /// | `ADD_REACTIONS`    | 512   |
+    /// | `CREATE_INVITES`   | 1024  |
+    /// | `MODERATE_MEMBERS` | 2048  |
+    /// | `MANAGE_USERS`     | 4096  |
+    /// | `MANAGE_REPORTS`   | 8192  |
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct Permissions: u64 {
+        /// Grants every permission and bypasses all channel overwrites.
+        const ADMINISTRATOR = 1 << 0;
+        /// Allows editing the community.
+        const MANAGE_COMMUNITY = 1 << 1;
+        /// Allows creating, editing and deleting channels as well as their overwrites.
+        const MANAGE_CHANNELS = 1 << 2;
+        /// Allows creating, editing, deleting and assigning roles below your highest role.
+        const MANAGE_ROLES = 1 << 3;
+        /// Allows deleting other members' messages.
+        const MANAGE_MESSAGES = 1 << 4;
+        /// Allows removing members from the community.
+        const KICK_MEMBERS = 1 << 5;
+        /// Allows banning members from the community.
+        const BAN_MEMBERS = 1 << 6;
+        /// Allows seeing a channel and reading its messages.
+        const VIEW_CHANNEL = 1 << 7;
+        /// Allows sending messages in a channel.
+        const SEND_MESSAGES = 1 << 8;
+        /// Allows reacting to messages in a channel.
+        const ADD_REACTIONS = 1 << 9;
+        /// Allows creating invites to register on the instance. This only applies to a user's
+        /// instance-wide permissions.
+        const CREATE_INVITES = 1 << 10;
+        /// Allows muting members below your highest role and changing channels' slow mode.
+        const MODERATE_MEMBERS = 1 << 11;
+        /// Allows using the admin API to suspend, verify and rename users as well as revoke their
+        /// sessions. This only applies to a user's instance-wide permissions.
+        const MANAGE_USERS = 1 << 12;
+        /// Allows viewing and resolving the reports made by users and receiving new ones through
+        /// the gateway. This only applies to a user's instance-wide permissions.
+        const MANAGE_REPORTS = 1 << 13;
+    }
+}
+
+impl Permissions {
+    /// The permissions of a community's default role when it gets created.
+    pub const DEFAULT: Self = Self::VIEW_CHANNEL
+        .union(Self::SEND_MESSAGES)
+        .union(Self::ADD_REACTIONS);
+}
+
+/// The type of a [`ChannelOverwrite`]'s target.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// "ROLE"
+/// ```
+#[autodoc(category = "Communities")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+#[cfg_attr(feature = "logic", derive(sqlx::Type))]
+#[cfg_attr(feature = "logic", sqlx(type_name = "overwrite_type"))]
+#[cfg_attr(feature = "logic", sqlx(rename_all = "UPPERCASE"))]
+pub enum OverwriteType {
+    /// The overwrite applies to every member with the role.
+    Role,
+    /// The overwrite applies to a single member.
+    Member,
+}
+
+/// A channel-specific change to the permissions of a role or member.
+///
+/// Overwrites are applied on top of a member's community-wide permissions in the following
+/// order: the community's default role, then the member's other roles, then the member
+/// themselves. `deny` is applied before `allow` at each step.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// {
+///   "target_id": 2384913657857,
+///   "type": "ROLE",
+///   "allow": 0,
+///   "deny": 256
+/// }
+/// ```
+#[autodoc(category = "Communities")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChannelOverwrite {
+    /// The ID of the role or member the overwrite applies to.
+    pub target_id: u64,
+    /// The type of the overwrite's target.
+    #[serde(rename = "type")]
+    pub overwrite_type: OverwriteType,
+    /// The [`Permissions`] the overwrite grants.
+    pub allow: u64,
+    /// The [`Permissions`] the overwrite revokes.
+    pub deny: u64,
+}
+
+/// The ChannelOverwriteEdit payload. This is used to create or replace a channel's overwrite for
+/// a role or member.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// {
+///   "type": "MEMBER",
+///   "allow": 256,
+///   "deny": 0
+/// }
+/// ```
+#[autodoc(category = "Communities")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChannelOverwriteEdit {
+    /// The type of the overwrite's target.
+    #[serde(rename = "type")]
+    pub overwrite_type: OverwriteType,
+    /// The [`Permissions`] the overwrite grants. Defaults to none.
+    #[serde(default)]
+    pub allow: u64,
+    /// The [`Permissions`] the overwrite revokes. Defaults to none.
+    #[serde(default)]
+    pub deny: u64,
+}
@@ -0,0 +1,232 @@
+use serde::{Deserialize, Serialize};
+
+use super::User;
+
+/// A vote on a [`Post`].
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// "UP"
+/// ```
+#[autodoc(category = "Posts")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum VoteType {
+    /// An up-vote, which increases the post's score and its author's social credit by one.
+    Up,
+    /// A down-vote, which decreases the post's score and its author's social credit by one.
+    Down,
+}
+
+/// The order posts are returned in.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// "HOT"
+/// ```
+#[autodoc(category = "Posts")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+#[cfg_attr(feature = "http", derive(rocket::FromFormField))]
+pub enum PostSort {
+    /// Posts with a high score that were created recently first.
+    #[default]
+    Hot,
+    /// The newest posts first.
+    New,
+    /// The posts with the highest score first.
+    Top,
+}
+
+/// The Post payload.
+///
+/// Posts are created in topics, boards which group posts about the same subject together.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// {
+///   "id": 2390219112449,
+///   "author": {
+///     "id": 48615849987333,
+///     "username": "yendri",
+///     "social_credit": 42,
+///     "follower_count": 0,
+///     "following_count": 0,
+///     "status": {
+///       "type": "ONLINE"
+///     },
+///     "badges": 0,
+///     "permissions": 0
+///   },
+///   "topic": "lanternflies",
+///   "title": "Found another one",
+///   "content": "It was on my car this time.",
+///   "score": 42,
+///   "comment_count": 3,
+///   "vote": "UP"
+/// }
+/// ```
+#[autodoc(category = "Posts")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Post {
+    /// The post's ID.
+    pub id: u64,
+    /// The post's author.
+    pub author: User,
+    /// The topic the post was created in.
+    pub topic: String,
+    /// The post's title.
+    pub title: String,
+    /// The post's content.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    /// The post's score, its up-votes minus its down-votes.
+    pub score: i32,
+    /// The amount of comments on the post.
+    pub comment_count: u64,
+    /// Your vote on the post.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vote: Option<VoteType>,
+}
+
+/// The PostCreate payload. This is used to create a post in a topic.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// {
+///   "topic": "lanternflies",
+///   "title": "Found another one",
+///   "content": "It was on my car this time."
+/// }
+/// ```
+#[autodoc(category = "Posts")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PostCreate {
+    /// The topic to create the post in. This field has to be between 1 and 32 characters long
+    /// and can only contain lowercase letters, numbers, underscores and dashes.
+    pub topic: String,
+    /// The post's title. This field has to be between 1 and 256 characters long.
+    pub title: String,
+    /// The post's content. This field has to be between 1 and 10000 characters long.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+}
+
+/// The PostVote payload. This is used to vote on a post.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// {
+///   "type": "UP"
+/// }
+/// ```
+#[autodoc(category = "Posts")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PostVote {
+    /// The vote's type.
+    #[serde(rename = "type")]
+    pub vote_type: VoteType,
+}
+
+/// The Comment payload. Comments are either direct replies to a post or replies to other
+/// comments, forming a tree.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// {
+///   "id": 2390252929025,
+///   "post_id": 2390219112449,
+///   "author": {
+///     "id": 48615849987334,
+///     "username": "olivier",
+///     "social_credit": 0,
+///     "follower_count": 0,
+///     "following_count": 0,
+///     "status": {
+///       "type": "OFFLINE"
+///     },
+///     "badges": 0,
+///     "permissions": 0
+///   },
+///   "content": "Stomp it.",
+///   "replies": [
+///     {
+///       "id": 2390273638401,
+///       "post_id": 2390219112449,
+///       "parent_id": 2390252929025,
+///       "author": {
+///         "id": 48615849987333,
+///         "username": "yendri",
+///         "social_credit": 42,
+///         "follower_count": 0,
+///         "following_count": 0,
+///         "status": {
+///           "type": "ONLINE"
+///         },
+///         "badges": 0,
+///         "permissions": 0
+///       },
+///       "content": "Already did."
+///     }
+///   ]
+/// }
+/// ```
+#[autodoc(category = "Posts")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Comment {
+    /// The comment's ID.
+    pub id: u64,
+    /// The ID of the post the comment is on.
+    pub post_id: u64,
+    /// The ID of the comment this comment replies to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<u64>,
+    /// The comment's author.
+    pub author: User,
+    /// The comment's content.
+    pub content: String,
+    /// The replies to the comment, oldest first.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub replies: Vec<Comment>,
+}
+
+/// The CommentCreate payload. This is used to comment on a post or reply to another comment.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// {
+///   "content": "Already did.",
+///   "parent_id": 2390252929025
+/// }
+/// ```
+#[autodoc(category = "Posts")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommentCreate {
+    /// The comment's content. This field has to be between 1 and 4000 characters long.
+    pub content: String,
+    /// The ID of the comment to reply to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<u64>,
+}
@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+
+/// The emoji used in a [`Reaction`].
+///
+/// Only unicode emojis are supported at the moment.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// {
+///   "type": "UNICODE",
+///   "emoji": "👍"
+/// }
+/// ```
+#[autodoc(category = "Messaging")]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+#[serde(tag = "type")]
+pub enum Emoji {
+    Unicode {
+        /// The emoji itself.
+        emoji: String,
+    },
+}
+
+/// An aggregated reaction on a [`Message`].
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// {
+///   "emoji": {
+///     "type": "UNICODE",
+///     "emoji": "👍"
+///   },
+///   "count": 3
+/// }
+/// ```
+#[autodoc(category = "Messaging")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Reaction {
+    /// The reaction's emoji.
+    pub emoji: Emoji,
+    /// The amount of users who reacted with this emoji.
+    pub count: u64,
+}
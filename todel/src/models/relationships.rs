@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+
+/// The type of a [`Relationship`].
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// "FRIEND"
+/// ```
+#[autodoc(category = "Relationships")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+#[cfg_attr(feature = "logic", derive(sqlx::Type))]
+#[cfg_attr(feature = "logic", sqlx(type_name = "relationship_type"))]
+#[cfg_attr(feature = "logic", sqlx(rename_all = "UPPERCASE"))]
+pub enum RelationshipType {
+    /// The two users are friends.
+    Friend,
+    /// The other user sent you a friend request.
+    Incoming,
+    /// You sent the other user a friend request.
+    Outgoing,
+    /// You blocked the other user.
+    Blocked,
+}
+
+/// The Relationship payload, which represents your relationship with another user.
+///
+/// Blocked users can't open DMs with you, send you friend requests or see your presence.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// {
+///   "id": 48615849987334,
+///   "type": "INCOMING"
+/// }
+/// ```
+#[autodoc(category = "Relationships")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Relationship {
+    /// The ID of the other user.
+    pub id: u64,
+    /// The relationship's type.
+    #[serde(rename = "type")]
+    pub relationship_type: RelationshipType,
+}
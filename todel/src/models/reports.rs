@@ -0,0 +1,169 @@
+use serde::{Deserialize, Serialize};
+
+/// The type of thing a [`Report`] is about.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// "MESSAGE"
+/// ```
+#[autodoc(category = "Reports")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+#[cfg_attr(feature = "logic", derive(sqlx::Type))]
+#[cfg_attr(feature = "logic", sqlx(type_name = "report_target_type"))]
+#[cfg_attr(feature = "logic", sqlx(rename_all = "UPPERCASE"))]
+pub enum ReportTargetType {
+    /// A user's profile.
+    User,
+    /// A message.
+    Message,
+    /// A file uploaded to Effis.
+    File,
+}
+
+/// The reason a [`Report`] was made.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// "HARASSMENT"
+/// ```
+#[autodoc(category = "Reports")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[cfg_attr(feature = "logic", derive(sqlx::Type))]
+#[cfg_attr(feature = "logic", sqlx(type_name = "report_category"))]
+#[cfg_attr(feature = "logic", sqlx(rename_all = "SCREAMING_SNAKE_CASE"))]
+pub enum ReportCategory {
+    /// Unsolicited advertising or repeated unwanted content.
+    Spam,
+    /// Targeted harassment or bullying.
+    Harassment,
+    /// Content attacking people based on who they are.
+    HateSpeech,
+    /// Sexual or graphic content.
+    Nsfw,
+    /// Content that's illegal to share.
+    IllegalContent,
+    /// Anything else, explained in the report's details.
+    Other,
+}
+
+/// The status of a [`Report`].
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// "OPEN"
+/// ```
+#[autodoc(category = "Reports")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+#[cfg_attr(feature = "http", derive(rocket::FromFormField))]
+#[cfg_attr(feature = "logic", derive(sqlx::Type))]
+#[cfg_attr(feature = "logic", sqlx(type_name = "report_status"))]
+#[cfg_attr(feature = "logic", sqlx(rename_all = "UPPERCASE"))]
+pub enum ReportStatus {
+    /// The report still has to be looked at by a moderator.
+    Open,
+    /// A moderator took action because of the report.
+    Actioned,
+    /// A moderator dismissed the report.
+    Dismissed,
+}
+
+/// The Report payload. Reports are how users flag abusive users, messages and files to the
+/// instance's moderators.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// {
+///   "id": 2391774093313,
+///   "reporter_id": 48615849987334,
+///   "target_type": "MESSAGE",
+///   "target_id": 2391730577409,
+///   "category": "HARASSMENT",
+///   "details": "They keep telling me to stomp on lanternflies",
+///   "status": "OPEN"
+/// }
+/// ```
+#[autodoc(category = "Reports")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Report {
+    /// The report's ID.
+    pub id: u64,
+    /// The ID of the user who made the report.
+    pub reporter_id: u64,
+    /// The type of the reported target.
+    pub target_type: ReportTargetType,
+    /// The ID of the reported user, message or file.
+    pub target_id: u64,
+    /// The reason for the report.
+    pub category: ReportCategory,
+    /// More information about the report.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+    /// The report's status.
+    pub status: ReportStatus,
+    /// The ID of the moderator who resolved the report.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolved_by: Option<u64>,
+}
+
+/// The ReportCreate payload. This is used to report a user, message or file.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// {
+///   "target_type": "MESSAGE",
+///   "target_id": 2391730577409,
+///   "category": "HARASSMENT",
+///   "details": "They keep telling me to stomp on lanternflies"
+/// }
+/// ```
+#[autodoc(category = "Reports")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReportCreate {
+    /// The type of the target being reported.
+    pub target_type: ReportTargetType,
+    /// The ID of the user, message or file being reported.
+    pub target_id: u64,
+    /// The reason for the report.
+    pub category: ReportCategory,
+    /// More information about the report. This field has to be between 1 and 2000 characters
+    /// long and is required for reports in the `OTHER` category.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+}
+
+/// The ReportResolve payload. This is used by moderators to close a report.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// {
+///   "status": "ACTIONED"
+/// }
+/// ```
+#[autodoc(category = "Reports")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReportResolve {
+    /// The report's new status. This has to be either `ACTIONED` or `DISMISSED`.
+    pub status: ReportStatus,
+}
@@ -208,6 +208,14 @@ macro_rules! error {
             }
         }
     };
+    (FORBIDDEN, $message:expr) => {
+        ErrorResponse::Forbidden {
+            shared: $crate::models::SharedErrorData {
+                status: 403,
+                message: $message.to_string(),
+            }
+        }
+    };
     (NOT_FOUND) => {
         ErrorResponse::NotFound {
             shared: $crate::models::SharedErrorData {
@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+
+/// The Role payload. Roles grant [`Permissions`] to the members of a [`Community`] they're
+/// assigned to.
+///
+/// Every community has a default role sharing the community's ID which applies to all of its
+/// members, it can be edited but not deleted.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// {
+///   "id": 2385168121857,
+///   "community_id": 2384913657857,
+///   "name": "Moderators",
+///   "permissions": 48,
+///   "position": 1
+/// }
+/// ```
+#[autodoc(category = "Communities")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Role {
+    /// The role's ID.
+    pub id: u64,
+    /// The ID of the community the role belongs to.
+    pub community_id: u64,
+    /// The role's name.
+    pub name: String,
+    /// The [`Permissions`] the role grants.
+    pub permissions: u64,
+    /// The role's position in the community's role hierarchy, the default role is always at 0.
+    pub position: u32,
+}
+
+/// The RoleCreate payload. This is used to create a role in a community.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// {
+///   "name": "Moderators",
+///   "permissions": 48
+/// }
+/// ```
+#[autodoc(category = "Communities")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoleCreate {
+    /// The role's name. This field has to be between 1 and 32 characters long.
+    pub name: String,
+    /// The [`Permissions`] the role grants. Defaults to none.
+    ///
+    /// You can only grant permissions you have yourself.
+    #[serde(default)]
+    pub permissions: u64,
+}
+
+/// The UpdateRole payload. This payload is used to update a role. The abscence of a field or it
+/// being `undefined` means that it won't have an effect.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// {
+///   "permissions": 560,
+///   "position": 2
+/// }
+/// ```
+#[autodoc(category = "Communities")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UpdateRole {
+    /// The role's new name. This field follows the same rules as the [`RoleCreate`] `name` field.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// The role's new permissions. This field follows the same rules as the [`RoleCreate`]
+    /// `permissions` field.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<u64>,
+    /// The role's new position. This has to be above 0 and below your highest role.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<u32>,
+}
@@ -15,7 +15,10 @@ use serde::{Deserialize, Serialize};
 ///   "id": 2312155037697,
 ///   "user_id": 2312155693057,
 ///   "platform": "linux",
-///   "client": "pilfer"
+///   "client": "pilfer",
+///   "ip": "fc00:e10d:7150:b1gb:00b5:f00d:babe:1337",
+///   "created_at": 1691780400,
+///   "last_used_at": 1692011280
 /// }
 /// ```
 #[autodoc(category = "Sessions")]
@@ -31,6 +34,11 @@ pub struct Session {
     pub client: String,
     /// The session's creation IP address.
     pub ip: IpAddr,
+    /// The UNIX timestamp (in seconds) at which the session was created.
+    pub created_at: u64,
+    /// The UNIX timestamp (in seconds) at which the session was last used, this is updated at most
+    /// once a minute.
+    pub last_used_at: u64,
 }
 
 /// The SessionCreate payload.
@@ -60,6 +68,11 @@ pub struct SessionCreate {
     pub platform: String,
     /// The client the session was created by.
     pub client: String,
+    /// A TOTP or recovery code, this is required if the user has two-factor authentication
+    /// enabled.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub two_factor_code: Option<String>,
 }
 
 /// The response to a [`SessionCreate`].
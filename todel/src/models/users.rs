@@ -52,6 +52,8 @@ pub struct Status {
 ///   "username": "yendri",
 ///   "display_name": "Nicolas",
 ///   "social_credit": -69420,
+///   "follower_count": 12,
+///   "following_count": 3,
 ///   "status": {
 ///     "type": "BUSY",
 ///     "text": "ayúdame por favor",
@@ -75,6 +77,10 @@ pub struct User {
     pub display_name: Option<String>,
     /// The user's social credit score.
     pub social_credit: i32,
+    /// The amount of users following the user.
+    pub follower_count: u64,
+    /// The amount of users the user follows.
+    pub following_count: u64,
     /// The user's status.
     pub status: Status,
     /// The user's bio. The upper limit is the instance's [`InstanceInfo`] `bio_limit`.
@@ -88,7 +94,7 @@ pub struct User {
     pub banner: Option<u64>,
     /// The user's badges as a bitfield.
     pub badges: u64,
-    /// The user's instance-wide permissions as a bitfield.
+    /// The user's instance-wide [`Permissions`] as a bitfield.
     pub permissions: u64,
     /// The user's email. This is only shown when the user queries their own data.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -96,6 +102,10 @@ pub struct User {
     /// The user's verification status. This is only shown when the user queries their own data.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub verified: Option<bool>,
+    /// Whether the user has two-factor authentication enabled. This is only shown when the user
+    /// queries their own data.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub two_factor_auth: Option<bool>,
 }
 
 impl fmt::Display for User {
@@ -121,7 +131,8 @@ impl fmt::Display for User {
 /// {
 ///   "username": "yendri",d
 ///   "email": "yendri@llamoyendri.io",
-///   "password": "authentícame por favor" // don't actually use this as a password
+///   "password": "authentícame por favor", // don't actually use this as a password
+///   "invite_code": "tYhL3bJq"
 /// }
 /// ```
 #[autodoc(category = "Users")]
@@ -136,6 +147,11 @@ pub struct UserCreate {
     pub email: String,
     /// The user's password.
     pub password: String,
+    /// The invite code to register with, this is required if the instance's registration mode
+    /// is `INVITE_ONLY`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub invite_code: Option<String>,
 }
 
 /// The UpdateUser payload. Any field set to `null`, `undefined` or is missing will be disregarded
@@ -166,6 +182,11 @@ pub struct UpdateUser {
     /// The user's new password.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub new_password: Option<String>,
+    /// A TOTP or recovery code, this is required if the user has two-factor authentication
+    /// enabled.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub two_factor_code: Option<String>,
 }
 
 /// The UpdateUserProfile payload. This payload is used to update a user's profile. The abscence of a
@@ -284,5 +305,81 @@ pub struct ResetPassword {
 #[autodoc(category = "Users")]
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PasswordDeleteCredentials {
+    /// The user's password.
     pub password: String,
+    /// A TOTP or recovery code, this is required if the user has two-factor authentication
+    /// enabled.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub two_factor_code: Option<String>,
+}
+
+/// The TwoFactorEnrollment payload. This is returned when a user starts enabling two-factor
+/// authentication and contains the secret to add to their authenticator app.
+///
+/// Two-factor authentication is only enabled once the user confirms it with a code from their
+/// authenticator app, the secret expires after 10 minutes otherwise.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// {
+///   "secret": "JBSWY3DPEHPK3PXP",
+///   "uri": "otpauth://totp/Eludris:yendri?secret=JBSWY3DPEHPK3PXP&issuer=Eludris&algorithm=SHA1&digits=6&period=30"
+/// }
+/// ```
+#[autodoc(category = "Users")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TwoFactorEnrollment {
+    /// The base32-encoded TOTP secret.
+    pub secret: String,
+    /// An `otpauth://` URI containing the secret, usually shown as a QR code.
+    pub uri: String,
+}
+
+/// The TwoFactorConfirm payload. This is used to finish enabling two-factor authentication.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// {
+///   "password": "authentícame por favor",
+///   "code": "492039"
+/// }
+/// ```
+#[autodoc(category = "Users")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TwoFactorConfirm {
+    /// The user's password.
+    pub password: String,
+    /// The current TOTP code from the user's authenticator app.
+    pub code: String,
+}
+
+/// The TwoFactorRecoveryCodes payload. These codes can each be used once instead of a TOTP code
+/// if the user loses access to their authenticator app.
+///
+/// The codes are only shown once, Eludris only stores their hashes.
+///
+/// -----
+///
+/// ### Example
+///
+/// ```json
+/// {
+///   "recovery_codes": [
+///     "k3v9q-x7m2p",
+///     "b8n4r-t6w1z"
+///   ]
+/// }
+/// ```
+#[autodoc(category = "Users")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TwoFactorRecoveryCodes {
+    /// The recovery codes.
+    pub recovery_codes: Vec<String>,
 }